@@ -0,0 +1,28 @@
+fn main() {
+    #[cfg(feature = "ffi")]
+    generate_header();
+}
+
+/// Writes the C header for the FFI to `nail.h` in `OUT_DIR`, leaving the source tree
+/// untouched; the copy in `include` is checked against it by the ffi tests.
+#[cfg(feature = "ffi")]
+fn generate_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set");
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR not set");
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    let config = cbindgen::Config {
+        usize_is_size_t: true,
+        ..cbindgen::Config::default()
+    };
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(format!("{}/src/ffi.rs", crate_dir))
+        .with_language(cbindgen::Language::C)
+        .with_include_guard("NAIL_H")
+        .with_no_includes()
+        .with_sys_include("stddef.h")
+        .with_sys_include("stdint.h")
+        .generate()
+        .expect("Failed to generate C header")
+        .write_to_file(format!("{}/nail.h", out_dir));
+}
@@ -0,0 +1,131 @@
+#ifndef NAIL_H
+#define NAIL_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * Returned by the functions below on success.
+ */
+#define NAIL_OK 0
+
+/**
+ * Returned when an argument is null or invalid, or when conversion fails.
+ */
+#define NAIL_ERROR -1
+
+/**
+ * Conversion options, created with `nail_options_new` and released with
+ * `nail_options_free`.
+ */
+typedef struct NailOptions NailOptions;
+
+/**
+ * A conversion of a stream that arrives in chunks, created with `nail_stream_new` and
+ * released with `nail_stream_free`.
+ */
+typedef struct NailStream NailStream;
+
+/**
+ * Converted data owned by nail, to be released with `nail_buffer_free`.
+ */
+typedef struct NailBuffer {
+  uint8_t *data;
+  size_t len;
+} NailBuffer;
+
+/**
+ * Creates the default options, or returns null if they cannot be created.
+ */
+struct NailOptions *nail_options_new(void);
+
+/**
+ * Renders timestamps with a chrono strftime pattern.
+ *
+ * # Safety
+ *
+ * `options` must come from `nail_options_new`, and `format` must be a NUL-terminated
+ * string.
+ */
+int nail_options_set_format(struct NailOptions *options, const char *format);
+
+/**
+ * Renders timestamps in an IANA timezone, such as `Europe/London`.
+ *
+ * # Safety
+ *
+ * `options` must come from `nail_options_new`, and `name` must be a NUL-terminated
+ * string.
+ */
+int nail_options_set_timezone(struct NailOptions *options, const char *name);
+
+/**
+ * # Safety
+ *
+ * `options` must be null or come from `nail_options_new`, and not be used afterwards.
+ */
+void nail_options_free(struct NailOptions *options);
+
+/**
+ * Converts the `len` bytes at `buf` as a complete input, storing the result in `out`.
+ * A null `options` uses the defaults.
+ *
+ * # Safety
+ *
+ * `buf` must point to `len` readable bytes, `options` must be null or come from
+ * `nail_options_new`, and `out` must point to a writable `NailBuffer`.
+ */
+int nail_replace(const uint8_t *buf,
+                 size_t len,
+                 const struct NailOptions *options,
+                 struct NailBuffer *out);
+
+/**
+ * Returns null if the stream cannot be created.
+ *
+ * # Safety
+ *
+ * `options` must be null or come from `nail_options_new`; the stream keeps its own copy.
+ */
+struct NailStream *nail_stream_new(const struct NailOptions *options);
+
+/**
+ * Converts the next `len` bytes of the stream, storing all output that can be
+ * resolved so far in `out`.
+ *
+ * # Safety
+ *
+ * `stream` must come from `nail_stream_new`, `buf` must point to `len` readable bytes,
+ * and `out` must point to a writable `NailBuffer`.
+ */
+int nail_stream_push(struct NailStream *stream,
+                     const uint8_t *buf,
+                     size_t len,
+                     struct NailBuffer *out);
+
+/**
+ * Resolves any digits retained at the end of the stream, storing the output in `out`.
+ *
+ * # Safety
+ *
+ * `stream` must come from `nail_stream_new`, and `out` must point to a writable
+ * `NailBuffer`.
+ */
+int nail_stream_finish(struct NailStream *stream, struct NailBuffer *out);
+
+/**
+ * # Safety
+ *
+ * `stream` must be null or come from `nail_stream_new`, and not be used afterwards.
+ */
+void nail_stream_free(struct NailStream *stream);
+
+/**
+ * # Safety
+ *
+ * `buffer` must be null or point to a `NailBuffer` filled in by nail, whose data is
+ * not used afterwards.
+ */
+void nail_buffer_free(struct NailBuffer *buffer);
+
+#endif  /* NAIL_H */
@@ -0,0 +1,195 @@
+use crate::formatter::append_custom_format;
+use crate::json::string_end;
+use crate::{
+    append_epoch_timestamp, parse_epoch_value, record_timestamp, AccessLogServer, ReplacerOptions,
+    ReplacerState, ASCII_CLOSE_BRACE, ASCII_CLOSE_BRACKET, ASCII_HYPHEN, ASCII_OPEN_BRACE,
+    ASCII_OPEN_BRACKET, ASCII_PLUS, ASCII_QUOTE, ASCII_SLASH, ASCII_SPACE,
+};
+
+/// HAProxy's TCP log has the fewest timers: Tw/Tc/Tt.
+const MIN_HAPROXY_TIMERS: usize = 3;
+/// Envoy's response code, response flags, bytes received, bytes sent, duration and
+/// upstream service time follow the request.
+const ENVOY_FIELDS_AFTER_REQUEST: usize = 6;
+
+/// Converts the epoch-valued fields of a line of an access log, such as nginx's `$msec`,
+/// Apache's `%{msec}t` or HAProxy's `%Ts%ms`. Quoted strings, captured headers and the
+/// fields that `server` writes as counters or durations are written unchanged.
+pub(crate) fn replace_access_log_timestamps(
+    line: &[u8],
+    server: &AccessLogServer,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let fields = field_ranges(line);
+    let counters = counter_fields(line, &fields, server);
+
+    let mut replaced = Vec::new();
+    let mut index = 0;
+    for (field, &(start, end)) in fields.iter().enumerate() {
+        if counters.contains(&field) {
+            continue;
+        }
+        let (start, end) = match line[start] {
+            ASCII_QUOTE | ASCII_OPEN_BRACE => continue,
+            ASCII_OPEN_BRACKET if *server == AccessLogServer::Combined => continue,
+            ASCII_OPEN_BRACKET if line[end - 1] == ASCII_CLOSE_BRACKET => (start + 1, end - 1),
+            _ => (start, end),
+        };
+        let value = &line[start..end];
+        let timestamp = match parse_epoch_value(value, options) {
+            Some(timestamp) => timestamp,
+            None => continue,
+        };
+        replaced.extend_from_slice(&line[index..start]);
+        record_timestamp(&timestamp, value, start, options, state);
+        if !append_custom_format(&timestamp, value, options, &mut replaced) {
+            if options.annotate {
+                replaced.extend_from_slice(value);
+                replaced.push(ASCII_SPACE);
+            }
+            append_epoch_timestamp(&timestamp, options, state, &mut replaced);
+        }
+        index = end;
+    }
+    replaced.extend_from_slice(&line[index..]);
+
+    if let Some(&byte) = line.last() {
+        state.preceding_byte = Some(byte);
+    }
+    state.offset += line.len() as u64;
+    replaced
+}
+
+/// Returns the positions of the fields that hold counters or durations, which may be
+/// large enough to look like epoch timestamps.
+fn counter_fields(line: &[u8], fields: &[(usize, usize)], server: &AccessLogServer) -> Vec<usize> {
+    let first_field_starting_with = |delimiter: u8| {
+        fields
+            .iter()
+            .position(|&(start, _)| line[start] == delimiter)
+    };
+    match server {
+        // the status and byte count follow the bracketed time and the quoted request
+        AccessLogServer::Combined => first_field_starting_with(ASCII_OPEN_BRACKET)
+            .map_or(Vec::new(), |time_field| {
+                vec![time_field + 2, time_field + 3]
+            }),
+        // the status and byte count, or just the byte count of a TCP log, follow the
+        // slash-separated timers, which are never epoch values themselves
+        AccessLogServer::Haproxy => fields
+            .iter()
+            .position(|&(start, end)| is_haproxy_timers(&line[start..end]))
+            .map_or(Vec::new(), |timers| vec![timers + 1, timers + 2]),
+        AccessLogServer::Envoy => first_field_starting_with(ASCII_QUOTE)
+            .map_or(Vec::new(), |request| {
+                (request + 1..=request + ENVOY_FIELDS_AFTER_REQUEST).collect()
+            }),
+    }
+}
+
+/// Whether `field` is a set of HAProxy timers, such as `10/0/30/69/109`, in which a
+/// timer is -1 if its stage was not reached and the total may have a `+` prefix.
+fn is_haproxy_timers(field: &[u8]) -> bool {
+    let mut timers = field.split(|&byte| byte == ASCII_SLASH);
+    timers.clone().count() >= MIN_HAPROXY_TIMERS
+        && timers.all(|timer| {
+            let digits = timer
+                .strip_prefix(&[ASCII_PLUS])
+                .or_else(|| timer.strip_prefix(&[ASCII_HYPHEN]))
+                .unwrap_or(timer);
+            !digits.is_empty() && digits.iter().all(u8::is_ascii_digit)
+        })
+}
+
+/// Splits a line into space-separated fields, keeping quoted strings, bracketed times
+/// and braced captures, which may contain spaces, whole.
+fn field_ranges(line: &[u8]) -> Vec<(usize, usize)> {
+    let mut fields = Vec::new();
+    let mut index = 0;
+    while index < line.len() {
+        if line[index].is_ascii_whitespace() {
+            index += 1;
+            continue;
+        }
+        let start = index;
+        index = match line[index] {
+            ASCII_QUOTE => string_end(line, index),
+            ASCII_OPEN_BRACKET => group_end(line, index, ASCII_CLOSE_BRACKET),
+            ASCII_OPEN_BRACE => group_end(line, index, ASCII_CLOSE_BRACE),
+            _ => {
+                let mut end = index;
+                while end < line.len() && !line[end].is_ascii_whitespace() {
+                    end += 1;
+                }
+                end
+            }
+        };
+        fields.push((start, index));
+    }
+    fields
+}
+
+/// Returns the index just past the `close` byte that ends the group starting at `start`.
+fn group_end(line: &[u8], start: usize, close: u8) -> usize {
+    line[start..]
+        .iter()
+        .position(|&byte| byte == close)
+        .map_or(line.len(), |end| start + end + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replace_epoch_timestamps_str_with_options;
+
+    fn replace(input: &str, preset: &str) -> String {
+        let options = ReplacerOptions::new().preset(preset).unwrap();
+        replace_epoch_timestamps_str_with_options(input, &options)
+    }
+
+    #[test]
+    fn convert_msec_but_not_counters() {
+        assert_eq!(
+            "10.0.0.1 - - [28/Jun/2018:21:01:10 +0100] \"GET /?t=1530216070 HTTP/1.1\" 200 1530216070 \"-\" \"curl\" [2018-06-28 20:01:10.317 UTC] 0.012\n",
+            replace(
+                "10.0.0.1 - - [28/Jun/2018:21:01:10 +0100] \"GET /?t=1530216070 HTTP/1.1\" 200 1530216070 \"-\" \"curl\" 1530216070.317 0.012\n",
+                "nginx"
+            )
+        );
+    }
+
+    #[test]
+    fn convert_leading_apache_msec() {
+        assert_eq!(
+            "[2018-06-28 20:01:10.317 UTC] 10.0.0.1 - - [28/Jun/2018:21:01:10 +0100] \"GET / HTTP/1.1\" 304 -\n",
+            replace(
+                "1530216070317 10.0.0.1 - - [28/Jun/2018:21:01:10 +0100] \"GET / HTTP/1.1\" 304 -\n",
+                "apache"
+            )
+        );
+        assert!(ReplacerOptions::new().preset("iis").is_err());
+    }
+
+    #[test]
+    fn leave_haproxy_timers_and_counters() {
+        assert_eq!(
+            "haproxy[14389]: 10.0.1.2:33317 [28/Jun/2018:20:01:10.317] http-in static/srv1 10/0/30/69/+1530216070 200 1530216070 - - ---- 1/1/1/1/0 0/0 {1530216070} \"GET / HTTP/1.1\" [2018-06-28 20:01:10.317 UTC]\n",
+            replace(
+                "haproxy[14389]: 10.0.1.2:33317 [28/Jun/2018:20:01:10.317] http-in static/srv1 10/0/30/69/+1530216070 200 1530216070 - - ---- 1/1/1/1/0 0/0 {1530216070} \"GET / HTTP/1.1\" 1530216070317\n",
+                "haproxy"
+            )
+        );
+    }
+
+    #[test]
+    fn convert_envoy_start_time_but_not_durations() {
+        assert_eq!(
+            "[[2018-06-28 20:01:10.317 UTC]] \"GET / HTTP/1.1\" 200 - 0 1530216070 1530216070 1530216070 \"-\" \"curl\"\n",
+            replace(
+                "[1530216070317] \"GET / HTTP/1.1\" 200 - 0 1530216070 1530216070 1530216070 \"-\" \"curl\"\n",
+                "envoy"
+            )
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{ReplacerOptions, Stats, StreamingReplacer, BUFFER_SIZE};
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// The asynchronous counterpart of `DepochReader`, converting epoch timestamps in the
+/// data read through it from `inner`.
+pub struct AsyncDepochReader<R: AsyncRead + Unpin> {
+    inner: R,
+    replacer: StreamingReplacer,
+    read_buffer: Vec<u8>,
+    /// Converted data not yet returned to the caller, from `position` on.
+    converted: Vec<u8>,
+    position: usize,
+    finished: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncDepochReader<R> {
+    pub fn new(inner: R) -> AsyncDepochReader<R> {
+        AsyncDepochReader::with_options(inner, ReplacerOptions::new())
+    }
+
+    pub fn with_options(inner: R, options: ReplacerOptions) -> AsyncDepochReader<R> {
+        AsyncDepochReader {
+            inner,
+            replacer: StreamingReplacer::with_options(options),
+            read_buffer: vec![0; BUFFER_SIZE],
+            converted: Vec::new(),
+            position: 0,
+            finished: false,
+        }
+    }
+
+    /// The totals for the data read so far; the elapsed time is not set.
+    pub fn stats(&self) -> &Stats {
+        self.replacer.stats()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AsyncDepochReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let reader = self.get_mut();
+        while reader.position == reader.converted.len() && !reader.finished {
+            let mut read_buf = ReadBuf::new(&mut reader.read_buffer);
+            ready!(Pin::new(&mut reader.inner).poll_read(cx, &mut read_buf))?;
+            let bytes_read = read_buf.filled().len();
+            reader.converted = if bytes_read == 0 {
+                // trailing digits are complete at the end of the input
+                reader.finished = true;
+                reader.replacer.finish()
+            } else {
+                reader
+                    .replacer
+                    .push_chunk(&reader.read_buffer[..bytes_read])
+            };
+            reader.position = 0;
+        }
+        let available = &reader.converted[reader.position..];
+        let length = available.len().min(buf.remaining());
+        buf.put_slice(&available[..length]);
+        reader.position += length;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// The asynchronous counterpart of `DepochWriter`, converting epoch timestamps in the
+/// data written through it to `inner`. Shutting the writer down resolves a trailing
+/// digit run, in the manner of `DepochWriter::finish`.
+pub struct AsyncDepochWriter<W: AsyncWrite + Unpin> {
+    inner: W,
+    replacer: StreamingReplacer,
+    /// Converted data not yet written to `inner`, from `position` on.
+    converted: Vec<u8>,
+    position: usize,
+    finished: bool,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncDepochWriter<W> {
+    pub fn new(inner: W) -> AsyncDepochWriter<W> {
+        AsyncDepochWriter::with_options(inner, ReplacerOptions::new())
+    }
+
+    pub fn with_options(inner: W, options: ReplacerOptions) -> AsyncDepochWriter<W> {
+        AsyncDepochWriter {
+            inner,
+            replacer: StreamingReplacer::with_options(options),
+            converted: Vec::new(),
+            position: 0,
+            finished: false,
+        }
+    }
+
+    /// The totals for the data written so far; the elapsed time is not set.
+    pub fn stats(&self) -> &Stats {
+        self.replacer.stats()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Writes all converted data to `inner`.
+    fn poll_write_converted(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.position < self.converted.len() {
+            let written =
+                ready!(Pin::new(&mut self.inner).poll_write(cx, &self.converted[self.position..]))?;
+            if written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.position += written;
+        }
+        self.converted.clear();
+        self.position = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for AsyncDepochWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let writer = self.get_mut();
+        ready!(writer.poll_write_converted(cx))?;
+        writer.converted = writer.replacer.push_chunk(buf);
+        // the data has been accepted, so a pending write to `inner` is left for later
+        if let Poll::Ready(Err(error)) = writer.poll_write_converted(cx) {
+            return Poll::Ready(Err(error));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let writer = self.get_mut();
+        ready!(writer.poll_write_converted(cx))?;
+        Pin::new(&mut writer.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let writer = self.get_mut();
+        ready!(writer.poll_write_converted(cx))?;
+        if !writer.finished {
+            writer.finished = true;
+            writer.converted = writer.replacer.finish();
+            ready!(writer.poll_write_converted(cx))?;
+        }
+        Pin::new(&mut writer.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn convert_while_reading() {
+        let mut reader = AsyncDepochReader::new(&b"a 1530216070317 b\n1530216070"[..]);
+        let mut output = String::new();
+        reader
+            .read_to_string(&mut output)
+            .await
+            .expect("Failed to read");
+
+        assert_eq!(
+            "a [2018-06-28 20:01:10.317 UTC] b\n[2018-06-28 20:01:10 UTC]",
+            output
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn resolve_trailing_digits_on_shutdown() {
+        let mut writer = AsyncDepochWriter::new(Vec::new());
+        writer.write_all(b"a 15302").await.expect("Failed to write");
+        writer
+            .write_all(b"16070317 b 1530216070")
+            .await
+            .expect("Failed to write");
+        writer.shutdown().await.expect("Failed to shut down");
+
+        assert_eq!(
+            "a [2018-06-28 20:01:10.317 UTC] b [2018-06-28 20:01:10 UTC]",
+            String::from_utf8(writer.into_inner()).unwrap()
+        );
+    }
+}
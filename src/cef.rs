@@ -0,0 +1,182 @@
+use crate::csv::line_content_end;
+use crate::formatter::append_custom_format;
+use crate::{
+    append_epoch_timestamp, parse_epoch_value, record_timestamp, replace_epoch_timestamps_in_state,
+    ReplacerOptions, ReplacerState, ASCII_BACKSLASH, ASCII_EQUALS, ASCII_SPACE, ASCII_TAB,
+};
+
+const ASCII_PIPE: u8 = 124;
+const CEF_PREFIX: &str = "CEF:";
+const LEEF_PREFIX: &str = "LEEF:";
+/// LEEF 2.0 declares the delimiter of its attributes in an extra header field.
+const LEEF_2_PREFIX: &str = "LEEF:2.0|";
+/// Version, vendor, product, product version, signature id, name and severity.
+const CEF_HEADER_FIELDS: usize = 7;
+/// Version, vendor, product, product version and event id.
+const LEEF_HEADER_FIELDS: usize = 5;
+/// The keys whose values are event times, in milliseconds since the epoch.
+const TIME_KEYS: &[&str] = &["rt", "start", "end", "devTime"];
+
+/// Converts the epoch values of the time extensions of an ArcSight CEF or QRadar LEEF
+/// event, such as `rt=1530216070317`, by key. The header, which may follow a syslog
+/// prefix, and every other extension are written unchanged. Lines that are not CEF or
+/// LEEF events are treated as free-form text.
+pub(crate) fn replace_cef_timestamps(
+    line: &[u8],
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let content_end = line_content_end(line);
+    let (extension_start, delimiter) = match extension_start(&line[..content_end]) {
+        Some(extension) => extension,
+        None => return replace_epoch_timestamps_in_state(line, true, options, state).data,
+    };
+
+    let mut replaced = Vec::new();
+    let mut index = 0;
+    for (key_start, equals, value_end) in
+        extension_pairs(&line[..content_end], extension_start, delimiter)
+    {
+        let key = &line[key_start..equals];
+        if !TIME_KEYS.iter().any(|name| name.as_bytes() == key) {
+            continue;
+        }
+        let value = &line[equals + 1..value_end];
+        let timestamp = match parse_epoch_value(value, options) {
+            Some(timestamp) => timestamp,
+            None => continue,
+        };
+        replaced.extend_from_slice(&line[index..equals + 1]);
+        record_timestamp(&timestamp, value, equals + 1, options, state);
+        if !append_custom_format(&timestamp, value, options, &mut replaced) {
+            if options.annotate {
+                replaced.extend_from_slice(value);
+                replaced.push(ASCII_SPACE);
+            }
+            append_epoch_timestamp(&timestamp, options, state, &mut replaced);
+        }
+        index = value_end;
+    }
+    replaced.extend_from_slice(&line[index..]);
+
+    if let Some(&byte) = line.last() {
+        state.preceding_byte = Some(byte);
+    }
+    state.offset += line.len() as u64;
+    replaced
+}
+
+/// Returns the index just past the header of a CEF or LEEF event, along with the
+/// delimiter of its attributes, which is `None` for CEF's space-separated extensions.
+fn extension_start(line: &[u8]) -> Option<(usize, Option<u8>)> {
+    let (header_start, header_fields) = match find(line, CEF_PREFIX) {
+        Some(start) => (start, CEF_HEADER_FIELDS),
+        None => (find(line, LEEF_PREFIX)?, LEEF_HEADER_FIELDS),
+    };
+    let is_leef_2 = line[header_start..].starts_with(LEEF_2_PREFIX.as_bytes());
+    let mut index = header_start;
+    for _ in 0..header_fields {
+        index += unescaped_position(&line[index..], ASCII_PIPE)? + 1;
+    }
+    if header_fields == CEF_HEADER_FIELDS {
+        return Some((index, None));
+    }
+    if !is_leef_2 {
+        return Some((index, Some(ASCII_TAB)));
+    }
+    let delimiter_end = index + unescaped_position(&line[index..], ASCII_PIPE)?;
+    Some((
+        delimiter_end + 1,
+        Some(leef_delimiter(&line[index..delimiter_end])),
+    ))
+}
+
+/// Parses the delimiter field of a LEEF 2.0 header: a single character, or its code in
+/// hexadecimal such as `x09` or `0x5E`. A missing delimiter defaults to a tab.
+fn leef_delimiter(field: &[u8]) -> u8 {
+    if field.len() == 1 {
+        return field[0];
+    }
+    let hex = field
+        .strip_prefix("0x".as_bytes())
+        .or_else(|| field.strip_prefix("x".as_bytes()))
+        .and_then(|hex| std::str::from_utf8(hex).ok())
+        .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+    hex.unwrap_or(ASCII_TAB)
+}
+
+/// Returns the key start, `=` position and value end of each `key=value` extension.
+/// A value runs until the separator before the next key, since CEF values may contain
+/// spaces; LEEF attributes are separated by `delimiter` instead.
+fn extension_pairs(line: &[u8], start: usize, delimiter: Option<u8>) -> Vec<(usize, usize, usize)> {
+    let separator = delimiter.unwrap_or(ASCII_SPACE);
+    let mut pairs: Vec<(usize, usize, usize)> = Vec::new();
+    let mut index = start;
+    while let Some(equals) = unescaped_position(&line[index..], ASCII_EQUALS) {
+        let equals = index + equals;
+        let key_start = line[index..equals]
+            .iter()
+            .rposition(|&byte| byte == separator)
+            .map_or(index, |position| index + position + 1);
+        if let Some(previous) = pairs.last_mut() {
+            previous.2 = (key_start - 1).max(previous.1 + 1);
+        }
+        pairs.push((key_start, equals, line.len()));
+        index = equals + 1;
+    }
+    pairs
+}
+
+/// Returns the position of the first `byte` in `bytes` that is not escaped by a backslash.
+fn unescaped_position(bytes: &[u8], byte: u8) -> Option<usize> {
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == ASCII_BACKSLASH {
+            index += 2;
+            continue;
+        }
+        if bytes[index] == byte {
+            return Some(index);
+        }
+        index += 1;
+    }
+    None
+}
+
+fn find(line: &[u8], prefix: &str) -> Option<usize> {
+    line.windows(prefix.len())
+        .position(|window| window == prefix.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{replace_epoch_timestamps_str_with_options, InputFormat};
+
+    fn replace(input: &str) -> String {
+        let options = ReplacerOptions::new().input_format(InputFormat::Cef);
+        replace_epoch_timestamps_str_with_options(input, &options)
+    }
+
+    #[test]
+    fn convert_cef_time_extensions() {
+        assert_eq!(
+            "Jun 28 20:01:10 host CEF:0|Acme|IDS|1.0|1530216070|Port scan|5|rt=[2018-06-28 20:01:10.317 UTC] msg=seen 1530216070317 times cnt=1530216070317 end=[2018-06-28 20:01:10.317 UTC]\n",
+            replace(
+                "Jun 28 20:01:10 host CEF:0|Acme|IDS|1.0|1530216070|Port scan|5|rt=1530216070317 msg=seen 1530216070317 times cnt=1530216070317 end=1530216070317\n"
+            )
+        );
+    }
+
+    #[test]
+    fn convert_leef_time_attributes() {
+        assert_eq!(
+            "LEEF:1.0|Acme|IDS|1.0|scan|devTime=[2018-06-28 20:01:10.317 UTC]\tsrcBytes=1530216070317\n\
+             LEEF:2.0|Acme|IDS|1.0|scan|^|srcBytes=1530216070317^devTime=[2018-06-28 20:01:10.317 UTC]\n",
+            replace(
+                "LEEF:1.0|Acme|IDS|1.0|scan|devTime=1530216070317\tsrcBytes=1530216070317\n\
+                 LEEF:2.0|Acme|IDS|1.0|scan|^|srcBytes=1530216070317^devTime=1530216070317\n"
+            )
+        );
+    }
+}
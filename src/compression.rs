@@ -0,0 +1,221 @@
+use flate2::read::MultiGzDecoder;
+#[cfg(feature = "fs")]
+use flate2::write::GzEncoder;
+#[cfg(feature = "fs")]
+use std::io::Write;
+use std::io::{BufRead, BufReader, Error, Read};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const XZ_MAGIC: &[u8] = &[0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00];
+#[cfg(all(feature = "fs", feature = "xz"))]
+const XZ_PRESET: u32 = 6;
+
+/// The compression format of an input, which may also be applied to its output.
+/// zstd and xz are only supported when nail is built with the `zstd` and `xz`
+/// features respectively; otherwise they are detected but reported as unsupported.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    fn detect(header: &[u8]) -> Compression {
+        if header.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if header.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else if header.starts_with(XZ_MAGIC) {
+            Compression::Xz
+        } else {
+            Compression::None
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip => ".gz",
+            Compression::Zstd => ".zst",
+            Compression::Xz => ".xz",
+        }
+    }
+
+    fn unsupported(self) -> Error {
+        Error::other(format!(
+            "{:?} compression is not supported by this build of nail",
+            self
+        ))
+    }
+}
+
+/// Wraps `input` so that compressed data is decompressed as it is read, detecting
+/// the format from its leading magic bytes.
+pub(crate) fn open_input<'a, R: Read + 'a>(
+    input: R,
+) -> std::io::Result<(Box<dyn Read + 'a>, Compression)> {
+    let mut reader = BufReader::new(input);
+    let compression = Compression::detect(reader.fill_buf()?);
+    let decompressed: Box<dyn Read + 'a> = match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(MultiGzDecoder::new(reader)),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::with_buffer(reader)?),
+        #[cfg(feature = "xz")]
+        Compression::Xz => Box::new(xz2::bufread::XzDecoder::new_multi_decoder(reader)),
+        #[allow(unreachable_patterns)]
+        unsupported => return Err(unsupported.unsupported()),
+    };
+    Ok((decompressed, compression))
+}
+
+/// Removes the extension of `compression` from `file_name`, so `app.log.1.gz` becomes
+/// `app.log.1`.
+#[cfg(feature = "fs")]
+pub(crate) fn strip_extension(file_name: &str, compression: Compression) -> &str {
+    file_name
+        .strip_suffix(compression.extension())
+        .filter(|stripped| !stripped.is_empty())
+        .unwrap_or(file_name)
+}
+
+/// A writer that compresses its output in the given format.
+#[cfg(feature = "fs")]
+pub(crate) enum CompressedWriter<W: Write> {
+    Plain(W),
+    Gzip(GzEncoder<W>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+    #[cfg(feature = "xz")]
+    Xz(xz2::write::XzEncoder<W>),
+}
+
+#[cfg(feature = "fs")]
+impl<W: Write> CompressedWriter<W> {
+    pub(crate) fn new(output: W, compression: Compression) -> std::io::Result<CompressedWriter<W>> {
+        Ok(match compression {
+            Compression::None => CompressedWriter::Plain(output),
+            Compression::Gzip => {
+                CompressedWriter::Gzip(GzEncoder::new(output, flate2::Compression::default()))
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                CompressedWriter::Zstd(zstd::stream::write::Encoder::new(output, 0)?)
+            }
+            #[cfg(feature = "xz")]
+            Compression::Xz => CompressedWriter::Xz(xz2::write::XzEncoder::new(output, XZ_PRESET)),
+            #[allow(unreachable_patterns)]
+            unsupported => return Err(unsupported.unsupported()),
+        })
+    }
+
+    /// Writes any trailing compressed data, returning the underlying writer.
+    pub(crate) fn finish(self) -> std::io::Result<W> {
+        match self {
+            CompressedWriter::Plain(output) => Ok(output),
+            CompressedWriter::Gzip(encoder) => encoder.finish(),
+            #[cfg(feature = "zstd")]
+            CompressedWriter::Zstd(encoder) => encoder.finish(),
+            #[cfg(feature = "xz")]
+            CompressedWriter::Xz(encoder) => encoder.finish(),
+        }
+    }
+}
+
+#[cfg(feature = "fs")]
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            CompressedWriter::Plain(output) => output.write(buf),
+            CompressedWriter::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "zstd")]
+            CompressedWriter::Zstd(encoder) => encoder.write(buf),
+            #[cfg(feature = "xz")]
+            CompressedWriter::Xz(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            CompressedWriter::Plain(output) => output.flush(),
+            CompressedWriter::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "zstd")]
+            CompressedWriter::Zstd(encoder) => encoder.flush(),
+            #[cfg(feature = "xz")]
+            CompressedWriter::Xz(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "fs")]
+    fn round_trip(compression: Compression) {
+        let mut writer = CompressedWriter::new(Vec::new(), compression).unwrap();
+        writer.write_all("some log data".as_bytes()).unwrap();
+        let compressed = writer.finish().unwrap();
+
+        let (mut reader, detected) = open_input(compressed.as_slice()).unwrap();
+        let mut decompressed = String::new();
+        reader.read_to_string(&mut decompressed).unwrap();
+
+        assert_eq!(compression, detected);
+        assert_eq!("some log data", decompressed);
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn round_trip_gzip_data() {
+        round_trip(Compression::Gzip);
+    }
+
+    #[cfg(all(feature = "fs", feature = "zstd"))]
+    #[test]
+    fn round_trip_zstd_data() {
+        round_trip(Compression::Zstd);
+    }
+
+    #[cfg(all(feature = "fs", feature = "xz"))]
+    #[test]
+    fn round_trip_xz_data() {
+        round_trip(Compression::Xz);
+    }
+
+    #[cfg(all(feature = "fs", not(feature = "zstd")))]
+    #[test]
+    fn report_unsupported_compression() {
+        let zstd_frame = [0x28, 0xb5, 0x2f, 0xfd, 0x00];
+
+        assert!(open_input(&zstd_frame[..]).is_err());
+        assert!(CompressedWriter::new(Vec::new(), Compression::Zstd).is_err());
+    }
+
+    #[test]
+    fn pass_through_uncompressed_data() {
+        let (mut reader, compression) = open_input("plain".as_bytes()).unwrap();
+        let mut data = String::new();
+        reader.read_to_string(&mut data).unwrap();
+
+        assert_eq!(Compression::None, compression);
+        assert_eq!("plain", data);
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn strip_compression_extension() {
+        assert_eq!(
+            "app.log.1",
+            strip_extension("app.log.1.gz", Compression::Gzip)
+        );
+        assert_eq!("app.log", strip_extension("app.log", Compression::Gzip));
+        assert_eq!(
+            "app.log.gz",
+            strip_extension("app.log.gz", Compression::None)
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{
+    byte_before, follows_prefix, ReplacerOptions, ReplacerState, TimestampKind, ASCII_ZERO,
+    DEFAULT_IDENTIFIER_KEY_PREFIXES, DEFAULT_KEY_PREFIXES,
+};
+use chrono::{DateTime, Utc};
+
+const ASCII_UNDERSCORE: u8 = 95;
+/// The highest confidence, of a candidate that is certainly a timestamp.
+pub(crate) const MAX_CONFIDENCE: u8 = 100;
+/// Ten digits are also common as counters, sizes and phone numbers.
+const SECONDS_CONFIDENCE: i32 = 40;
+const LONGER_DIGITS_CONFIDENCE: i32 = 60;
+/// The attribute name of an LDAP timestamp has already been seen.
+const LDAP_CONFIDENCE: i32 = 90;
+const IDENTIFIER_CONFIDENCE: i32 = 50;
+const TIME_KEY_BONUS: i32 = 30;
+const IDENTIFIER_KEY_PENALTY: i32 = 40;
+const DELIMITED_BONUS: i32 = 10;
+/// A letter or underscore alongside suggests part of a name or identifier.
+const ADJOINING_WORD_PENALTY: i32 = 30;
+const PAST_BONUS: i32 = 10;
+/// Seconds ending in this many zeros are more often limits or round numbers.
+const ROUND_SECONDS_ZEROS: usize = 4;
+const ROUND_SECONDS_PENALTY: i32 = 20;
+
+/// Scores how likely the candidate of `length` bytes at `start` is to be a timestamp
+/// rather than some other number, out of `MAX_CONFIDENCE`, from the number of digits,
+/// the characters around it, the key before it and whether it is in the past.
+pub(crate) fn confidence(
+    input: &[u8],
+    start: usize,
+    length: usize,
+    kind: TimestampKind,
+    date_time: DateTime<Utc>,
+    options: &ReplacerOptions,
+    state: &ReplacerState,
+) -> u8 {
+    let mut score = match kind {
+        TimestampKind::Seconds => SECONDS_CONFIDENCE,
+        TimestampKind::Ldap => LDAP_CONFIDENCE,
+        kind if kind.is_identifier() => IDENTIFIER_CONFIDENCE,
+        _ => LONGER_DIGITS_CONFIDENCE,
+    };
+
+    let is_time_keyed = follows_prefix(input, start, DEFAULT_KEY_PREFIXES, state)
+        || options
+            .key_prefixes
+            .as_ref()
+            .is_some_and(|prefixes| follows_prefix(input, start, prefixes, state));
+    if is_time_keyed {
+        score += TIME_KEY_BONUS;
+    } else if follows_prefix(input, start, DEFAULT_IDENTIFIER_KEY_PREFIXES, state) {
+        score -= IDENTIFIER_KEY_PENALTY;
+    }
+
+    let is_word = |byte: Option<u8>| {
+        byte.is_some_and(|byte| byte.is_ascii_alphabetic() || byte == ASCII_UNDERSCORE)
+    };
+    let preceding = byte_before(input, start, 1, state);
+    let following = input.get(start + length).copied();
+    let is_delimited = |byte: Option<u8>| {
+        byte.is_none_or(|byte| byte.is_ascii_whitespace() || byte.is_ascii_punctuation())
+    };
+    if is_word(preceding) || is_word(following) {
+        score -= ADJOINING_WORD_PENALTY;
+    } else if is_delimited(preceding) && is_delimited(following) {
+        score += DELIMITED_BONUS;
+    }
+
+    if date_time <= Utc::now() {
+        score += PAST_BONUS;
+    }
+    let candidate = &input[start..start + length];
+    let is_round =
+        kind == TimestampKind::Seconds && candidate.ends_with(&[ASCII_ZERO; ROUND_SECONDS_ZEROS]);
+    if is_round {
+        score -= ROUND_SECONDS_PENALTY;
+    }
+    score.clamp(0, i32::from(MAX_CONFIDENCE)) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_epoch_timestamps, StreamingReplacer};
+
+    #[test]
+    fn score_candidates_by_their_surroundings() {
+        let input = b"ts=1530216070 1530216070 id=1530216070 C1530216070 1530220000";
+        let scores: Vec<u8> = find_epoch_timestamps(input)
+            .map(|found| found.confidence)
+            .collect();
+
+        assert_eq!(vec![90, 60, 20, 20, 40], scores);
+    }
+
+    #[test]
+    fn replace_only_candidates_above_the_threshold() {
+        let options = ReplacerOptions::new().min_confidence(50);
+        let mut replacer = StreamingReplacer::with_options(options);
+        let mut output = replacer.push_chunk("order_id=1530216070 at 1530216070 t".as_bytes());
+        output.extend(replacer.push_chunk("s=1530216070317".as_bytes()));
+        output.extend(replacer.finish());
+
+        assert_eq!(
+            "order_id=1530216070 at [2018-06-28 20:01:10 UTC] ts=[2018-06-28 20:01:10.317 UTC]",
+            String::from_utf8(output).unwrap()
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use crate::formatter::append_custom_format;
+use crate::{
+    parse_epoch_value, record_timestamp, render_timestamp, CsvColumn, ReplacerOptions,
+    ReplacerState, ANSI_HIGHLIGHT, ANSI_RESET, ASCII_COMMA, ASCII_NEWLINE, ASCII_QUOTE,
+};
+
+const ASCII_CARRIAGE_RETURN: u8 = 13;
+
+/// Progress through a CSV stream, carried from one line to the next.
+#[derive(Default)]
+pub(crate) struct CsvState {
+    /// The zero-based indices of the columns to convert, once known.
+    selected: Option<Vec<usize>>,
+    /// The index of the field that the next line starts in.
+    field: usize,
+    /// Whether the next line continues a quoted field that contains a line break.
+    in_quotes: bool,
+}
+
+/// Converts the fields of a line of CSV that are in one of `columns` and consist
+/// entirely of an epoch value, which may be quoted. When any column is selected by name,
+/// the first line is taken to be the header and is written unchanged. A converted value
+/// is quoted if it was quoted in the input or contains characters that require it.
+pub(crate) fn replace_csv_timestamps(
+    line: &[u8],
+    columns: &[CsvColumn],
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let mut replaced = Vec::new();
+    if state.csv.selected.is_none() {
+        let has_header = columns
+            .iter()
+            .any(|column| matches!(column, CsvColumn::Name(_)));
+        state.csv.selected = Some(select_columns(columns, has_header.then_some(line)));
+        if has_header {
+            replaced.extend_from_slice(line);
+            state.offset += line.len() as u64;
+            return replaced;
+        }
+    }
+
+    let content_end = line_content_end(line);
+    let mut field_start = 0;
+    // a field continued from the previous line is never converted
+    let mut continued = state.csv.in_quotes;
+    let mut in_quotes = state.csv.in_quotes;
+    for index in 0..=content_end {
+        let at_field_end = index == content_end || (!in_quotes && line[index] == ASCII_COMMA);
+        if !at_field_end {
+            if line[index] == ASCII_QUOTE {
+                in_quotes = !in_quotes;
+            }
+            continue;
+        }
+        if index == content_end && in_quotes {
+            break;
+        }
+        let field = &line[field_start..index];
+        if !continued && is_selected(columns, state, state.csv.field) {
+            append_field(field, field_start, options, state, &mut replaced);
+        } else {
+            replaced.extend_from_slice(field);
+        }
+        if index < content_end {
+            replaced.push(ASCII_COMMA);
+            state.csv.field += 1;
+        }
+        field_start = index + 1;
+        continued = false;
+    }
+    if in_quotes {
+        replaced.extend_from_slice(&line[field_start.min(line.len())..]);
+    } else {
+        replaced.extend_from_slice(&line[content_end..]);
+        state.csv.field = 0;
+    }
+    state.csv.in_quotes = in_quotes;
+
+    if let Some(&byte) = line.last() {
+        state.preceding_byte = Some(byte);
+    }
+    state.offset += line.len() as u64;
+    replaced
+}
+
+/// An empty selection converts every column.
+fn is_selected(columns: &[CsvColumn], state: &ReplacerState, field: usize) -> bool {
+    columns.is_empty()
+        || state
+            .csv
+            .selected
+            .as_ref()
+            .is_some_and(|selected| selected.contains(&field))
+}
+
+fn select_columns(columns: &[CsvColumn], header: Option<&[u8]>) -> Vec<usize> {
+    let names: Vec<Vec<u8>> = header
+        .map(|header| {
+            header[..line_content_end(header)]
+                .split(|&byte| byte == ASCII_COMMA)
+                .map(unquote)
+                .collect()
+        })
+        .unwrap_or_default();
+    columns
+        .iter()
+        .filter_map(|column| match column {
+            CsvColumn::Index(index) => index.checked_sub(1),
+            CsvColumn::Name(name) => names.iter().position(|field| field == name.as_bytes()),
+        })
+        .collect()
+}
+
+fn unquote(field: &[u8]) -> Vec<u8> {
+    match field.strip_prefix(&[ASCII_QUOTE]) {
+        Some(quoted) => {
+            let inner = quoted.strip_suffix(&[ASCII_QUOTE]).unwrap_or(quoted);
+            let mut unquoted = Vec::new();
+            let mut index = 0;
+            while index < inner.len() {
+                unquoted.push(inner[index]);
+                // a doubled quote is an escaped quote
+                index += if inner[index] == ASCII_QUOTE { 2 } else { 1 };
+            }
+            unquoted
+        }
+        None => field.to_vec(),
+    }
+}
+
+/// Returns the index of the line break at the end of `line`, or its length if it has none.
+pub(crate) fn line_content_end(line: &[u8]) -> usize {
+    let mut end = line.len();
+    if end > 0 && line[end - 1] == ASCII_NEWLINE {
+        end -= 1;
+        if end > 0 && line[end - 1] == ASCII_CARRIAGE_RETURN {
+            end -= 1;
+        }
+    }
+    end
+}
+
+fn append_field(
+    field: &[u8],
+    field_start: usize,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+    append_buffer: &mut Vec<u8>,
+) {
+    let quoted =
+        field.len() >= 2 && field[0] == ASCII_QUOTE && field[field.len() - 1] == ASCII_QUOTE;
+    let value = if quoted {
+        &field[1..field.len() - 1]
+    } else {
+        field
+    };
+    let timestamp = match parse_epoch_value(value, options) {
+        Some(timestamp) => timestamp,
+        None => {
+            append_buffer.extend_from_slice(field);
+            return;
+        }
+    };
+    let value_start = field_start + if quoted { 1 } else { 0 };
+    record_timestamp(&timestamp, value, value_start, options, state);
+    if append_custom_format(&timestamp, value, options, append_buffer) {
+        return;
+    }
+    let rendered = render_timestamp(&timestamp, options, state);
+    let needs_quotes = quoted
+        || rendered.bytes().any(|byte| {
+            [
+                ASCII_COMMA,
+                ASCII_QUOTE,
+                ASCII_NEWLINE,
+                ASCII_CARRIAGE_RETURN,
+            ]
+            .contains(&byte)
+        });
+    if options.color {
+        append_buffer.extend_from_slice(ANSI_HIGHLIGHT);
+    }
+    if needs_quotes {
+        append_buffer.push(ASCII_QUOTE);
+        append_buffer.extend_from_slice(rendered.replace('"', "\"\"").as_bytes());
+        append_buffer.push(ASCII_QUOTE);
+    } else {
+        append_buffer.extend_from_slice(rendered.as_bytes());
+    }
+    if options.color {
+        append_buffer.extend_from_slice(ANSI_RESET);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{replace_epoch_timestamps_str_with_options, InputFormat};
+
+    fn replace(input: &str, columns: Vec<CsvColumn>, options: ReplacerOptions) -> String {
+        replace_epoch_timestamps_str_with_options(
+            input,
+            &options.input_format(InputFormat::Csv { columns }),
+        )
+    }
+
+    #[test]
+    fn convert_columns_selected_by_index() {
+        assert_eq!(
+            "1530216070,[2018-06-28 20:01:10 UTC],\"[2018-06-28 20:01:10.317 UTC]\"\r\n\
+             1530216070,x 1530216070,\r\n",
+            replace(
+                "1530216070,1530216070,\"1530216070317\"\r\n1530216070,x 1530216070,\r\n",
+                vec![CsvColumn::Index(2), CsvColumn::Index(3)],
+                ReplacerOptions::new()
+            )
+        );
+    }
+
+    #[test]
+    fn convert_columns_selected_by_header_name() {
+        assert_eq!(
+            "id,\"created at\",note\n\
+             1530216070,[2018-06-28 20:01:10 UTC],\"a, \"\"quoted\"\"\n1530216070 note\"\n\
+             42,[2018-06-28 20:01:10 UTC],1530216070\n",
+            replace(
+                "id,\"created at\",note\n\
+                 1530216070,1530216070,\"a, \"\"quoted\"\"\n1530216070 note\"\n\
+                 42,1530216070,1530216070\n",
+                vec![CsvColumn::Name("created at".to_string())],
+                ReplacerOptions::new()
+            )
+        );
+    }
+
+    #[test]
+    fn quote_rendered_values_containing_commas() {
+        assert_eq!(
+            "\"Jun 28, 2018\",7",
+            replace(
+                "1530216070,7",
+                Vec::new(),
+                ReplacerOptions::new()
+                    .format("%b %d, %Y")
+                    .unwrap()
+                    .prefix("")
+                    .suffix("")
+            )
+        );
+    }
+}
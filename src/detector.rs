@@ -0,0 +1,157 @@
+use crate::formatter::append_custom_format;
+use crate::{
+    append_epoch_timestamp, record_timestamp, replace_epoch_timestamps_in_state, EpochTimestamp,
+    ReplacerOptions, ReplacerState, ASCII_SPACE, MAX_FRACTIONAL_DIGITS,
+};
+use chrono::prelude::*;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Finds timestamps in a format that nail does not recognise itself. Detectors are
+/// given one line of free-form text at a time, including its line break.
+pub trait TimestampDetector: Send + Sync {
+    fn scan(&self, line: &[u8]) -> Vec<DetectedTimestamp>;
+}
+
+/// A timestamp found by a `TimestampDetector`, rendered in place of the bytes in `range`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DetectedTimestamp {
+    pub range: Range<usize>,
+    pub date_time: DateTime<Utc>,
+    /// The number of sub-second digits to render, from 0 to 9.
+    pub fractional_digits: usize,
+}
+
+/// The detectors that run before nail's own epoch scanning, in order of precedence.
+#[derive(Clone, Default)]
+pub(crate) struct DetectorChain(Vec<Arc<dyn TimestampDetector>>);
+
+impl DetectorChain {
+    pub(crate) fn push(&mut self, detector: Arc<dyn TimestampDetector>) {
+        self.0.push(detector);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Runs each detector over `line`, discarding any match that overlaps a match from
+    /// an earlier detector.
+    fn scan(&self, line: &[u8]) -> Vec<DetectedTimestamp> {
+        let mut detected: Vec<DetectedTimestamp> = Vec::new();
+        for detector in &self.0 {
+            for candidate in detector.scan(line) {
+                let overlaps = detected.iter().any(|kept| {
+                    candidate.range.start < kept.range.end && kept.range.start < candidate.range.end
+                });
+                if !overlaps && candidate.range.end <= line.len() {
+                    detected.push(candidate);
+                }
+            }
+        }
+        detected.sort_by_key(|timestamp| timestamp.range.start);
+        detected
+    }
+}
+
+impl fmt::Debug for DetectorChain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DetectorChain({} detectors)", self.0.len())
+    }
+}
+
+impl PartialEq for DetectorChain {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.0.iter().zip(&other.0).all(|(a, b)| Arc::ptr_eq(a, b))
+    }
+}
+
+/// Converts the timestamps found by the configured detectors in `line`, and any epoch
+/// timestamps in the text between them.
+pub(crate) fn replace_detected_timestamps(
+    line: &[u8],
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let mut replaced = Vec::new();
+    let mut index = 0;
+    for detected in options.detectors.scan(line) {
+        let gap = &line[index..detected.range.start];
+        replaced.extend(replace_epoch_timestamps_in_state(gap, true, options, state).data);
+
+        let original = &line[detected.range.clone()];
+        let timestamp = EpochTimestamp {
+            seconds: detected.date_time.timestamp(),
+            nanos: detected.date_time.timestamp_subsec_nanos(),
+            fractional_digits: detected.fractional_digits.min(MAX_FRACTIONAL_DIGITS),
+            offset: None,
+        };
+        record_timestamp(&timestamp, original, 0, options, state);
+        if !append_custom_format(&timestamp, original, options, &mut replaced) {
+            if options.annotate {
+                replaced.extend_from_slice(original);
+                replaced.push(ASCII_SPACE);
+            }
+            append_epoch_timestamp(&timestamp, options, state, &mut replaced);
+        }
+        if let Some(&byte) = original.last() {
+            state.preceding_byte = Some(byte);
+        }
+        state.offset += original.len() as u64;
+        index = detected.range.end;
+    }
+    replaced.extend(replace_epoch_timestamps_in_state(&line[index..], true, options, state).data);
+    replaced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StreamingReplacer;
+
+    /// Detects compact `YYYYMMDDHHMMSS` datetimes.
+    struct CompactDetector;
+
+    impl TimestampDetector for CompactDetector {
+        fn scan(&self, line: &[u8]) -> Vec<DetectedTimestamp> {
+            let text = String::from_utf8_lossy(line);
+            text.split(' ')
+                .scan(0, |start, word| {
+                    let range = *start..*start + word.len();
+                    *start += word.len() + 1;
+                    Some((range, word))
+                })
+                .filter_map(|(range, word)| {
+                    let date_time = NaiveDateTime::parse_from_str(word, "%Y%m%d%H%M%S").ok()?;
+                    Some(DetectedTimestamp {
+                        range,
+                        date_time: Utc.from_utc_datetime(&date_time),
+                        fractional_digits: 0,
+                    })
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn convert_detected_and_epoch_timestamps() {
+        let options = ReplacerOptions::new()
+            .detector(Arc::new(CompactDetector))
+            .annotate(true);
+        let mut replacer = StreamingReplacer::with_options(options).recording_matches();
+        let mut output = replacer.push_chunk("a 20180628200110 b 1530216070 c\n".as_bytes());
+        output.extend(replacer.finish());
+
+        assert_eq!(
+            "a 20180628200110 [2018-06-28 20:01:10 UTC] b 1530216070 [2018-06-28 20:01:10 UTC] c\n",
+            String::from_utf8_lossy(&output)
+        );
+        let offsets: Vec<u64> = replacer
+            .take_matches()
+            .iter()
+            .map(|timestamp_match| timestamp_match.offset)
+            .collect();
+        assert_eq!(vec![2, 19], offsets);
+    }
+}
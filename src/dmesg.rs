@@ -0,0 +1,91 @@
+use crate::{
+    anchored_timestamp, is_plausible, replace_line_timestamp, ReplacerOptions, ReplacerState,
+    ASCII_CLOSE_BRACKET, ASCII_OPEN_BRACKET,
+};
+#[cfg(feature = "fs")]
+use chrono::TimeZone;
+use chrono::{DateTime, Utc};
+#[cfg(feature = "fs")]
+use std::fs::read_to_string;
+
+#[cfg(feature = "fs")]
+const PROC_STAT: &str = "/proc/stat";
+/// The line of `/proc/stat` that holds the boot time, in seconds since the epoch.
+#[cfg(feature = "fs")]
+const BOOT_TIME_PREFIX: &str = "btime ";
+
+/// Returns the time at which this system booted, as recorded by the kernel in
+/// `/proc/stat`.
+#[cfg(feature = "fs")]
+pub fn system_boot_time() -> Result<DateTime<Utc>, String> {
+    let stat = read_to_string(PROC_STAT)
+        .map_err(|error| format!("Unable to read {}: {}", PROC_STAT, error))?;
+    stat.lines()
+        .find_map(|line| line.strip_prefix(BOOT_TIME_PREFIX))
+        .and_then(|seconds| seconds.trim().parse().ok())
+        .and_then(|seconds| Utc.timestamp_opt(seconds, 0).single())
+        .ok_or_else(|| format!("No boot time in {}", PROC_STAT))
+}
+
+/// Converts the seconds since boot that start a line of dmesg output, such as
+/// `[12345.678901]`, including after a `<6>` priority or a `kern  :info  :` prefix. The
+/// bracketed field is replaced as a whole, much like `dmesg -T`, and the message is
+/// written unchanged.
+pub(crate) fn replace_dmesg_timestamps(
+    line: &[u8],
+    boot_time: &DateTime<Utc>,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let converted = uptime_range(line).and_then(|(start, end)| {
+        let uptime = line[start + 1..end - 1].trim_ascii_start();
+        let timestamp = anchored_timestamp(boot_time, uptime)
+            .filter(|timestamp| is_plausible(timestamp, options))?;
+        Some((start, end, timestamp))
+    });
+    replace_line_timestamp(line, converted, options, state)
+}
+
+/// Returns the range of the first bracketed field of `line`, including its brackets.
+fn uptime_range(line: &[u8]) -> Option<(usize, usize)> {
+    let open = line.iter().position(|&byte| byte == ASCII_OPEN_BRACKET)?;
+    let close = open
+        + line[open..]
+            .iter()
+            .position(|&byte| byte == ASCII_CLOSE_BRACKET)?;
+    Some((open, close + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{replace_epoch_timestamps_str_with_options, InputFormat};
+    use chrono::TimeZone;
+
+    fn replace(input: &str) -> String {
+        let boot_time = Utc.timestamp_opt(1530216070, 0).unwrap();
+        let options = ReplacerOptions::new().input_format(InputFormat::Dmesg { boot_time });
+        replace_epoch_timestamps_str_with_options(input, &options)
+    }
+
+    #[test]
+    fn convert_offsets_from_boot() {
+        assert_eq!(
+            "[2018-06-28 20:01:10.000000 UTC] Linux version 4.15.0 (1530216070)\n\
+             <6>[2018-06-28 23:26:55.678901 UTC] usb 1-1: new device\n\
+             kern  :info  : [2018-06-28 20:01:11.5 UTC] eth0: link up\n",
+            replace(
+                "[    0.000000] Linux version 4.15.0 (1530216070)\n\
+                 <6>[12345.678901] usb 1-1: new device\n\
+                 kern  :info  : [    1.5] eth0: link up\n"
+            )
+        );
+    }
+
+    #[test]
+    fn leave_implausible_offsets_unchanged() {
+        let input =
+            "[99999999999999999999999.5] boom\n[9999999999.5] far future\n[-1.5] negative\n";
+        assert_eq!(input, replace(input));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{digit_run_end, follows_prefix, ReplacerOptions, ReplacerState};
+
+const MILLIS_PER_SECOND: u64 = 1000;
+const MILLIS_PER_MINUTE: u64 = 60_000;
+const MILLIS_PER_HOUR: u64 = 3_600_000;
+/// The units that may follow a millisecond duration, and are replaced along with it.
+const MILLISECOND_UNITS: &[&[u8]] = &[b" ms", b"ms"];
+
+/// The result of scanning for a duration at a position in the input.
+pub(crate) enum DurationCandidate {
+    /// The length of the duration in the input, including any unit, and its value in
+    /// milliseconds.
+    Duration(usize, u64),
+    NotDuration,
+    /// The input ends before the duration and any unit can be seen in full.
+    Incomplete,
+}
+
+/// Scans for a number of milliseconds at `start` that follows one of the duration keys,
+/// along with a following `ms` unit.
+pub(crate) fn scan_duration(
+    input: &[u8],
+    start: usize,
+    end_of_input: bool,
+    options: &ReplacerOptions,
+    state: &ReplacerState,
+) -> DurationCandidate {
+    let keys = match &options.duration_keys {
+        Some(keys) => keys,
+        None => return DurationCandidate::NotDuration,
+    };
+    if !input[start].is_ascii_digit() || !follows_prefix(input, start, keys, state) {
+        return DurationCandidate::NotDuration;
+    }
+    let digits_end = digit_run_end(input, start);
+    let unit_length = match unit_length(&input[digits_end..], end_of_input) {
+        Some(length) => length,
+        None => return DurationCandidate::Incomplete,
+    };
+    match std::str::from_utf8(&input[start..digits_end])
+        .ok()
+        .and_then(|digits| digits.parse().ok())
+    {
+        Some(millis) => DurationCandidate::Duration(digits_end - start + unit_length, millis),
+        None => DurationCandidate::NotDuration,
+    }
+}
+
+/// The length of the millisecond unit at the start of `rest`, 0 if there is none, or
+/// `None` if more input is needed to tell.
+fn unit_length(rest: &[u8], end_of_input: bool) -> Option<usize> {
+    for unit in MILLISECOND_UNITS {
+        if rest.starts_with(unit) {
+            return match rest.get(unit.len()) {
+                // a word such as `msgs` is not a unit
+                Some(byte) if byte.is_ascii_alphanumeric() => Some(0),
+                None if !end_of_input => None,
+                _ => Some(unit.len()),
+            };
+        }
+        if !end_of_input && unit.starts_with(rest) {
+            return None;
+        }
+    }
+    Some(0)
+}
+
+/// Renders a number of milliseconds as hours, minutes and seconds, such as `1m 33.211s`,
+/// leaving out any that are zero; durations under a second stay in milliseconds.
+pub(crate) fn append_duration(millis: u64, output: &mut Vec<u8>) {
+    if millis < MILLIS_PER_SECOND {
+        output.extend_from_slice(format!("{}ms", millis).as_bytes());
+        return;
+    }
+    let mut parts = Vec::new();
+    let hours = millis / MILLIS_PER_HOUR;
+    if hours > 0 {
+        parts.push(format!("{}h", hours));
+    }
+    let minutes = millis % MILLIS_PER_HOUR / MILLIS_PER_MINUTE;
+    if minutes > 0 {
+        parts.push(format!("{}m", minutes));
+    }
+    let seconds_millis = millis % MILLIS_PER_MINUTE;
+    if seconds_millis > 0 {
+        let seconds = format!(
+            "{}.{:03}",
+            seconds_millis / MILLIS_PER_SECOND,
+            seconds_millis % MILLIS_PER_SECOND
+        );
+        parts.push(format!(
+            "{}s",
+            seconds.trim_end_matches('0').trim_end_matches('.')
+        ));
+    }
+    output.extend_from_slice(parts.join(" ").as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{replace_epoch_timestamps_str_with_options, ReplacerOptions, StreamingReplacer};
+
+    #[test]
+    fn humanize_durations_after_duration_keys() {
+        let options = ReplacerOptions::new()
+            .humanize_durations(vec!["took".to_string(), "duration_ms=".to_string()]);
+        assert_eq!(
+            "[2018-06-28 20:01:10 UTC] took 1m 33.211s, duration_ms=2h 5s 12 msgs took 250ms",
+            replace_epoch_timestamps_str_with_options(
+                "1530216070 took 93211 ms, duration_ms=7205000 12 msgs took 250ms",
+                &options
+            )
+        );
+
+        let mut replacer = StreamingReplacer::with_options(options);
+        let mut output = replacer.push_chunk(b"request took 930");
+        output.extend(replacer.push_chunk(b"00 m"));
+        output.extend(replacer.push_chunk(b"s\n"));
+        output.extend(replacer.finish());
+        assert_eq!(b"request took 1m 33s\n".to_vec(), output);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{
+    byte_before, follows_prefix, ReplacerOptions, ReplacerState, ASCII_CLOSE_PAREN,
+    ASCII_DECIMAL_POINT, ASCII_HYPHEN, ASCII_PLUS, ASCII_SPACE, ASCII_ZERO,
+};
+
+/// The separators that may follow the country code of a phone number.
+const COUNTRY_CODE_SEPARATORS: &[u8] = &[ASCII_SPACE, ASCII_HYPHEN, ASCII_DECIMAL_POINT];
+const MAX_COUNTRY_CODE_DIGITS: usize = 3;
+/// The longest area code or line number group joined to the rest of a phone number.
+const MAX_PHONE_GROUP_DIGITS: usize = 4;
+/// The number of bytes before a digit run that are needed to see a joined phone number
+/// group and the byte before it, which also covers a `+`, country code and separator.
+pub(crate) const PHONE_NUMBER_LOOKBEHIND: usize = MAX_PHONE_GROUP_DIGITS + 2;
+const MIN_CARD_DIGITS: usize = 13;
+const MAX_CARD_DIGITS: usize = 19;
+/// The leading digits of the card networks: Mastercard, American Express, Diners Club
+/// and JCB, Visa, Mastercard and Discover. Timestamps before 2033 all start with 1.
+const CARD_LEADING_DIGITS: &[u8] = &[2, 3, 4, 5, 6];
+
+/// Whether the candidate timestamp of `length` bytes at `start` looks like an
+/// identifier that the options leave unchanged.
+pub(crate) fn is_excluded(
+    input: &[u8],
+    start: usize,
+    length: usize,
+    options: &ReplacerOptions,
+    state: &ReplacerState,
+) -> bool {
+    (options.skip_phone_numbers && is_phone_number(input, start, length, state))
+        || (options.skip_card_numbers && is_card_number(&input[start..start + length], options))
+        || options
+            .skip_key_prefixes
+            .as_ref()
+            .is_some_and(|prefixes| follows_prefix(input, start, prefixes, state))
+}
+
+/// Whether the digit run at `start` is directly preceded by `+` or a country code such
+/// as `+44 `, or is joined by a hyphen or parenthesis to a short group of digits, as in
+/// `555-1530216070` or `(555)1530216070`.
+fn is_phone_number(input: &[u8], start: usize, length: usize, state: &ReplacerState) -> bool {
+    let before = |back: usize| byte_before(input, start, back, state);
+    let preceding = match before(1) {
+        Some(byte) => byte,
+        None => return false,
+    };
+    if preceding == ASCII_PLUS {
+        return true;
+    }
+    let group_digits = (2..)
+        .take(MAX_PHONE_GROUP_DIGITS + 1)
+        .take_while(|&back| before(back).is_some_and(|byte| byte.is_ascii_digit()))
+        .count();
+    let has_country_code = COUNTRY_CODE_SEPARATORS.contains(&preceding)
+        && (1..=MAX_COUNTRY_CODE_DIGITS).contains(&group_digits)
+        && before(group_digits + 2) == Some(ASCII_PLUS);
+    let joined_before = (preceding == ASCII_HYPHEN || preceding == ASCII_CLOSE_PAREN)
+        && (1..=MAX_PHONE_GROUP_DIGITS).contains(&group_digits);
+
+    let following = &input[(start + length).min(input.len())..];
+    let following_digits = following
+        .iter()
+        .skip(1)
+        .take(MAX_PHONE_GROUP_DIGITS + 1)
+        .take_while(|byte| byte.is_ascii_digit())
+        .count();
+    let joined_after = following.first() == Some(&ASCII_HYPHEN)
+        && (1..=MAX_PHONE_GROUP_DIGITS).contains(&following_digits);
+    has_country_code || joined_before || joined_after
+}
+
+/// Whether `candidate` is 13 to 19 digits, possibly with digit separators, that start
+/// with a card network's leading digit and pass the Luhn check.
+fn is_card_number(candidate: &[u8], options: &ReplacerOptions) -> bool {
+    let is_digits = candidate
+        .iter()
+        .all(|byte| byte.is_ascii_digit() || options.digit_separators.contains(byte));
+    if !is_digits {
+        return false;
+    }
+    let digits: Vec<u8> = candidate
+        .iter()
+        .filter(|byte| byte.is_ascii_digit())
+        .map(|byte| byte - ASCII_ZERO)
+        .collect();
+    (MIN_CARD_DIGITS..=MAX_CARD_DIGITS).contains(&digits.len())
+        && CARD_LEADING_DIGITS.contains(&digits[0])
+        && passes_luhn_check(&digits)
+}
+
+fn passes_luhn_check(digits: &[u8]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(position, &digit)| {
+            let digit = u32::from(digit);
+            if !position.is_multiple_of(2) {
+                let doubled = digit * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                digit
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StreamingReplacer;
+
+    fn replace(chunks: &[&str], options: ReplacerOptions) -> String {
+        let mut replacer = StreamingReplacer::with_options(options);
+        let mut output = Vec::new();
+        for chunk in chunks {
+            output.extend(replacer.push_chunk(chunk.as_bytes()));
+        }
+        output.extend(replacer.finish());
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn skip_phone_numbers() {
+        assert_eq!(
+            "call +1530216070, +44 1530216070, (555)1530216070 or 1530216070-12 \
+             at [2018-06-28 20:01:10 UTC] [2018-06-28 20:00:00 UTC]-[2018-06-28 20:01:10 UTC]",
+            replace(
+                &[
+                    "call +1530216070, +44 ",
+                    "1530216070, (555)1530216070 or 1530216070-12 ",
+                    "at 1530216070 1530216000-1530216070"
+                ],
+                ReplacerOptions::new().skip_phone_numbers(true)
+            )
+        );
+    }
+
+    #[test]
+    fn skip_card_numbers_and_identifiers() {
+        assert_eq!(
+            "card 4111111111111111 [2100-04-11 07:18:31.111112 UTC] order=1530216070 \
+             \"user_id\": 1530216070 ts=[2018-06-28 20:01:10.317 UTC]",
+            replace(
+                &[
+                    "card 4111111111111111 4111111111111112 order=1530216070 \"user_",
+                    "id\": 1530216070 ts=1530216070317"
+                ],
+                ReplacerOptions::new().max_year(2100).skip_identifiers()
+            )
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use crate::compression::open_input;
+use crate::formatter::append_custom_format;
+use crate::{
+    absolute_timestamp, find_matches, scan_timestamp, Candidate, EpochTimestamp, ReplacerOptions,
+    ReplacerState, TimestampMatch,
+};
+use std::collections::HashSet;
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io::{Read, Write};
+
+const NANOS_PER_MILLI: u32 = 1_000_000;
+const NANOS_PER_MICRO: u32 = 1_000;
+
+/// Writes each timestamp found in the files to standard output on a line of its own,
+/// without the text around it. Timestamps are rendered as configured by `options`,
+/// without its prefix and suffix. With `unique` set, a timestamp rendered the same as
+/// one already written is skipped.
+#[cfg(feature = "fs")]
+pub fn extract_files(files: &[String], options: &ReplacerOptions, unique: bool) {
+    let stdout = std::io::stdout();
+    let mut stdout_lock = stdout.lock();
+    let mut extractor = Extractor::new(options, unique);
+    for file_name in files {
+        let (mut input, _) =
+            open_input(File::open(file_name).unwrap()).expect("Error reading from input file");
+        extractor.extract(&mut input, &mut stdout_lock);
+    }
+}
+
+/// Writes each timestamp found in standard input to standard output, as
+/// `extract_files` does.
+pub fn extract_stdin(options: &ReplacerOptions, unique: bool) {
+    let (mut input, _) =
+        open_input(std::io::stdin().lock()).expect("Error reading from input file");
+    let stdout = std::io::stdout();
+    let mut stdout_lock = stdout.lock();
+    Extractor::new(options, unique).extract(&mut input, &mut stdout_lock);
+}
+
+struct Extractor<'a> {
+    options: &'a ReplacerOptions,
+    /// The timestamps written so far, when only unique timestamps are written.
+    written: Option<HashSet<String>>,
+}
+
+impl<'a> Extractor<'a> {
+    fn new(options: &'a ReplacerOptions, unique: bool) -> Extractor<'a> {
+        Extractor {
+            options,
+            written: unique.then(HashSet::new),
+        }
+    }
+
+    fn extract(&mut self, input: &mut dyn Read, output: &mut dyn Write) {
+        find_matches(input, self.options, |matches| {
+            self.write_matches(&matches, output)
+        });
+        output.flush().expect("Error flushing output")
+    }
+
+    fn write_matches(&mut self, matches: &[TimestampMatch], output: &mut dyn Write) {
+        for timestamp_match in matches {
+            let rendered = render_match(timestamp_match, self.options);
+            let is_new = match &mut self.written {
+                Some(written) => written.insert(rendered.clone()),
+                None => true,
+            };
+            if is_new {
+                writeln!(output, "{}", rendered).expect("Failed to write");
+            }
+        }
+    }
+}
+
+/// Renders a timestamp as it is rendered in converted output, with the precision of
+/// its original epoch value.
+fn render_match(timestamp_match: &TimestampMatch, options: &ReplacerOptions) -> String {
+    let date_time = &timestamp_match.date_time;
+    let original = timestamp_match.original.as_bytes();
+    let timestamp = match scan_timestamp(original, 0, true, options, &ReplacerState::new()) {
+        Candidate::Timestamp(length, timestamp, _) if length == original.len() => timestamp,
+        // a timestamp that is not an epoch value, such as a dmesg uptime, which is
+        // rebuilt from its conversion as it was before any shift
+        _ => {
+            let unshifted = options.shift.map_or(*date_time, |shift| *date_time - shift);
+            EpochTimestamp {
+                seconds: unshifted.timestamp(),
+                nanos: unshifted.timestamp_subsec_nanos(),
+                fractional_digits: fractional_digits(unshifted.timestamp_subsec_nanos()),
+                offset: None,
+            }
+        }
+    };
+    let mut custom = Vec::new();
+    if append_custom_format(&timestamp, original, options, &mut custom) {
+        return String::from_utf8_lossy(&custom).into_owned();
+    }
+    absolute_timestamp(date_time, &timestamp, options)
+}
+
+/// The fewest of 0, 3, 6 or 9 fractional digits that show `nanos` exactly.
+fn fractional_digits(nanos: u32) -> usize {
+    if nanos == 0 {
+        0
+    } else if nanos.is_multiple_of(NANOS_PER_MILLI) {
+        3
+    } else if nanos.is_multiple_of(NANOS_PER_MICRO) {
+        6
+    } else {
+        9
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OutputStyle;
+
+    fn extract(input: &str, options: ReplacerOptions, unique: bool) -> String {
+        let mut output = Vec::new();
+        Extractor::new(&options, unique).extract(&mut input.as_bytes(), &mut output);
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn extract_timestamps_one_per_line() {
+        assert_eq!(
+            "2018-06-28 20:01:10 UTC\n2018-06-28 20:01:10.317 UTC\n2018-06-28 20:01:10 UTC\n",
+            extract(
+                "a 1530216070 b\nc 1530216070317 1530216070\n",
+                ReplacerOptions::new(),
+                false
+            )
+        );
+    }
+
+    #[test]
+    fn extract_unique_timestamps() {
+        assert_eq!(
+            "2018-06-28T20:01:10Z\n2018-06-28T20:01:10.317Z\n",
+            extract(
+                "a 1530216070 b\nc 1530216070317 1530216070\n",
+                ReplacerOptions::new().style(OutputStyle::Rfc3339),
+                true
+            )
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use crate::{ReplacerOptions, StreamingReplacer};
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// Returned by the functions below on success.
+pub const NAIL_OK: c_int = 0;
+/// Returned when an argument is null or invalid, or when conversion fails.
+pub const NAIL_ERROR: c_int = -1;
+
+/// Conversion options, created with `nail_options_new` and released with
+/// `nail_options_free`.
+pub struct NailOptions {
+    options: ReplacerOptions,
+}
+
+/// A conversion of a stream that arrives in chunks, created with `nail_stream_new` and
+/// released with `nail_stream_free`.
+pub struct NailStream {
+    replacer: StreamingReplacer,
+}
+
+/// Converted data owned by nail, to be released with `nail_buffer_free`.
+#[repr(C)]
+pub struct NailBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// Creates the default options, or returns null if they cannot be created.
+#[no_mangle]
+pub extern "C" fn nail_options_new() -> *mut NailOptions {
+    catch_panic(ptr::null_mut(), || {
+        Box::into_raw(Box::new(NailOptions {
+            options: ReplacerOptions::new(),
+        }))
+    })
+}
+
+/// Renders timestamps with a chrono strftime pattern.
+///
+/// # Safety
+///
+/// `options` must come from `nail_options_new`, and `format` must be a NUL-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn nail_options_set_format(
+    options: *mut NailOptions,
+    format: *const c_char,
+) -> c_int {
+    update_options(options, format, |options, format| options.format(format))
+}
+
+/// Renders timestamps in an IANA timezone, such as `Europe/London`.
+///
+/// # Safety
+///
+/// `options` must come from `nail_options_new`, and `name` must be a NUL-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn nail_options_set_timezone(
+    options: *mut NailOptions,
+    name: *const c_char,
+) -> c_int {
+    update_options(options, name, |options, name| options.timezone(name))
+}
+
+/// # Safety
+///
+/// `options` must be null or come from `nail_options_new`, and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn nail_options_free(options: *mut NailOptions) {
+    catch_panic((), || {
+        if !options.is_null() {
+            drop(Box::from_raw(options));
+        }
+    })
+}
+
+/// Converts the `len` bytes at `buf` as a complete input, storing the result in `out`.
+/// A null `options` uses the defaults.
+///
+/// # Safety
+///
+/// `buf` must point to `len` readable bytes, `options` must be null or come from
+/// `nail_options_new`, and `out` must point to a writable `NailBuffer`.
+#[no_mangle]
+pub unsafe extern "C" fn nail_replace(
+    buf: *const u8,
+    len: usize,
+    options: *const NailOptions,
+    out: *mut NailBuffer,
+) -> c_int {
+    if (buf.is_null() && len > 0) || out.is_null() {
+        return NAIL_ERROR;
+    }
+    catch_panic(NAIL_ERROR, || {
+        let replacer = options_or_default(options).build();
+        *out = into_buffer(replacer.replace(input_slice(buf, len)));
+        NAIL_OK
+    })
+}
+
+/// Returns null if the stream cannot be created.
+///
+/// # Safety
+///
+/// `options` must be null or come from `nail_options_new`; the stream keeps its own copy.
+#[no_mangle]
+pub unsafe extern "C" fn nail_stream_new(options: *const NailOptions) -> *mut NailStream {
+    catch_panic(ptr::null_mut(), || {
+        Box::into_raw(Box::new(NailStream {
+            replacer: StreamingReplacer::with_options(options_or_default(options)),
+        }))
+    })
+}
+
+/// Converts the next `len` bytes of the stream, storing all output that can be
+/// resolved so far in `out`.
+///
+/// # Safety
+///
+/// `stream` must come from `nail_stream_new`, `buf` must point to `len` readable bytes,
+/// and `out` must point to a writable `NailBuffer`.
+#[no_mangle]
+pub unsafe extern "C" fn nail_stream_push(
+    stream: *mut NailStream,
+    buf: *const u8,
+    len: usize,
+    out: *mut NailBuffer,
+) -> c_int {
+    if stream.is_null() || (buf.is_null() && len > 0) || out.is_null() {
+        return NAIL_ERROR;
+    }
+    catch_panic(NAIL_ERROR, || {
+        *out = into_buffer((*stream).replacer.push_chunk(input_slice(buf, len)));
+        NAIL_OK
+    })
+}
+
+/// Resolves any digits retained at the end of the stream, storing the output in `out`.
+///
+/// # Safety
+///
+/// `stream` must come from `nail_stream_new`, and `out` must point to a writable
+/// `NailBuffer`.
+#[no_mangle]
+pub unsafe extern "C" fn nail_stream_finish(
+    stream: *mut NailStream,
+    out: *mut NailBuffer,
+) -> c_int {
+    if stream.is_null() || out.is_null() {
+        return NAIL_ERROR;
+    }
+    catch_panic(NAIL_ERROR, || {
+        *out = into_buffer((*stream).replacer.finish());
+        NAIL_OK
+    })
+}
+
+/// # Safety
+///
+/// `stream` must be null or come from `nail_stream_new`, and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn nail_stream_free(stream: *mut NailStream) {
+    catch_panic((), || {
+        if !stream.is_null() {
+            drop(Box::from_raw(stream));
+        }
+    })
+}
+
+/// # Safety
+///
+/// `buffer` must be null or point to a `NailBuffer` filled in by nail, whose data is
+/// not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn nail_buffer_free(buffer: *mut NailBuffer) {
+    if buffer.is_null() || (*buffer).data.is_null() {
+        return;
+    }
+    catch_panic((), || {
+        let data = ptr::slice_from_raw_parts_mut((*buffer).data, (*buffer).len);
+        drop(Box::from_raw(data));
+        (*buffer).data = ptr::null_mut();
+        (*buffer).len = 0;
+    })
+}
+
+unsafe fn update_options(
+    options: *mut NailOptions,
+    value: *const c_char,
+    update: impl FnOnce(ReplacerOptions, &str) -> Result<ReplacerOptions, String>,
+) -> c_int {
+    if options.is_null() || value.is_null() {
+        return NAIL_ERROR;
+    }
+    let value = match CStr::from_ptr(value).to_str() {
+        Ok(value) => value,
+        Err(_) => return NAIL_ERROR,
+    };
+    let options = &mut (*options).options;
+    catch_panic(NAIL_ERROR, || match update(options.clone(), value) {
+        Ok(updated) => {
+            *options = updated;
+            NAIL_OK
+        }
+        Err(_) => NAIL_ERROR,
+    })
+}
+
+/// Runs `body`, returning `on_panic` instead should it panic, since unwinding into the
+/// C caller is undefined behaviour.
+fn catch_panic<T>(on_panic: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(on_panic)
+}
+
+unsafe fn options_or_default(options: *const NailOptions) -> ReplacerOptions {
+    if options.is_null() {
+        ReplacerOptions::new()
+    } else {
+        (*options).options.clone()
+    }
+}
+
+unsafe fn input_slice<'a>(buf: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(buf, len)
+    }
+}
+
+fn into_buffer(data: Vec<u8>) -> NailBuffer {
+    let len = data.len();
+    NailBuffer {
+        data: Box::into_raw(data.into_boxed_slice()) as *mut u8,
+        len,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take_string(buffer: &mut NailBuffer) -> String {
+        let converted = String::from_utf8_lossy(input_slice(buffer.data, buffer.len)).to_string();
+        nail_buffer_free(buffer);
+        converted
+    }
+
+    #[test]
+    fn replace_through_c_abi() {
+        unsafe {
+            let options = nail_options_new();
+            let format = b"%Y-%m-%dT%H:%M:%S\0";
+            assert_eq!(
+                NAIL_OK,
+                nail_options_set_format(options, format.as_ptr() as *const c_char)
+            );
+            let invalid_zone = b"Nowhere/Special\0";
+            assert_eq!(
+                NAIL_ERROR,
+                nail_options_set_timezone(options, invalid_zone.as_ptr() as *const c_char)
+            );
+
+            let input = b"at 1530216070";
+            let mut out = NailBuffer {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+            assert_eq!(
+                NAIL_OK,
+                nail_replace(input.as_ptr(), input.len(), options, &mut out)
+            );
+            assert_eq!("at [2018-06-28T20:01:10]", take_string(&mut out));
+            nail_options_free(options);
+        }
+    }
+
+    #[test]
+    fn stream_through_c_abi() {
+        unsafe {
+            let stream = nail_stream_new(ptr::null());
+            let mut out = NailBuffer {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+            let mut converted = String::new();
+            for chunk in [&b"a 15302"[..], &b"16070 b"[..]] {
+                assert_eq!(
+                    NAIL_OK,
+                    nail_stream_push(stream, chunk.as_ptr(), chunk.len(), &mut out)
+                );
+                converted.push_str(&take_string(&mut out));
+            }
+            assert_eq!(NAIL_OK, nail_stream_finish(stream, &mut out));
+            converted.push_str(&take_string(&mut out));
+            nail_stream_free(stream);
+
+            assert_eq!("a [2018-06-28 20:01:10 UTC] b", converted);
+        }
+    }
+
+    #[test]
+    fn report_panics_as_errors() {
+        assert_eq!(
+            NAIL_ERROR,
+            catch_panic(NAIL_ERROR, || -> c_int { panic!("conversion failed") })
+        );
+    }
+
+    #[test]
+    fn checked_in_header_matches_generated_one() {
+        assert_eq!(
+            include_str!(concat!(env!("OUT_DIR"), "/nail.h")),
+            include_str!("../include/nail.h")
+        );
+    }
+}
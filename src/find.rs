@@ -0,0 +1,249 @@
+use crate::confidence::confidence;
+use crate::exclusion::is_excluded;
+use crate::{
+    is_delimited, is_keyed, is_plausible, may_start_timestamp, scan_timestamp, Candidate,
+    ReplacerOptions, ReplacerState,
+};
+use chrono::prelude::*;
+use std::ops::Range;
+
+/// The form in which an epoch timestamp was written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimestampKind {
+    Seconds,
+    Milliseconds,
+    Microseconds,
+    /// Seconds with a decimal fraction, such as `1530216070.317`.
+    FractionalSeconds,
+    /// A Windows FILETIME: 100-nanosecond intervals since 1601-01-01, in 18 digits.
+    Filetime,
+    /// A FILETIME value of an Active Directory attribute, such as `lastLogonTimestamp`.
+    Ldap,
+    /// A WebKit (Chrome) timestamp: microseconds since 1601-01-01, in 17 digits.
+    Webkit,
+    /// .NET `DateTime` ticks: 100-nanosecond intervals since 0001-01-01, in 18 digits.
+    DotnetTicks,
+    /// An `@`-prefixed TAI64N label, such as `@400000005b353e9002b47e38`.
+    Tai64n,
+    /// A snowflake ID, which holds its creation time in milliseconds in its high bits.
+    Snowflake,
+    /// A MongoDB ObjectId, which starts with its creation time in seconds.
+    ObjectId,
+    /// A version 7 UUID, which starts with its creation time in milliseconds.
+    Uuid7,
+    /// A ULID, which starts with its creation time in milliseconds.
+    Ulid,
+}
+
+impl TimestampKind {
+    /// Whether the timestamp is part of an identifier, which is kept and annotated
+    /// rather than replaced.
+    pub(crate) fn is_identifier(self) -> bool {
+        matches!(
+            self,
+            TimestampKind::Snowflake
+                | TimestampKind::ObjectId
+                | TimestampKind::Uuid7
+                | TimestampKind::Ulid
+        )
+    }
+}
+
+/// An epoch timestamp found in the input, located by the byte range it occupies.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Match {
+    pub range: Range<usize>,
+    pub kind: TimestampKind,
+    pub datetime: DateTime<Utc>,
+    /// How likely the match is to be a timestamp rather than another number, out of 100.
+    pub confidence: u8,
+}
+
+/// The epoch timestamps in an input, in order, as found by `find_epoch_timestamps`.
+pub struct EpochTimestamps<'a> {
+    input: &'a [u8],
+    index: usize,
+    options: ReplacerOptions,
+    state: ReplacerState,
+}
+
+/// Finds the epoch timestamps that `replace_epoch_timestamps` would convert in
+/// `input`, without rewriting it.
+pub fn find_epoch_timestamps(input: &[u8]) -> EpochTimestamps<'_> {
+    find_epoch_timestamps_with_options(input, &ReplacerOptions::new())
+}
+
+/// As `find_epoch_timestamps`, recognising timestamps as configured by `options`.
+pub fn find_epoch_timestamps_with_options<'a>(
+    input: &'a [u8],
+    options: &ReplacerOptions,
+) -> EpochTimestamps<'a> {
+    EpochTimestamps {
+        input,
+        index: 0,
+        options: options.clone(),
+        state: ReplacerState::new(),
+    }
+}
+
+impl Iterator for EpochTimestamps<'_> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        let input = self.input;
+        while self.index < input.len() {
+            let start = self.index;
+            if !may_start_timestamp(input[start], &self.options) {
+                self.index += 1;
+                continue;
+            }
+            match scan_timestamp(input, start, true, &self.options, &self.state) {
+                Candidate::Timestamp(length, timestamp, kind)
+                    if is_plausible(&timestamp, &self.options)
+                        && is_delimited(input, start, length, &self.options, &self.state)
+                        && is_keyed(input, start, &self.options, &self.state)
+                        && !is_excluded(input, start, length, &self.options, &self.state) =>
+                {
+                    self.index += length;
+                    let datetime = timestamp.date_time().expect("Invalid timestamp");
+                    let confidence = confidence(
+                        input,
+                        start,
+                        length,
+                        kind,
+                        datetime,
+                        &self.options,
+                        &self.state,
+                    );
+                    if confidence < self.options.min_confidence {
+                        continue;
+                    }
+                    return Some(Match {
+                        range: start..self.index,
+                        kind,
+                        datetime,
+                        confidence,
+                    });
+                }
+                Candidate::Timestamp(length, _, _) | Candidate::NotTimestamp(length) => {
+                    self.index += length;
+                }
+                Candidate::Incomplete => break,
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_timestamps_of_each_kind() {
+        let input = b"a 1530216070 b 1530216070317 c 1530216070317123 d 1530216070.5 e 12345";
+        let matches: Vec<(Range<usize>, TimestampKind)> = find_epoch_timestamps(input)
+            .map(|found| (found.range, found.kind))
+            .collect();
+
+        assert_eq!(
+            vec![
+                (2..12, TimestampKind::Seconds),
+                (15..28, TimestampKind::Milliseconds),
+                (31..47, TimestampKind::Microseconds),
+                (50..62, TimestampKind::FractionalSeconds),
+            ],
+            matches
+        );
+        assert_eq!(
+            Utc.timestamp_opt(1530216070, 317_000_000).unwrap(),
+            find_epoch_timestamps(input).nth(1).unwrap().datetime
+        );
+    }
+
+    #[test]
+    fn find_only_timestamps_accepted_by_options() {
+        let options = ReplacerOptions::new().key_prefixes(vec!["ts=".to_string()]);
+        let ranges: Vec<Range<usize>> =
+            find_epoch_timestamps_with_options(b"id=1530216070 ts=1530216070", &options)
+                .map(|found| found.range)
+                .collect();
+
+        assert_eq!(vec![17..27], ranges);
+    }
+
+    #[test]
+    fn find_filetime_timestamps_only_when_enabled() {
+        let input = b"created 131746896703170000";
+        let options = ReplacerOptions::new().filetime_timestamps(true);
+        let found: Vec<Match> = find_epoch_timestamps_with_options(input, &options).collect();
+
+        assert_eq!(0, find_epoch_timestamps(input).count());
+        assert_eq!(
+            vec![Match {
+                range: 8..26,
+                kind: TimestampKind::Filetime,
+                datetime: Utc.timestamp_opt(1530216070, 317_000_000).unwrap(),
+                confidence: 80,
+            }],
+            found
+        );
+    }
+
+    #[test]
+    fn distinguish_ldap_attributes_from_filetimes() {
+        let input = b"pwdLastSet: 131746896703170000 created=131746896703170000";
+        let options = ReplacerOptions::new()
+            .filetime_timestamps(true)
+            .ldap_timestamps(true);
+        let kinds: Vec<TimestampKind> = find_epoch_timestamps_with_options(input, &options)
+            .map(|found| found.kind)
+            .collect();
+        let ldap_only = ReplacerOptions::new().ldap_timestamps(true);
+
+        assert_eq!(vec![TimestampKind::Ldap, TimestampKind::Filetime], kinds);
+        assert_eq!(
+            1,
+            find_epoch_timestamps_with_options(input, &ldap_only).count()
+        );
+    }
+
+    #[test]
+    fn find_webkit_timestamps_only_when_enabled() {
+        let input = b"last_visit_time=13174689670317123";
+        let options = ReplacerOptions::new().webkit_timestamps(true);
+        let found: Vec<Match> = find_epoch_timestamps_with_options(input, &options).collect();
+
+        assert_eq!(0, find_epoch_timestamps(input).count());
+        assert_eq!(
+            vec![Match {
+                range: 16..33,
+                kind: TimestampKind::Webkit,
+                datetime: Utc.timestamp_opt(1530216070, 317_123_000).unwrap(),
+                confidence: 100,
+            }],
+            found
+        );
+    }
+
+    #[test]
+    fn distinguish_dotnet_ticks_from_filetimes_by_range() {
+        let input = b"a 131746896703170000 b 636658128703170000";
+        let options = ReplacerOptions::new()
+            .filetime_timestamps(true)
+            .dotnet_timestamps(true);
+        let found: Vec<(TimestampKind, DateTime<Utc>)> =
+            find_epoch_timestamps_with_options(input, &options)
+                .map(|found| (found.kind, found.datetime))
+                .collect();
+        let date_time = Utc.timestamp_opt(1530216070, 317_000_000).unwrap();
+
+        assert_eq!(
+            vec![
+                (TimestampKind::Filetime, date_time),
+                (TimestampKind::DotnetTicks, date_time)
+            ],
+            found
+        );
+    }
+}
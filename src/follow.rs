@@ -0,0 +1,174 @@
+use crate::{ReplacerOptions, StreamingReplacer, BUFFER_SIZE};
+use std::fs::{metadata, File, Metadata};
+use std::io::{ErrorKind, Read, Write};
+use std::thread::sleep;
+use std::time::Duration;
+
+/// Converts a file as it grows, in the manner of `tail -F`. The file is reopened when
+/// it is replaced (log rotation) or truncated.
+pub struct Follower {
+    file_name: String,
+    file: Option<File>,
+    identity: Option<FileIdentity>,
+    position: u64,
+    replacer: StreamingReplacer,
+    read_buffer: Vec<u8>,
+}
+
+impl Follower {
+    pub fn new(file_name: &str, options: &ReplacerOptions) -> Follower {
+        Follower {
+            file_name: file_name.to_string(),
+            file: None,
+            identity: None,
+            position: 0,
+            replacer: StreamingReplacer::with_options(options.clone()),
+            read_buffer: vec![0; BUFFER_SIZE],
+        }
+    }
+
+    /// Converts any data appended since the last poll, returning whether anything was
+    /// read. A missing file is not an error, as it may be about to be recreated.
+    pub fn poll(&mut self, output: &mut dyn Write) -> std::io::Result<bool> {
+        if self.file.is_none() && !self.open()? {
+            return Ok(false);
+        }
+        let read_any = self.read_available(output)?;
+        if !read_any && self.is_rotated() {
+            // the old file has been read to its end, so trailing digits are complete
+            output.write_all(&self.replacer.finish())?;
+            output.flush()?;
+            self.file = None;
+            self.identity = None;
+            self.position = 0;
+            return Ok(self.open()? && self.read_available(output)?);
+        }
+        Ok(read_any)
+    }
+
+    fn open(&mut self) -> std::io::Result<bool> {
+        match File::open(&self.file_name) {
+            Ok(file) => {
+                self.identity = Some(FileIdentity::of(&file.metadata()?));
+                self.file = Some(file);
+                Ok(true)
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn read_available(&mut self, output: &mut dyn Write) -> std::io::Result<bool> {
+        let file = self.file.as_mut().expect("File not open");
+        let mut read_any = false;
+        loop {
+            let read_length = file.read(&mut self.read_buffer)?;
+            if read_length == 0 {
+                break;
+            }
+            read_any = true;
+            self.position += read_length as u64;
+            output.write_all(&self.replacer.push_chunk(&self.read_buffer[..read_length]))?;
+        }
+        if read_any {
+            output.flush()?;
+        }
+        Ok(read_any)
+    }
+
+    fn is_rotated(&self) -> bool {
+        match metadata(&self.file_name) {
+            Ok(current) => {
+                Some(FileIdentity::of(&current)) != self.identity || current.len() < self.position
+            }
+            // the file has been moved away and not yet recreated
+            Err(_) => false,
+        }
+    }
+}
+
+/// Identifies the file behind a path, so that replacement of the file can be detected.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct FileIdentity {
+    device: u64,
+    inode: u64,
+}
+
+impl FileIdentity {
+    #[cfg(unix)]
+    fn of(metadata: &Metadata) -> FileIdentity {
+        use std::os::unix::fs::MetadataExt;
+        FileIdentity {
+            device: metadata.dev(),
+            inode: metadata.ino(),
+        }
+    }
+
+    // without inodes, rotation can only be detected by the file shrinking
+    #[cfg(not(unix))]
+    fn of(_metadata: &Metadata) -> FileIdentity {
+        FileIdentity {
+            device: 0,
+            inode: 0,
+        }
+    }
+}
+
+/// Follows `file_name` indefinitely, writing converted output to stdout.
+pub fn follow_file(file_name: &str, options: &ReplacerOptions, poll_interval: Duration) {
+    let stdout = std::io::stdout();
+    let mut stdout_lock = stdout.lock();
+    let mut follower = Follower::new(file_name, options);
+    loop {
+        let read_any = follower
+            .poll(&mut stdout_lock)
+            .expect("Error following file");
+        if !read_any {
+            sleep(poll_interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{rename, OpenOptions};
+    use std::time::Instant;
+
+    fn append(name: &str, data: &str) {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(name)
+            .unwrap()
+            .write_all(data.as_bytes())
+            .expect("Failed to write file");
+    }
+
+    #[test]
+    fn follow_appended_data_and_rotation() {
+        let name = format!("/tmp/{:?}-follow", Instant::now());
+        let mut follower = Follower::new(&name, &ReplacerOptions::new());
+        let mut output = Vec::new();
+
+        assert!(!follower.poll(&mut output).unwrap());
+        append(&name, "a 1530216070\nb 15302");
+        assert!(follower.poll(&mut output).unwrap());
+        assert_eq!(
+            "a [2018-06-28 20:01:10 UTC]\nb ",
+            String::from_utf8_lossy(&output)
+        );
+
+        append(&name, "16070317\n");
+        follower.poll(&mut output).unwrap();
+        rename(&name, name.clone() + ".1").unwrap();
+        append(&name, "c 1530216070");
+        assert!(follower.poll(&mut output).unwrap());
+        assert!(!follower.poll(&mut output).unwrap());
+
+        assert_eq!(
+            "a [2018-06-28 20:01:10 UTC]\nb [2018-06-28 20:01:10.317 UTC]\nc ",
+            String::from_utf8_lossy(&output)
+        );
+    }
+}
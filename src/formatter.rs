@@ -0,0 +1,105 @@
+use crate::{EpochTimestamp, ReplacerOptions};
+use chrono::prelude::*;
+use std::fmt;
+use std::sync::Arc;
+
+/// Renders converted timestamps in an application-specific way, such as an HTML span.
+/// The returned bytes replace the original timestamp as they are; the prefix, suffix,
+/// colour, annotation, mode and style options do not apply, and nor does quoting in
+/// structured input.
+pub trait TimestampFormatter: Send + Sync {
+    fn format(&self, date_time: &DateTime<Utc>, original: &[u8]) -> Vec<u8>;
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct CustomFormatter(Option<Arc<dyn TimestampFormatter>>);
+
+impl CustomFormatter {
+    pub(crate) fn new(formatter: Arc<dyn TimestampFormatter>) -> CustomFormatter {
+        CustomFormatter(Some(formatter))
+    }
+}
+
+impl fmt::Debug for CustomFormatter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "CustomFormatter(Some)"),
+            None => write!(f, "CustomFormatter(None)"),
+        }
+    }
+}
+
+impl PartialEq for CustomFormatter {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Appends the rendering of the custom formatter, returning false if none is configured.
+pub(crate) fn append_custom_format(
+    timestamp: &EpochTimestamp,
+    original: &[u8],
+    options: &ReplacerOptions,
+    append_buffer: &mut Vec<u8>,
+) -> bool {
+    match &options.formatter.0 {
+        Some(formatter) => {
+            let date_time = timestamp.converted_date_time(options);
+            append_buffer.extend(formatter.format(&date_time, original));
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{replace_epoch_timestamps_str_with_options, InputFormat};
+
+    struct HtmlFormatter;
+
+    impl TimestampFormatter for HtmlFormatter {
+        fn format(&self, date_time: &DateTime<Utc>, original: &[u8]) -> Vec<u8> {
+            format!(
+                "<time datetime=\"{}\">{}</time>",
+                date_time.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                String::from_utf8_lossy(original)
+            )
+            .into_bytes()
+        }
+    }
+
+    fn replace(input: &str, options: ReplacerOptions) -> String {
+        replace_epoch_timestamps_str_with_options(
+            input,
+            &options.formatter(Arc::new(HtmlFormatter)),
+        )
+    }
+
+    #[test]
+    fn render_with_custom_formatter() {
+        assert_eq!(
+            "at <time datetime=\"2018-06-28T20:01:10.317Z\">1530216070317</time>\n",
+            replace(
+                "at 1530216070317\n",
+                ReplacerOptions::new().annotate(true).color(true)
+            )
+        );
+    }
+
+    #[test]
+    fn render_structured_input_with_custom_formatter() {
+        assert_eq!(
+            "time=<time datetime=\"2018-06-28T20:01:10Z\">1530216070</time> id=1530216070",
+            replace(
+                "time=1530216070 id=1530216070",
+                ReplacerOptions::new().input_format(InputFormat::logfmt())
+            )
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use crate::compression::open_input;
+use crate::{find_matches, ReplacerOptions};
+use chrono::prelude::*;
+use chrono::Duration;
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io::{Read, Write};
+
+/// The units of a duration such as `90s` or `5m`, in milliseconds, with `ms` ahead of
+/// `s` as it ends with the same suffix.
+const DURATION_UNITS: &[(&str, i64)] = &[
+    ("ms", 1),
+    ("s", 1000),
+    ("m", 60_000),
+    ("h", 3_600_000),
+    ("d", 86_400_000),
+];
+
+/// A period between two timestamps of the input in which no timestamps were found.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gap {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl Gap {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// Finds the gaps longer than a threshold between the timestamps of an input, in the
+/// order they are found. A timestamp earlier than one before it, as written by an
+/// asynchronous appender, neither starts nor ends a gap.
+pub struct GapFinder {
+    threshold: Duration,
+    latest: Option<DateTime<Utc>>,
+}
+
+impl GapFinder {
+    pub fn new(threshold: Duration) -> GapFinder {
+        GapFinder {
+            threshold,
+            latest: None,
+        }
+    }
+
+    /// Returns the gap that `date_time` ends, if it is further than the threshold
+    /// after the latest timestamp so far.
+    pub fn record(&mut self, date_time: DateTime<Utc>) -> Option<Gap> {
+        let gap = self
+            .latest
+            .filter(|&latest| date_time - latest > self.threshold)
+            .map(|latest| Gap {
+                start: latest,
+                end: date_time,
+            });
+        if self.latest.is_none_or(|latest| date_time > latest) {
+            self.latest = Some(date_time);
+        }
+        gap
+    }
+
+    /// Writes a line for each gap in the timestamps that `options` would convert in
+    /// `input`, starting with `name` if one is given.
+    pub fn write_gaps(
+        &mut self,
+        name: Option<&str>,
+        input: &mut dyn Read,
+        options: &ReplacerOptions,
+        output: &mut dyn Write,
+    ) {
+        find_matches(input, options, |matches| {
+            for timestamp_match in matches {
+                if let Some(gap) = self.record(timestamp_match.date_time) {
+                    if let Some(name) = name {
+                        write!(output, "{}: ", name).expect("Failed to write");
+                    }
+                    writeln!(output, "{}", describe_gap(&gap)).expect("Failed to write");
+                }
+            }
+        });
+        output.flush().expect("Error flushing output")
+    }
+}
+
+/// Reports the gaps longer than `threshold` between the timestamps of each file on
+/// standard output.
+#[cfg(feature = "fs")]
+pub fn find_gaps_in_files(files: &[String], options: &ReplacerOptions, threshold: Duration) {
+    let stdout = std::io::stdout();
+    let mut stdout_lock = stdout.lock();
+    for file_name in files {
+        let (mut input, _) =
+            open_input(File::open(file_name).unwrap()).expect("Error reading from input file");
+        GapFinder::new(threshold).write_gaps(
+            Some(file_name),
+            &mut input,
+            options,
+            &mut stdout_lock,
+        );
+    }
+}
+
+/// Reports the gaps longer than `threshold` between the timestamps of standard input
+/// on standard output.
+pub fn find_gaps_in_stdin(options: &ReplacerOptions, threshold: Duration) {
+    let (mut input, _) =
+        open_input(std::io::stdin().lock()).expect("Error reading from input file");
+    let stdout = std::io::stdout();
+    let mut stdout_lock = stdout.lock();
+    GapFinder::new(threshold).write_gaps(None, &mut input, options, &mut stdout_lock);
+}
+
+/// Parses a duration such as `500ms`, `90s`, `5m`, `2h` or `1d`. A number without a unit
+/// is in seconds.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid duration: {}", value);
+    let (number, millis_per_unit) = DURATION_UNITS
+        .iter()
+        .find_map(|&(suffix, millis)| Some((value.strip_suffix(suffix)?, millis)))
+        .unwrap_or((value, 1000));
+    let count: i64 = number.parse().map_err(|_| invalid())?;
+    count
+        .checked_mul(millis_per_unit)
+        .filter(|&millis| millis >= 0)
+        .and_then(Duration::try_milliseconds)
+        .ok_or_else(invalid)
+}
+
+/// Parses a duration as `parse_duration` does, with an optional leading `+` or `-`.
+pub fn parse_signed_duration(value: &str) -> Result<Duration, String> {
+    match value.strip_prefix('-') {
+        Some(magnitude) => parse_duration(magnitude).map(|duration| -duration),
+        None => parse_duration(value.strip_prefix('+').unwrap_or(value)),
+    }
+}
+
+/// Describes a gap as `no events for 00:07:32 between <start> and <end>`.
+pub(crate) fn describe_gap(gap: &Gap) -> String {
+    let seconds = gap.duration().num_seconds();
+    format!(
+        "no events for {:02}:{:02}:{:02} between {} and {}",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60,
+        gap.start.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+        gap.end.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_gaps_longer_than_the_threshold() {
+        let input = "a 1530216070\nb 1530216100317\nc 1530216552\nd 1530216500\ne 1530216560\n";
+        let mut output = Vec::new();
+        GapFinder::new(Duration::try_minutes(5).unwrap()).write_gaps(
+            Some("app.log"),
+            &mut input.as_bytes(),
+            &ReplacerOptions::new(),
+            &mut output,
+        );
+
+        assert_eq!(
+            "app.log: no events for 00:07:31 between 2018-06-28T20:01:40.317Z \
+             and 2018-06-28T20:09:12Z\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(
+            Ok(Duration::try_milliseconds(500).unwrap()),
+            parse_duration("500ms")
+        );
+        assert_eq!(Ok(Duration::try_seconds(90).unwrap()), parse_duration("90"));
+        assert_eq!(Ok(Duration::try_minutes(5).unwrap()), parse_duration("5m"));
+        assert_eq!(Ok(Duration::try_days(1).unwrap()), parse_duration("1d"));
+        assert!(parse_duration("5 minutes").is_err());
+        assert!(parse_duration("-5m").is_err());
+        assert_eq!(
+            Ok(Duration::try_hours(-2).unwrap()),
+            parse_signed_duration("-2h")
+        );
+        assert_eq!(
+            Ok(Duration::try_hours(2).unwrap()),
+            parse_signed_duration("+2h")
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{
+    anchored_timestamp, is_plausible, replace_line_timestamp, ReplacerOptions, ReplacerState,
+    ASCII_CLOSE_BRACKET, ASCII_COLON, ASCII_DECIMAL_POINT, ASCII_OPEN_BRACKET, ASCII_SPACE,
+    ASCII_ZERO, MAX_FRACTIONAL_DIGITS,
+};
+use chrono::{DateTime, Duration, Utc};
+
+/// The format of the `-XX:+PrintGCDateStamps` and unified logging `time` decorations.
+const DATE_STAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f%z";
+const SECONDS_SUFFIX: &str = "s";
+const MILLISECONDS_SUFFIX: &str = "ms";
+const DIGITS_IN_MILLISECONDS: usize = 3;
+
+/// Progress through a GC log, carried from one line to the next.
+#[derive(Default)]
+pub(crate) struct GcLogState {
+    /// The start of the JVM, once found from a line with both a date stamp and an uptime.
+    start_time: Option<DateTime<Utc>>,
+}
+
+/// The decorations at the start of a line of a GC log.
+#[derive(Default)]
+struct Decorations {
+    /// The wall-clock time at which the line was written, if it has a date stamp.
+    date_time: Option<DateTime<Utc>>,
+    /// The range of the seconds since the JVM started, the text to replace, and the
+    /// uptime in seconds.
+    uptime: Option<(usize, usize, Vec<u8>)>,
+}
+
+/// Converts the uptime at the start of a line of a JVM GC log, either the `1234.567:`
+/// of `-XX:+PrintGCDetails` or the `[1.234s]` or `[1234ms]` decoration of unified
+/// logging, to the wall-clock time relative to `start_time`. Without a start time, the
+/// first line that also has a date stamp decides it, and earlier lines are unchanged.
+pub(crate) fn replace_gc_log_timestamps(
+    line: &[u8],
+    start_time: &Option<DateTime<Utc>>,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let decorations = decorations(line);
+    if let (None, Some(date_time), Some((_, _, uptime))) = (
+        state.gc_log.start_time,
+        decorations.date_time,
+        &decorations.uptime,
+    ) {
+        state.gc_log.start_time = anchored_timestamp(&DateTime::UNIX_EPOCH, uptime)
+            .and_then(|elapsed| Duration::new(elapsed.seconds, elapsed.nanos))
+            .and_then(|elapsed| date_time.checked_sub_signed(elapsed));
+    }
+
+    let converted = decorations.uptime.and_then(|(start, end, uptime)| {
+        let start_time = start_time.or(state.gc_log.start_time)?;
+        let timestamp = anchored_timestamp(&start_time, &uptime)
+            .filter(|timestamp| is_plausible(timestamp, options))?;
+        Some((start, end, timestamp))
+    });
+    replace_line_timestamp(line, converted, options, state)
+}
+
+/// Finds the date stamp and uptime that start a line, in either the bracketed
+/// decorations of unified logging or the `date: uptime:` prefix of earlier JVMs.
+fn decorations(line: &[u8]) -> Decorations {
+    let mut decorations = Decorations::default();
+    if line.first() == Some(&ASCII_OPEN_BRACKET) {
+        let mut start = 0;
+        while line.get(start) == Some(&ASCII_OPEN_BRACKET) {
+            let end = match line[start..]
+                .iter()
+                .position(|&byte| byte == ASCII_CLOSE_BRACKET)
+            {
+                Some(close) => start + close + 1,
+                None => break,
+            };
+            let decoration = &line[start + 1..end - 1];
+            if let Some(date_time) = date_stamp(decoration) {
+                decorations.date_time.get_or_insert(date_time);
+            } else if let Some(uptime) = unified_uptime(decoration) {
+                decorations.uptime.get_or_insert((start, end, uptime));
+            }
+            start = end;
+        }
+        return decorations;
+    }
+
+    // the fields of the prefix are each followed by a colon and a space, which a date
+    // stamp never contains
+    let mut fields = line.split(|&byte| byte == ASCII_SPACE);
+    let mut start = 0;
+    let mut field = fields.next().unwrap_or_default();
+    if let Some(date_time) = field.strip_suffix(&[ASCII_COLON]).and_then(date_stamp) {
+        decorations.date_time = Some(date_time);
+        start += field.len() + 1;
+        field = fields.next().unwrap_or_default();
+    }
+    if let Some(uptime) = field.strip_suffix(&[ASCII_COLON]) {
+        if is_seconds(uptime) {
+            decorations.uptime = Some((start, start + uptime.len(), uptime.to_vec()));
+        }
+    }
+    decorations
+}
+
+fn date_stamp(field: &[u8]) -> Option<DateTime<Utc>> {
+    let field = std::str::from_utf8(field).ok()?;
+    DateTime::parse_from_str(field, DATE_STAMP_FORMAT)
+        .ok()
+        .map(|date_time| date_time.with_timezone(&Utc))
+}
+
+/// Returns the uptime of a unified logging decoration such as `1.234s` or `1234ms`, in
+/// seconds.
+fn unified_uptime(decoration: &[u8]) -> Option<Vec<u8>> {
+    if let Some(millis) = decoration.strip_suffix(MILLISECONDS_SUFFIX.as_bytes()) {
+        if millis.is_empty() || !millis.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        // written as seconds with a three-digit fraction
+        let mut padded =
+            vec![ASCII_ZERO; (DIGITS_IN_MILLISECONDS + 1).saturating_sub(millis.len())];
+        padded.extend_from_slice(millis);
+        let point = padded.len() - DIGITS_IN_MILLISECONDS;
+        padded.insert(point, ASCII_DECIMAL_POINT);
+        return Some(padded);
+    }
+    decoration
+        .strip_suffix(SECONDS_SUFFIX.as_bytes())
+        .filter(|seconds| is_seconds(seconds))
+        .map(|seconds| seconds.to_vec())
+}
+
+/// Whether `field` is a number of seconds with a fraction, such as `1234.567`.
+fn is_seconds(field: &[u8]) -> bool {
+    match field.iter().position(|&byte| byte == ASCII_DECIMAL_POINT) {
+        Some(point) => {
+            point > 0
+                && field.len() - point - 1 <= MAX_FRACTIONAL_DIGITS
+                && field[..point].iter().all(u8::is_ascii_digit)
+                && field[point + 1..].iter().all(u8::is_ascii_digit)
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{replace_epoch_timestamps_str_with_options, InputFormat};
+    use chrono::TimeZone;
+
+    fn replace(input: &str, start_time: Option<DateTime<Utc>>) -> String {
+        let options = ReplacerOptions::new().input_format(InputFormat::GcLog { start_time });
+        replace_epoch_timestamps_str_with_options(input, &options)
+    }
+
+    #[test]
+    fn convert_uptimes_from_a_start_time() {
+        let start_time = Utc.timestamp_opt(1530216070, 0).single();
+        assert_eq!(
+            "[2018-06-28 20:01:10.011 UTC][info][gc] GC(0) Pause Young 1530216070K->12M\n\
+             [2018-06-28 20:21:44.567 UTC]: [GC (Allocation Failure) [PSYoungGen: 1234.567K]]\n",
+            replace(
+                "[11ms][info][gc] GC(0) Pause Young 1530216070K->12M\n\
+                 1234.567: [GC (Allocation Failure) [PSYoungGen: 1234.567K]]\n",
+                start_time
+            )
+        );
+    }
+
+    #[test]
+    fn find_the_start_time_from_date_stamps() {
+        assert_eq!(
+            "[0.005s][info][gc] Using G1\n\
+             [2018-06-28T21:01:10.317+0100][2018-06-28 20:01:10.317 UTC][info][gc] GC(0)\n\
+             [2018-06-28 20:01:11.317 UTC][info][gc] GC(1)\n",
+            replace(
+                "[0.005s][info][gc] Using G1\n\
+                 [2018-06-28T21:01:10.317+0100][2.000s][info][gc] GC(0)\n\
+                 [3000ms][info][gc] GC(1)\n",
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn leave_oversized_uptimes_unchanged() {
+        let start_time = Utc.timestamp_opt(1530216070, 0).single();
+        let input = "99999999999999999999999.123: [GC\n9999999999.123: [GC\n";
+        assert_eq!(input, replace(input, start_time));
+        let input = "[2018-06-28T21:01:10.317+0100][9999999999999.000s][info][gc] GC(0)\n";
+        assert_eq!(input, replace(input, None));
+    }
+}
@@ -0,0 +1,160 @@
+use crate::compression::open_input;
+use crate::{find_matches, ReplacerOptions};
+use chrono::prelude::*;
+use std::collections::BTreeMap;
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io::{Read, Write};
+
+const SECONDS_PER_MINUTE: i64 = 60;
+const SECONDS_PER_HOUR: i64 = 3600;
+const SECONDS_PER_DAY: i64 = 86_400;
+/// The length of the bar of the fullest bucket in a bar chart.
+const MAX_BAR_WIDTH: u64 = 60;
+const BAR_CHARACTER: char = '#';
+
+/// The span of time counted in each bucket of a histogram. Buckets start at whole
+/// minutes, hours or days in UTC.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BucketSize {
+    Minute,
+    Hour,
+    Day,
+}
+
+impl BucketSize {
+    fn seconds(self) -> i64 {
+        match self {
+            BucketSize::Minute => SECONDS_PER_MINUTE,
+            BucketSize::Hour => SECONDS_PER_HOUR,
+            BucketSize::Day => SECONDS_PER_DAY,
+        }
+    }
+
+    fn label_format(self) -> &'static str {
+        match self {
+            BucketSize::Minute => "%Y-%m-%d %H:%M",
+            BucketSize::Hour => "%Y-%m-%d %H:00",
+            BucketSize::Day => "%Y-%m-%d",
+        }
+    }
+}
+
+/// Counts of the timestamps found in the input, by the bucket that they fall in.
+#[derive(Debug)]
+pub struct Histogram {
+    bucket_size: BucketSize,
+    /// The number of timestamps in each bucket, keyed by its start in epoch seconds.
+    counts: BTreeMap<i64, u64>,
+}
+
+impl Histogram {
+    pub fn new(bucket_size: BucketSize) -> Histogram {
+        Histogram {
+            bucket_size,
+            counts: BTreeMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, date_time: DateTime<Utc>) {
+        let seconds = self.bucket_size.seconds();
+        let bucket = date_time.timestamp().div_euclid(seconds) * seconds;
+        *self.counts.entry(bucket).or_insert(0) += 1;
+    }
+
+    /// Counts the timestamps that `options` would convert in `input`.
+    pub fn record_input(&mut self, input: &mut dyn Read, options: &ReplacerOptions) {
+        find_matches(input, options, |matches| {
+            for timestamp_match in matches {
+                self.record(timestamp_match.date_time);
+            }
+        });
+    }
+
+    /// Writes a line with the start and count of each bucket from the first timestamp
+    /// to the last, including empty buckets. With `bars` set, each count is followed
+    /// by a bar scaled to the largest count.
+    pub fn write(&self, output: &mut dyn Write, bars: bool) {
+        let (first, last) = match (self.counts.keys().next(), self.counts.keys().last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => return,
+        };
+        let max_count = self.counts.values().copied().max().unwrap_or(0);
+        let count_width = max_count.to_string().len();
+        let seconds = self.bucket_size.seconds();
+        for bucket in (first..=last).step_by(seconds as usize) {
+            let count = self.counts.get(&bucket).copied().unwrap_or(0);
+            let label = Utc
+                .timestamp_opt(bucket, 0)
+                .unwrap()
+                .format(self.bucket_size.label_format());
+            let mut line = format!("{} {:>2$}", label, count, count_width);
+            if bars && count > 0 {
+                // any count at all is shown by at least one character
+                let width = (count * MAX_BAR_WIDTH).div_ceil(max_count) as usize;
+                line.push(' ');
+                line.extend(std::iter::repeat_n(BAR_CHARACTER, width));
+            }
+            writeln!(output, "{}", line).expect("Failed to write");
+        }
+    }
+}
+
+/// Writes a histogram of the timestamps in the files, counted together, to standard
+/// output.
+#[cfg(feature = "fs")]
+pub fn histogram_files(
+    files: &[String],
+    options: &ReplacerOptions,
+    bucket_size: BucketSize,
+    bars: bool,
+) {
+    let mut histogram = Histogram::new(bucket_size);
+    for file_name in files {
+        let (mut input, _) =
+            open_input(File::open(file_name).unwrap()).expect("Error reading from input file");
+        histogram.record_input(&mut input, options);
+    }
+    histogram.write(&mut std::io::stdout().lock(), bars);
+}
+
+/// Writes a histogram of the timestamps in standard input to standard output.
+pub fn histogram_stdin(options: &ReplacerOptions, bucket_size: BucketSize, bars: bool) {
+    let (mut input, _) =
+        open_input(std::io::stdin().lock()).expect("Error reading from input file");
+    let mut histogram = Histogram::new(bucket_size);
+    histogram.record_input(&mut input, options);
+    histogram.write(&mut std::io::stdout().lock(), bars);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_timestamps_per_bucket() {
+        let input = "a 1530216070\nb 1530216130\nc 1530219600317\nd 1530223200 1530223259\n";
+        let mut histogram = Histogram::new(BucketSize::Hour);
+        histogram.record_input(&mut input.as_bytes(), &ReplacerOptions::new());
+        let mut output = Vec::new();
+        histogram.write(&mut output, false);
+        assert_eq!(
+            "2018-06-28 20:00 2\n2018-06-28 21:00 1\n2018-06-28 22:00 2\n",
+            String::from_utf8(output).unwrap()
+        );
+
+        let mut histogram = Histogram::new(BucketSize::Minute);
+        histogram.record_input(&mut input.as_bytes(), &ReplacerOptions::new());
+        let mut output = Vec::new();
+        histogram.write(&mut output, true);
+        let lines: Vec<String> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(120, lines.len());
+        assert_eq!(format!("2018-06-28 20:01 1 {}", "#".repeat(30)), lines[0]);
+        assert_eq!("2018-06-28 20:03 0", lines[2]);
+        assert_eq!(format!("2018-06-28 22:00 2 {}", "#".repeat(60)), lines[119]);
+    }
+}
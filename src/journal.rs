@@ -0,0 +1,210 @@
+use crate::formatter::append_custom_format;
+use crate::{
+    append_epoch_timestamp, parse_epoch_value, record_timestamp, replace_epoch_timestamps_in_state,
+    ReplacerOptions, ReplacerState, ASCII_EQUALS, ASCII_NEWLINE, ASCII_SPACE,
+};
+
+const BYTES_IN_LENGTH: usize = 8;
+
+/// The fields holding wallclock times, in microseconds since the epoch.
+const REALTIME_FIELDS: &[&str] = &["__REALTIME_TIMESTAMP", "_SOURCE_REALTIME_TIMESTAMP"];
+/// The field whose free-form text is searched for epoch timestamps.
+const MESSAGE_FIELD: &str = "MESSAGE";
+
+/// Progress through a journal export stream, carried from one line to the next.
+#[derive(Default)]
+pub(crate) struct JournalState {
+    /// The binary field whose length or data is being read, if any.
+    binary: Option<BinaryField>,
+}
+
+/// A field serialised as its name, a little-endian 64-bit length, the raw data and a
+/// line break, used by journald for values that are not single-line text.
+struct BinaryField {
+    is_message: bool,
+    length: Vec<u8>,
+    /// The data read so far, followed by the line break that ends it.
+    data: Vec<u8>,
+}
+
+/// Converts the realtime timestamps of records in `journalctl -o export` format, along
+/// with any epoch timestamps in their messages, writing every other field unchanged.
+/// The length of a binary message is rewritten to match its converted data.
+pub(crate) fn replace_journal_fields(
+    line: &[u8],
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let mut replaced = Vec::new();
+    let mut index = 0;
+    while index < line.len() {
+        let field = match &mut state.journal.binary {
+            Some(field) => field,
+            None => {
+                // the rest of the line is a text field, or the name of a binary field
+                append_text_field(&line[index..], options, state, &mut replaced);
+                break;
+            }
+        };
+        if field.length.len() < BYTES_IN_LENGTH {
+            let length_end = line.len().min(index + BYTES_IN_LENGTH - field.length.len());
+            field.length.extend_from_slice(&line[index..length_end]);
+            index = length_end;
+            continue;
+        }
+        let mut length_bytes = [0; BYTES_IN_LENGTH];
+        length_bytes.copy_from_slice(&field.length);
+        let length = u64::from_le_bytes(length_bytes) as usize;
+        let data_end = line.len().min(index + length + 1 - field.data.len());
+        field.data.extend_from_slice(&line[index..data_end]);
+        index = data_end;
+        if field.data.len() == length + 1 {
+            if let Some(field) = state.journal.binary.take() {
+                append_binary_field(field, length, options, state, &mut replaced);
+            }
+        }
+    }
+    replaced
+}
+
+fn append_text_field(
+    line: &[u8],
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+    append_buffer: &mut Vec<u8>,
+) {
+    let content = line.strip_suffix(&[ASCII_NEWLINE]).unwrap_or(line);
+    let (name, value) = match content.iter().position(|&byte| byte == ASCII_EQUALS) {
+        Some(equals) => (&content[..equals], &content[equals + 1..]),
+        None => {
+            if !content.is_empty() && content.len() < line.len() {
+                state.journal.binary = Some(BinaryField {
+                    is_message: content == MESSAGE_FIELD.as_bytes(),
+                    length: Vec::new(),
+                    data: Vec::new(),
+                });
+            }
+            pass_through(line, state, append_buffer);
+            return;
+        }
+    };
+    pass_through(&line[..name.len() + 1], state, append_buffer);
+    if name == MESSAGE_FIELD.as_bytes() {
+        append_buffer.extend(replace_epoch_timestamps_in_state(value, true, options, state).data);
+    } else if REALTIME_FIELDS.iter().any(|field| field.as_bytes() == name) {
+        append_realtime_value(value, options, state, append_buffer);
+    } else {
+        pass_through(value, state, append_buffer);
+    }
+    pass_through(&line[content.len()..], state, append_buffer);
+}
+
+fn append_realtime_value(
+    value: &[u8],
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+    append_buffer: &mut Vec<u8>,
+) {
+    let timestamp = match parse_epoch_value(value, options) {
+        Some(timestamp) => timestamp,
+        None => {
+            pass_through(value, state, append_buffer);
+            return;
+        }
+    };
+    record_timestamp(&timestamp, value, 0, options, state);
+    if !append_custom_format(&timestamp, value, options, append_buffer) {
+        if options.annotate {
+            append_buffer.extend_from_slice(value);
+            append_buffer.push(ASCII_SPACE);
+        }
+        append_epoch_timestamp(&timestamp, options, state, append_buffer);
+    }
+    if let Some(&byte) = value.last() {
+        state.preceding_byte = Some(byte);
+    }
+    state.offset += value.len() as u64;
+}
+
+fn append_binary_field(
+    field: BinaryField,
+    length: usize,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+    append_buffer: &mut Vec<u8>,
+) {
+    state.offset += BYTES_IN_LENGTH as u64;
+    let data = &field.data[..length];
+    let converted = if field.is_message {
+        replace_epoch_timestamps_in_state(data, true, options, state).data
+    } else {
+        state.offset += data.len() as u64;
+        data.to_vec()
+    };
+    append_buffer.extend_from_slice(&(converted.len() as u64).to_le_bytes());
+    append_buffer.extend_from_slice(&converted);
+    pass_through(&field.data[length..], state, append_buffer);
+}
+
+/// Writes `bytes` unchanged, accounting for them as consumed input.
+pub(crate) fn pass_through(bytes: &[u8], state: &mut ReplacerState, append_buffer: &mut Vec<u8>) {
+    append_buffer.extend_from_slice(bytes);
+    if let Some(&byte) = bytes.last() {
+        state.preceding_byte = Some(byte);
+    }
+    state.offset += bytes.len() as u64;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InputFormat, StreamingReplacer};
+
+    fn replace(input: &[u8]) -> Vec<u8> {
+        let options = ReplacerOptions::new().input_format(InputFormat::JournalExport);
+        let mut replacer = StreamingReplacer::with_options(options);
+        let mut output = Vec::new();
+        // chunks that split the binary length and data
+        for chunk in input.chunks(5) {
+            output.extend(replacer.push_chunk(chunk));
+        }
+        output.extend(replacer.finish());
+        output
+    }
+
+    #[test]
+    fn convert_realtime_timestamps_and_messages() {
+        assert_eq!(
+            "__CURSOR=s=1530216070317123\n\
+             __REALTIME_TIMESTAMP=[2018-06-28 20:01:10.317123 UTC]\n\
+             __MONOTONIC_TIMESTAMP=1530216070317123\n\
+             MESSAGE=started at [2018-06-28 20:01:10 UTC]\n\
+             \n",
+            String::from_utf8(replace(
+                b"__CURSOR=s=1530216070317123\n\
+                  __REALTIME_TIMESTAMP=1530216070317123\n\
+                  __MONOTONIC_TIMESTAMP=1530216070317123\n\
+                  MESSAGE=started at 1530216070\n\
+                  \n"
+            ))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn rewrite_the_length_of_binary_messages() {
+        let message = b"a\n1530216070";
+        let mut input = b"MESSAGE\n".to_vec();
+        input.extend_from_slice(&(message.len() as u64).to_le_bytes());
+        input.extend_from_slice(message);
+        input.extend_from_slice(b"\nPRIORITY=6\n");
+
+        let converted = b"a\n[2018-06-28 20:01:10 UTC]";
+        let mut expected = b"MESSAGE\n".to_vec();
+        expected.extend_from_slice(&(converted.len() as u64).to_le_bytes());
+        expected.extend_from_slice(converted);
+        expected.extend_from_slice(b"\nPRIORITY=6\n");
+
+        assert_eq!(expected, replace(&input));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::formatter::append_custom_format;
+use crate::stats::json_string;
+use crate::{
+    parse_epoch_value, record_timestamp, render_timestamp, EpochTimestamp, ReplacerOptions,
+    ReplacerState, ANSI_HIGHLIGHT, ANSI_RESET, ASCII_BACKSLASH, ASCII_COLON, ASCII_HYPHEN,
+    ASCII_QUOTE,
+};
+
+const NUMBER_CHARACTERS: &[u8] = b"0123456789+-.eE";
+
+/// Converts the epoch numbers in a line of JSON that are the value of one of `keys`.
+/// The line is scanned rather than parsed, so that everything other than the converted
+/// values is written back byte for byte, and a line that is not valid JSON is still
+/// converted as far as its key/value pairs can be recognised.
+pub(crate) fn replace_json_timestamps(
+    line: &[u8],
+    keys: &[String],
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let mut replaced = Vec::new();
+    // the most recent string, which is a key if it is followed by a colon
+    let mut last_string: Option<&[u8]> = None;
+    let mut key: Option<&[u8]> = None;
+    let mut index = 0;
+    while index < line.len() {
+        let byte = line[index];
+        if byte == ASCII_QUOTE {
+            let end = string_end(line, index);
+            replaced.extend_from_slice(&line[index..end]);
+            last_string = Some(&line[index + 1..(end - 1).max(index + 1)]);
+            key = None;
+            index = end;
+        } else if byte == ASCII_COLON && last_string.is_some() {
+            key = last_string.take();
+            replaced.push(byte);
+            index += 1;
+        } else if byte == ASCII_HYPHEN || byte.is_ascii_digit() {
+            let end = number_end(line, index);
+            let number = &line[index..end];
+            let is_timestamp_key =
+                key.is_some_and(|key| keys.iter().any(|name| name.as_bytes() == key));
+            match parse_epoch_value(number, options) {
+                Some(timestamp) if is_timestamp_key => {
+                    record_timestamp(&timestamp, number, index, options, state);
+                    if !append_custom_format(&timestamp, number, options, &mut replaced) {
+                        append_json_timestamp(&timestamp, options, state, &mut replaced);
+                    }
+                }
+                _ => replaced.extend_from_slice(number),
+            }
+            last_string = None;
+            key = None;
+            index = end;
+        } else {
+            if !byte.is_ascii_whitespace() {
+                last_string = None;
+                key = None;
+            }
+            replaced.push(byte);
+            index += 1;
+        }
+    }
+
+    if let Some(&byte) = line.last() {
+        state.preceding_byte = Some(byte);
+    }
+    state.offset += line.len() as u64;
+    replaced
+}
+
+/// Returns the index just past the string starting at `start`, or the end of the line
+/// if the string is not terminated.
+pub(crate) fn string_end(line: &[u8], start: usize) -> usize {
+    let mut index = start + 1;
+    while index < line.len() {
+        match line[index] {
+            ASCII_BACKSLASH => index += 2,
+            ASCII_QUOTE => return index + 1,
+            _ => index += 1,
+        }
+    }
+    line.len()
+}
+
+fn number_end(line: &[u8], start: usize) -> usize {
+    let mut end = start;
+    while end < line.len() && NUMBER_CHARACTERS.contains(&line[end]) {
+        end += 1;
+    }
+    end
+}
+
+fn append_json_timestamp(
+    timestamp: &EpochTimestamp,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+    append_buffer: &mut Vec<u8>,
+) {
+    // colour codes are kept outside the string, where they do not need escaping
+    if options.color {
+        append_buffer.extend_from_slice(ANSI_HIGHLIGHT);
+    }
+    let rendered = render_timestamp(timestamp, options, state);
+    append_buffer.extend_from_slice(json_string(&rendered).as_bytes());
+    if options.color {
+        append_buffer.extend_from_slice(ANSI_RESET);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{replace_epoch_timestamps_str_with_options, InputFormat};
+
+    fn replace(input: &str, options: ReplacerOptions) -> String {
+        replace_epoch_timestamps_str_with_options(input, &options.input_format(InputFormat::json()))
+    }
+
+    #[test]
+    fn convert_only_values_of_timestamp_keys() {
+        assert_eq!(
+            "{\"id\": 1530216070, \"ts\": \"[2018-06-28 20:01:10 UTC]\", \"msg\": \"ts 1530216070\"}\n\
+             {\"event\":{\"created_at\":\"[2018-06-28 20:01:10.317 UTC]\",\"count\":1530216070317}}",
+            replace(
+                "{\"id\": 1530216070, \"ts\": 1530216070, \"msg\": \"ts 1530216070\"}\n\
+                 {\"event\":{\"created_at\":1530216070317,\"count\":1530216070317}}",
+                ReplacerOptions::new()
+            )
+        );
+    }
+
+    #[test]
+    fn convert_aws_export_keys() {
+        let options = ReplacerOptions::new().preset("cloudwatch").unwrap();
+        let output = replace_epoch_timestamps_str_with_options(
+            "{\n  \"events\": [{\n    \"timestamp\": 1530216070317,\n    \"ingestionTime\": 1530216070318,\n    \"eventId\": 1530216070317\n  }]\n}\n\
+             {\"eventTime\":1530216070317,\"ts\":1530216070317}\n",
+            &options,
+        );
+        assert_eq!(
+            "{\n  \"events\": [{\n    \"timestamp\": \"[2018-06-28 20:01:10.317 UTC]\",\n    \"ingestionTime\": \"[2018-06-28 20:01:10.318 UTC]\",\n    \"eventId\": 1530216070317\n  }]\n}\n\
+             {\"eventTime\":\"[2018-06-28 20:01:10.317 UTC]\",\"ts\":1530216070317}\n",
+            output
+        );
+    }
+
+    #[test]
+    fn leave_other_values_of_timestamp_keys() {
+        let input = "{\"ts\": [1530216070], \"time\": \"1530216070\", \"expires\": 1.5e9, \"timestamp\": 42}";
+
+        assert_eq!(input, replace(input, ReplacerOptions::new()));
+    }
+
+    #[test]
+    fn escape_rendered_timestamps() {
+        assert_eq!(
+            "{\"ts\":\"\\\"2018-06-28\\\"\"}",
+            replace(
+                "{\"ts\":1530216070}",
+                ReplacerOptions::new()
+                    .format("\"%Y-%m-%d\"")
+                    .unwrap()
+                    .prefix("")
+                    .suffix("")
+            )
+        );
+    }
+}
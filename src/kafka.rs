@@ -0,0 +1,126 @@
+use crate::formatter::append_custom_format;
+use crate::journal::pass_through;
+use crate::{
+    append_epoch_timestamp, parse_epoch_value, record_timestamp, replace_epoch_timestamps_in_state,
+    ReplacerOptions, ReplacerState, ASCII_COLON, ASCII_SPACE, ASCII_TAB,
+};
+
+/// The names of the fields that kafka-console-consumer writes before the key and value,
+/// with `print.timestamp`, `print.partition`, `print.offset` and `print.headers`.
+const METADATA_FIELDS: &[&str] = &[
+    "CreateTime",
+    "LogAppendTime",
+    "Partition",
+    "Offset",
+    "Headers",
+];
+/// The fields whose value is the record's timestamp, in milliseconds since the epoch.
+const TIMESTAMP_FIELDS: &[&str] = &["CreateTime", "LogAppendTime"];
+
+/// Converts the `CreateTime:` or `LogAppendTime:` timestamp that starts a line of
+/// kafka-console-consumer output, and the epoch timestamps in the record's key and value
+/// if `payload` is set. The other metadata fields and the tabs between fields are
+/// written unchanged.
+pub(crate) fn replace_kafka_timestamps(
+    line: &[u8],
+    payload: bool,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let mut replaced = Vec::new();
+    let mut index = 0;
+    while let Some(colon) = metadata_name_end(&line[index..]) {
+        let name = &line[index..index + colon];
+        let value_start = index + colon + 1;
+        let value_end = line[value_start..]
+            .iter()
+            .position(u8::is_ascii_whitespace)
+            .map_or(line.len(), |end| value_start + end);
+        pass_through(&line[index..value_start], state, &mut replaced);
+        let value = &line[value_start..value_end];
+        let timestamp = if TIMESTAMP_FIELDS
+            .iter()
+            .any(|field| field.as_bytes() == name)
+        {
+            parse_epoch_value(value, options)
+        } else {
+            None
+        };
+        match timestamp {
+            Some(timestamp) => {
+                record_timestamp(&timestamp, value, 0, options, state);
+                if !append_custom_format(&timestamp, value, options, &mut replaced) {
+                    if options.annotate {
+                        replaced.extend_from_slice(value);
+                        replaced.push(ASCII_SPACE);
+                    }
+                    append_epoch_timestamp(&timestamp, options, state, &mut replaced);
+                }
+                state.preceding_byte = value.last().copied();
+                state.offset += value.len() as u64;
+            }
+            None => pass_through(value, state, &mut replaced),
+        }
+        index = value_end;
+        if line.get(index) != Some(&ASCII_TAB) {
+            break;
+        }
+        pass_through(&line[index..index + 1], state, &mut replaced);
+        index += 1;
+    }
+    if payload {
+        replaced
+            .extend(replace_epoch_timestamps_in_state(&line[index..], true, options, state).data);
+    } else {
+        pass_through(&line[index..], state, &mut replaced);
+    }
+    replaced
+}
+
+/// Returns the position of the colon after the name of the metadata field that starts
+/// `field`, if it is one.
+fn metadata_name_end(field: &[u8]) -> Option<usize> {
+    METADATA_FIELDS
+        .iter()
+        .find(|name| {
+            field.starts_with(name.as_bytes()) && field.get(name.len()) == Some(&ASCII_COLON)
+        })
+        .map(|name| name.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replace_epoch_timestamps_str_with_options;
+
+    fn replace(input: &str, preset: &str) -> String {
+        let options = ReplacerOptions::new().preset(preset).unwrap();
+        replace_epoch_timestamps_str_with_options(input, &options)
+    }
+
+    #[test]
+    fn convert_record_timestamps() {
+        assert_eq!(
+            "CreateTime:[2018-06-28 20:01:10.317 UTC]\tPartition:0\tOffset:1530216070\torder-1530216070\t{\"at\":1530216070}\n\
+             LogAppendTime:[2018-06-28 20:01:10.317 UTC]\tnull\t1530216070\n\
+             NO_TIMESTAMP\tnull\t1530216070\n",
+            replace(
+                "CreateTime:1530216070317\tPartition:0\tOffset:1530216070\torder-1530216070\t{\"at\":1530216070}\n\
+                 LogAppendTime:1530216070317\tnull\t1530216070\n\
+                 NO_TIMESTAMP\tnull\t1530216070\n",
+                "kafka"
+            )
+        );
+    }
+
+    #[test]
+    fn convert_payload_timestamps() {
+        assert_eq!(
+            "CreateTime:[2018-06-28 20:01:10.317 UTC]\tnull\t{\"at\":[2018-06-28 20:01:10 UTC]}\n",
+            replace(
+                "CreateTime:1530216070317\tnull\t{\"at\":1530216070}\n",
+                "kafka-payload"
+            )
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use crate::compression::open_input;
+use crate::{ReplacerOptions, StreamingReplacer, BUFFER_SIZE};
+use std::fs::File;
+use std::io::{Read, Write};
+
+/// The number of bytes at the start of a file in which a NUL marks it as binary.
+const BINARY_CHECK_LENGTH: u64 = 8192;
+const ASCII_NUL: u8 = 0;
+
+/// Writes a converted copy of a file to `output` as a LESSOPEN input pipe, so that `less`
+/// pages the file with its timestamps converted. The file is never modified, and
+/// compressed files are decompressed. Nothing is written for a file that cannot be read
+/// or that looks binary, which `less` then shows as it would without a preprocessor, and
+/// conversion stops quietly once `less` stops reading.
+pub fn less_preprocess(file_name: &str, options: &ReplacerOptions, output: &mut dyn Write) {
+    let file = match File::open(file_name) {
+        Ok(file) if file.metadata().is_ok_and(|metadata| metadata.is_file()) => file,
+        _ => return,
+    };
+    let (mut input, _) = match open_input(file) {
+        Ok(input) => input,
+        Err(_) => return,
+    };
+    let mut head = Vec::new();
+    if input
+        .by_ref()
+        .take(BINARY_CHECK_LENGTH)
+        .read_to_end(&mut head)
+        .is_err()
+        || head.contains(&ASCII_NUL)
+    {
+        return;
+    }
+    let mut replacer = StreamingReplacer::with_options(options.clone());
+    // an error writing means that less has quit, and one reading that the file changed
+    let _ = page(&head, &mut input, &mut replacer, output);
+}
+
+fn page(
+    head: &[u8],
+    input: &mut dyn Read,
+    replacer: &mut StreamingReplacer,
+    output: &mut dyn Write,
+) -> std::io::Result<()> {
+    output.write_all(&replacer.push_chunk(head))?;
+    let mut read_buffer = [0; BUFFER_SIZE];
+    loop {
+        let read_length = input.read(&mut read_buffer)?;
+        if read_length == 0 {
+            break;
+        }
+        output.write_all(&replacer.push_chunk(&read_buffer[..read_length]))?;
+    }
+    output.write_all(&replacer.finish())?;
+    output.flush()
+}
+
+/// The shell command that sets LESSOPEN to run the nail `executable` with `options` on
+/// each file that `less` opens.
+pub fn lessopen_stanza(executable: &str, options: &[String]) -> String {
+    let pipe: Vec<String> = std::iter::once(executable)
+        .chain(std::iter::once("lessopen"))
+        .chain(options.iter().map(String::as_str))
+        .chain(std::iter::once("%s"))
+        .map(shell_quote)
+        .collect();
+    format!(
+        "export LESSOPEN='|{}'",
+        pipe.join(" ").replace('\'', "'\\''")
+    )
+}
+
+/// Quotes `word` for a POSIX shell, unless it is made only of characters that need none.
+fn shell_quote(word: &str) -> String {
+    let is_plain =
+        |character: char| character.is_ascii_alphanumeric() || "_-+=.,:/@%".contains(character);
+    if !word.is_empty() && word.chars().all(is_plain) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn convert_text_files_but_not_binary_ones() {
+        let file_name = format!("/tmp/{:?}-less.log", Instant::now());
+        let mut output = Vec::new();
+        std::fs::write(&file_name, "a 1530216070\n").unwrap();
+        less_preprocess(&file_name, &ReplacerOptions::new(), &mut output);
+        assert_eq!(b"a [2018-06-28 20:01:10 UTC]\n".to_vec(), output);
+
+        let mut output = Vec::new();
+        std::fs::write(&file_name, b"\x7fELF\x00 1530216070\n").unwrap();
+        less_preprocess(&file_name, &ReplacerOptions::new(), &mut output);
+        assert!(output.is_empty());
+
+        assert_eq!(
+            "export LESSOPEN='|/usr/bin/nail lessopen --tz '\\''America/New York'\\'' %s'",
+            lessopen_stanza(
+                "/usr/bin/nail",
+                &["--tz".to_string(), "America/New York".to_string()]
+            )
+        );
+    }
+}
@@ -1,28 +1,227 @@
+use crate::access_log::replace_access_log_timestamps;
+use crate::cef::replace_cef_timestamps;
+use crate::compression::open_input;
+#[cfg(feature = "fs")]
+use crate::compression::{strip_extension, CompressedWriter};
+use crate::confidence::confidence;
+use crate::csv::{replace_csv_timestamps, CsvState};
+use crate::detector::replace_detected_timestamps;
+use crate::dmesg::replace_dmesg_timestamps;
+use crate::duration::{append_duration, scan_duration, DurationCandidate};
+use crate::exclusion::is_excluded;
+use crate::formatter::append_custom_format;
+use crate::gc_log::{replace_gc_log_timestamps, GcLogState};
+use crate::journal::{replace_journal_fields, JournalState};
+use crate::json::replace_json_timestamps;
+use crate::jwt::{append_jwt_claims, scan_jwt, JwtCandidate};
+use crate::kafka::replace_kafka_timestamps;
+use crate::line_prefix::replace_line_prefix_timestamps;
+use crate::logfmt::replace_logfmt_timestamps;
+use crate::normalize::normalize_datetimes;
+use crate::packet::replace_packet_timestamps;
+use crate::prometheus::replace_prometheus_timestamps;
+use crate::report::MatchReport;
+use crate::sql::{replace_sql_timestamps, SqlState};
+use crate::syslog::replace_syslog_timestamps;
+use crate::utf8::LossyDecoder;
+use crate::zeek::{replace_zeek_timestamps, ZeekState};
 use chrono::prelude::*;
+use std::borrow::Cow;
+#[cfg(feature = "fs")]
 use std::fs::*;
 use std::io::Read;
 use std::io::Write;
-use std::ops::Rem;
-use std::time::*;
+use std::ops::{RangeInclusive, Rem};
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+
+mod access_log;
+#[cfg(feature = "tokio")]
+mod async_io;
+mod cef;
+mod compression;
+mod confidence;
+mod csv;
+mod detector;
+mod dmesg;
+mod duration;
+mod exclusion;
+mod extract;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod find;
+#[cfg(feature = "fs")]
+mod follow;
+mod formatter;
+mod gaps;
+mod gc_log;
+mod histogram;
+mod journal;
+mod json;
+mod jwt;
+mod kafka;
+#[cfg(feature = "fs")]
+mod lessopen;
+mod line_prefix;
+mod logfmt;
+mod merge;
+mod normalize;
+mod options;
+mod packet;
+mod prometheus;
+mod rate;
+mod reader;
+mod replacer;
+mod report;
+mod reverse;
+mod sessions;
+#[cfg(feature = "fs")]
+mod split;
+mod sql;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod stats;
+mod syslog;
+mod template;
+mod utf8;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod writer;
+mod zeek;
+
+#[cfg(feature = "tokio")]
+pub use crate::async_io::{AsyncDepochReader, AsyncDepochWriter};
+pub use crate::compression::Compression;
+pub use crate::detector::{DetectedTimestamp, TimestampDetector};
+#[cfg(feature = "fs")]
+pub use crate::dmesg::system_boot_time;
+#[cfg(feature = "fs")]
+pub use crate::extract::extract_files;
+pub use crate::extract::extract_stdin;
+pub use crate::find::{
+    find_epoch_timestamps, find_epoch_timestamps_with_options, EpochTimestamps, Match,
+    TimestampKind,
+};
+#[cfg(feature = "fs")]
+pub use crate::follow::{follow_file, Follower};
+pub use crate::formatter::TimestampFormatter;
+#[cfg(feature = "fs")]
+pub use crate::gaps::find_gaps_in_files;
+pub use crate::gaps::{find_gaps_in_stdin, parse_duration, parse_signed_duration, Gap, GapFinder};
+#[cfg(feature = "fs")]
+pub use crate::histogram::histogram_files;
+pub use crate::histogram::{histogram_stdin, BucketSize, Histogram};
+#[cfg(feature = "fs")]
+pub use crate::lessopen::{less_preprocess, lessopen_stanza};
+#[cfg(feature = "fs")]
+pub use crate::merge::{check_order_files, merge_files, merge_files_with_skew, sort_files};
+pub use crate::merge::{check_order_stdin, sort_stdin, OrderCheck, SkewMap};
+pub use crate::options::{
+    parse_instant, AccessLogServer, BoundaryPolicy, CsvColumn, EpochClassification, InputFormat,
+    InvalidUtf8, OutputStyle, OutputZone, QuotedTimestamps, ReplacerOptions, Rounding, TimeUnit,
+    TimestampMode, DEFAULT_DURATION_KEY_PREFIXES, DEFAULT_IDENTIFIER_KEY_PREFIXES,
+    DEFAULT_KEY_PREFIXES, DISCORD_SNOWFLAKE_EPOCH, LDAP_TIMESTAMP_ATTRIBUTES,
+    TWITTER_SNOWFLAKE_EPOCH,
+};
+#[cfg(feature = "fs")]
+pub use crate::rate::rate_files;
+pub use crate::rate::{rate_stdin, RateCounter, RateFormat};
+pub use crate::reader::DepochReader;
+pub use crate::replacer::{Replacer, ReplacerBuilder};
+pub use crate::report::ReportFormat;
+pub use crate::reverse::replace_datetimes;
+#[cfg(feature = "fs")]
+pub use crate::sessions::split_sessions_in_files;
+pub use crate::sessions::{split_sessions_in_stdin, SessionOutput};
+#[cfg(feature = "fs")]
+pub use crate::split::{split_files_by, split_stdin_by, SplitPeriod};
+#[cfg(feature = "sqlite")]
+pub use crate::sqlite::{convert_sqlite_table, SqliteOutput};
+pub use crate::stats::Stats;
+#[cfg(feature = "wasm")]
+pub use crate::wasm::{replace, Options};
+pub use crate::writer::DepochWriter;
 
 const DIGITS_IN_EPOCH_SECOND_TIMESTAMP: usize = 10;
 const DIGITS_IN_EPOCH_MILLISECOND_TIMESTAMP: usize = 13;
+const DIGITS_IN_EPOCH_MICROSECOND_TIMESTAMP: usize = 16;
+/// The fewest digits of an epoch classified by its magnitude: seconds before 2001-09-09.
+const MIN_MAGNITUDE_DIGITS: usize = 9;
+/// FILETIME, LDAP and .NET timestamps count 100-nanosecond ticks, in 18 digits.
+const DIGITS_IN_TICK_COUNT: usize = 18;
+const DIGITS_IN_WEBKIT_TIMESTAMP: usize = 17;
+const HEX_DIGITS_IN_TAI64N_LABEL: [usize; 2] = [24, 32];
+/// The TAI64 label of the TAI second at the Unix epoch is this value plus 10, as TAI
+/// was 10 seconds ahead of UTC in 1970; daemontools ignores later leap seconds.
+const TAI64_LABEL_BASE: u64 = 1 << 62;
+const TAI_OFFSET_SECONDS: i64 = 10;
+/// The seconds from the FILETIME and WebKit epoch, 1601-01-01, to the Unix epoch.
+const FILETIME_EPOCH_OFFSET_SECONDS: i64 = 11_644_473_600;
+/// The seconds from the .NET epoch, 0001-01-01, to the Unix epoch.
+const DOTNET_EPOCH_OFFSET_SECONDS: i64 = 62_135_596_800;
+const FILETIME_TICKS_PER_SECOND: i64 = 10_000_000;
+const NANOS_PER_FILETIME_TICK: i64 = 100;
+const DIGITS_IN_SNOWFLAKE_ID: std::ops::RangeInclusive<usize> = 18..=19;
+const SNOWFLAKE_TIMESTAMP_SHIFT: u32 = 22;
+const HEX_DIGITS_IN_EPOCH_SECOND_TIMESTAMP: usize = 8;
+const HEX_DIGITS_IN_OBJECT_ID: usize = 24;
+/// The length of the ` +0100` that follows the epoch seconds in git's raw dates.
+const GIT_OFFSET_LENGTH: usize = 6;
+const DIGITS_IN_GROUP: usize = 3;
+const ULID_LENGTH: usize = 26;
+const ULID_TIMESTAMP_LENGTH: usize = 10;
+const CROCKFORD_BASE32_ALPHABET: &str = "0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const UUID_LENGTH: usize = 36;
+const UUID_HYPHEN_POSITIONS: [usize; 4] = [8, 13, 18, 23];
+const UUID_VERSION_POSITION: usize = 14;
+const UUID_VARIANT_POSITION: usize = 19;
+const HEX_DIGITS_IN_EPOCH_MILLISECOND_TIMESTAMP: std::ops::RangeInclusive<usize> = 11..=12;
 const NANOS_PER_MILLISECOND: i64 = 1_000_000;
+const NANOS_PER_MICROSECOND: i64 = 1_000;
+const MAX_FRACTIONAL_DIGITS: usize = 9;
+/// The most digits of whole seconds elapsed since an anchor, which always fit in an i64.
+const MAX_ELAPSED_SECONDS_DIGITS: usize = 18;
+const NANOS_PER_SECOND: u32 = 1_000_000_000;
 const BUFFER_SIZE: usize = 1024;
+const ANSI_HIGHLIGHT: &[u8] = b"\x1b[36m";
+const ANSI_RESET: &[u8] = b"\x1b[0m";
 const ASCII_ZERO: u8 = 48;
+const ASCII_SEVEN: u8 = 55;
 const ASCII_LOWERCASE_A: u8 = 97;
 const ASCII_UPPERCASE_A: u8 = 65;
 const ASCII_LOWERCASE_F: u8 = 102;
 const ASCII_UPPERCASE_F: u8 = 70;
 const ASCII_LOWERCASE_X: u8 = 120;
+const ASCII_LOWERCASE_E: u8 = 101;
+const ASCII_LOWERCASE_L: u8 = 108;
+const ASCII_UPPERCASE_E: u8 = 69;
+const ASCII_UPPERCASE_L: u8 = 76;
+const ASCII_DECIMAL_POINT: u8 = 46;
+const ASCII_SPACE: u8 = 32;
+const ASCII_PLUS: u8 = 43;
+const ASCII_HYPHEN: u8 = 45;
+const ASCII_OPEN_BRACKET: u8 = 91;
+const ASCII_CLOSE_BRACKET: u8 = 93;
+const ASCII_NEWLINE: u8 = 10;
+const ASCII_AT: u8 = 64;
+const ASCII_QUOTE: u8 = 34;
+const ASCII_APOSTROPHE: u8 = 39;
+const ASCII_TAB: u8 = 9;
+const ASCII_HASH: u8 = 35;
+const ASCII_CLOSE_PAREN: u8 = 41;
+const ASCII_COMMA: u8 = 44;
+const ASCII_SLASH: u8 = 47;
+const ASCII_COLON: u8 = 58;
+const ASCII_EQUALS: u8 = 61;
+const ASCII_BACKSLASH: u8 = 92;
+const ASCII_OPEN_BRACE: u8 = 123;
+const ASCII_CLOSE_BRACE: u8 = 125;
 
 
 pub fn enhex(tokens: &[String]) {
     for token in tokens {
-        let mut tmp = Vec::new();
-        tmp.extend_from_slice(token.as_bytes());
         // TODO validate that all chars are ascii_digits
-        println!("{}", to_hex_chars(ascii_to_integer(&mut tmp)))
+        println!("{}", to_hex_chars(ascii_to_integer(token.as_bytes())))
     }
 }
 
@@ -37,18 +236,20 @@ fn to_hex_chars(input: u64) -> String {
     format!("0x{:x}", input)
 }
 
-fn to_decimal_chars(input: &String) -> String {
-    let mut value: u64 = 0;
+fn to_decimal_chars(input: &str) -> String {
     let chars = input.as_bytes();
     let start = if has_hex_indicator(chars) { 2 } else { 0 };
-    for i in start..chars.len() {
+    format!("{}", hex_to_integer(&chars[start..]))
+}
+
+fn hex_to_integer(hex_chars: &[u8]) -> u64 {
+    let mut value: u64 = 0;
+    for &hex_char in hex_chars {
         value *= 16;
-        let index = i;
-        let hex_char = chars[index];
-        if hex_char >= ASCII_UPPERCASE_A && hex_char <= ASCII_UPPERCASE_F {
+        if (ASCII_UPPERCASE_A..=ASCII_UPPERCASE_F).contains(&hex_char) {
             let v = 10 + (hex_char - ASCII_UPPERCASE_A) as u64;
             value += v
-        } else if hex_char >= ASCII_LOWERCASE_A && hex_char <= ASCII_LOWERCASE_F {
+        } else if (ASCII_LOWERCASE_A..=ASCII_LOWERCASE_F).contains(&hex_char) {
             let v = 10 + (hex_char - ASCII_LOWERCASE_A) as u64;
             value += v
         } else {
@@ -56,243 +257,2561 @@ fn to_decimal_chars(input: &String) -> String {
             value += v
         }
     }
-    return format!("{}", value)
+    value
 }
 
 fn has_hex_indicator(chars: &[u8]) -> bool {
     chars.len() > 1 && chars[0] == ASCII_ZERO && chars[1] == ASCII_LOWERCASE_X
 }
 
-fn hex_ascii_to_integer(integer_accumulator: &mut Vec<u8>) -> u64 {
+fn ascii_to_integer(digits: &[u8]) -> u64 {
     let mut value: u64 = 0;
-    integer_accumulator.reverse();
-    loop {
-        if let Some(next) = integer_accumulator.pop() {
-            value *= 10;
-            value += (next - ASCII_ZERO) as u64
-        } else {
-            break;
-        }
+    for &next in digits {
+        value *= 10;
+        value += (next - ASCII_ZERO) as u64
     }
     value
 }
 
-fn ascii_to_integer(integer_accumulator: &mut Vec<u8>) -> u64 {
-    let mut value: u64 = 0;
-    integer_accumulator.reverse();
-    loop {
-        if let Some(next) = integer_accumulator.pop() {
-            value *= 10;
-            value += (next - ASCII_ZERO) as u64
-        } else {
-            break;
+
+/// The direction in which timestamps are converted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Conversion {
+    /// Replace epoch values with human-readable datetimes.
+    ToDatetime,
+    /// Replace human-readable datetimes with epoch values in the given unit.
+    ToEpoch(EpochUnit),
+    /// Replace human-readable datetimes in any recognised format with the configured
+    /// style and zone.
+    Normalize,
+    /// Replace human-readable datetimes that carry an offset or zone with the configured
+    /// style and zone, leaving those without one unchanged.
+    ToZone,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EpochUnit {
+    Seconds,
+    Milliseconds,
+    Microseconds,
+}
+
+/// Converts each file into a sibling file with a `.depoch` (or, in reverse, `.epoch`)
+/// suffix. Compressed files are decompressed as they are read; the output is
+/// compressed in the same format only if the options request it.
+#[cfg(feature = "fs")]
+pub fn process_files(files: &[String], options: &ReplacerOptions) -> Vec<Stats> {
+    convert_files(files, options, None)
+}
+
+/// Converts each file in turn to standard output, for reading rather than keeping.
+#[cfg(feature = "fs")]
+pub fn process_files_to_stdout(files: &[String], options: &ReplacerOptions) -> Vec<Stats> {
+    let stdout = std::io::stdout();
+    let mut stdout_lock = stdout.lock();
+    let mut read_buffer = [0; BUFFER_SIZE];
+    files
+        .iter()
+        .map(|file_name| {
+            let (mut input, _) =
+                open_input(File::open(file_name).unwrap()).expect("Error reading from input file");
+            let mut replacer = StreamingReplacer::with_options(options.clone());
+            process_input(
+                &mut input,
+                &mut stdout_lock,
+                &mut read_buffer,
+                &mut replacer,
+                false,
+                None,
+            )
+        })
+        .collect()
+}
+
+/// As `process_files`, also writing a record of each converted timestamp to `report`.
+#[cfg(feature = "fs")]
+pub fn process_files_with_report(
+    files: &[String],
+    options: &ReplacerOptions,
+    format: ReportFormat,
+    report: &mut dyn Write,
+) -> Vec<Stats> {
+    convert_files(files, options, Some((format, report)))
+}
+
+#[cfg(feature = "fs")]
+fn convert_files(
+    files: &[String],
+    options: &ReplacerOptions,
+    mut report: Option<(ReportFormat, &mut dyn Write)>,
+) -> Vec<Stats> {
+    let mut read_buffer = [0; BUFFER_SIZE];
+    let mut all_stats = Vec::new();
+    for file_name in files {
+        let (mut input, compression) =
+            open_input(File::open(file_name).unwrap()).expect("Error reading from input file");
+        let mut output = create_output(file_name, compression, options);
+        let mut replacer = StreamingReplacer::with_options(options.clone());
+        let mut match_report = report
+            .as_mut()
+            .map(|(format, report)| MatchReport::new(file_name, *format, &mut **report));
+        all_stats.push(process_input(
+            &mut input,
+            &mut output,
+            &mut read_buffer,
+            &mut replacer,
+            false,
+            match_report.as_mut(),
+        ));
+        output.finish().expect("Failed to write");
+    }
+    all_stats
+}
+
+/// Rewrites each file with its timestamps converted. The output is written to a
+/// temporary file alongside the original, synced, and then renamed over it, so the
+/// original is never left partially rewritten. When `backup_suffix` is given the
+/// original content is kept in a file with that suffix appended to its name.
+#[cfg(feature = "fs")]
+pub fn process_files_in_place(
+    files: &[String],
+    options: &ReplacerOptions,
+    backup_suffix: Option<&str>,
+) -> Vec<Stats> {
+    let mut read_buffer = [0; BUFFER_SIZE];
+    let mut all_stats = Vec::new();
+    for file_name in files {
+        let path = Path::new(file_name);
+        let temp_path = temporary_path(path);
+        let file = File::open(path).unwrap();
+        let permissions = file.metadata().unwrap().permissions();
+        // the file keeps its name, so it also keeps its compression format
+        let (mut input, compression) = open_input(file).expect("Error reading from input file");
+        let temp_file = OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .open(&temp_path)
+            .expect("Failed to create temporary file");
+        let mut output =
+            CompressedWriter::new(temp_file, compression).expect("Failed to create temporary file");
+        let mut replacer = StreamingReplacer::with_options(options.clone());
+        all_stats.push(process_input(
+            &mut input,
+            &mut output,
+            &mut read_buffer,
+            &mut replacer,
+            false,
+            None,
+        ));
+        let temp_file = output.finish().expect("Failed to write");
+        temp_file
+            .set_permissions(permissions)
+            .expect("Failed to copy file permissions");
+        temp_file.sync_all().expect("Failed to sync temporary file");
+
+        if let Some(suffix) = backup_suffix {
+            let backup_path = PathBuf::from(file_name.to_string() + suffix);
+            if backup_path.exists() {
+                remove_file(&backup_path).expect("Failed to remove old backup file");
+            }
+            // a hard link keeps the original content without copying it, and
+            // survives the rename below
+            if hard_link(path, &backup_path).is_err() {
+                copy(path, &backup_path).expect("Failed to create backup file");
+            }
         }
+        rename(&temp_path, path).expect("Failed to replace file");
     }
-    value
+    all_stats
+}
+
+/// Creates the file that the conversion of `file_name` is written to.
+#[cfg(feature = "fs")]
+fn create_output(
+    file_name: &str,
+    compression: Compression,
+    options: &ReplacerOptions,
+) -> CompressedWriter<File> {
+    open_output(file_name, compression, options, false)
+}
+
+/// Opens the file that the conversion of `file_name` is written to, appending to it
+/// rather than truncating it if `append` is set. Compressed output is appended as a
+/// further compressed stream, which decompresses as a continuation of the first.
+#[cfg(feature = "fs")]
+fn open_output(
+    file_name: &str,
+    compression: Compression,
+    options: &ReplacerOptions,
+    append: bool,
+) -> CompressedWriter<File> {
+    let suffix = match options.conversion {
+        Conversion::ToDatetime => ".depoch",
+        Conversion::ToEpoch(_) => ".epoch",
+        Conversion::Normalize => ".normalized",
+        Conversion::ToZone => ".rezoned",
+    };
+    let output_compression = if options.compress_output {
+        compression
+    } else {
+        Compression::None
+    };
+    let target_file_name = strip_extension(file_name, compression).to_string()
+        + suffix
+        + output_compression.extension();
+    let target_file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(target_file_name)
+        .unwrap();
+    CompressedWriter::new(target_file, output_compression).expect("Failed to create output file")
+}
+
+#[cfg(feature = "fs")]
+fn temporary_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .expect("Not a file")
+        .to_string_lossy()
+        .to_string();
+    path.with_file_name(format!(".{}.{}.nail-tmp", file_name, std::process::id()))
+}
+
+/// Converts standard input to standard output as data arrives, for use in pipelines.
+pub fn process_stdin(options: &ReplacerOptions) -> Stats {
+    convert_stdin(options, None)
 }
 
+/// As `process_stdin`, also writing a record of each converted timestamp to `report`.
+pub fn process_stdin_with_report(
+    options: &ReplacerOptions,
+    format: ReportFormat,
+    report: &mut dyn Write,
+) -> Stats {
+    let mut match_report = MatchReport::new("(standard input)", format, report);
+    convert_stdin(options, Some(&mut match_report))
+}
 
-pub fn process_files(files: &[String]) {
-    let mut options = OpenOptions::new();
-    options.read(true);
+fn convert_stdin(options: &ReplacerOptions, report: Option<&mut MatchReport>) -> Stats {
+    let (mut input, _) =
+        open_input(std::io::stdin().lock()).expect("Error reading from input file");
+    let stdout = std::io::stdout();
+    let mut stdout_lock = stdout.lock();
     let mut read_buffer = [0; BUFFER_SIZE];
+    let mut replacer = StreamingReplacer::with_options(options.clone());
+
+    process_input(
+        &mut input,
+        &mut stdout_lock,
+        &mut read_buffer,
+        &mut replacer,
+        true,
+        report,
+    )
+}
+
+/// Lists the timestamps that would be converted in each file, without writing any
+/// output files.
+#[cfg(feature = "fs")]
+pub fn dry_run_files(files: &[String], options: &ReplacerOptions) {
+    report_files(files, options, ReportFormat::Text);
+}
+
+/// Lists the timestamps that would be converted in standard input.
+pub fn dry_run_stdin(options: &ReplacerOptions) {
+    report_stdin(options, ReportFormat::Text);
+}
+
+/// Writes a record of each timestamp that would be converted in each file to standard
+/// output, without writing any output files.
+#[cfg(feature = "fs")]
+pub fn report_files(files: &[String], options: &ReplacerOptions, format: ReportFormat) {
+    let stdout = std::io::stdout();
+    let mut stdout_lock = stdout.lock();
     for file_name in files {
-        let target_file_name = file_name.to_string() + &".depoch".to_string();
-        let mut file = options.open(file_name).unwrap();
-        let mut target_file = options
-            .create(true)
-            .write(true)
-            .open(target_file_name)
-            .unwrap();
-        process_input(&mut file, &mut target_file, &mut read_buffer)
+        let (mut input, _) =
+            open_input(File::open(file_name).unwrap()).expect("Error reading from input file");
+        let mut report = MatchReport::new(file_name, format, &mut stdout_lock);
+        report_matches(&mut input, &mut report, options);
     }
 }
 
-pub fn process_stdin() {
-    let stdin = std::io::stdin();
-    let mut stdin_lock = stdin.lock();
+/// Writes a record of each timestamp that would be converted in standard input to
+/// standard output.
+pub fn report_stdin(options: &ReplacerOptions, format: ReportFormat) {
+    let (mut input, _) =
+        open_input(std::io::stdin().lock()).expect("Error reading from input file");
     let stdout = std::io::stdout();
     let mut stdout_lock = stdout.lock();
+    let mut report = MatchReport::new("(standard input)", format, &mut stdout_lock);
+    report_matches(&mut input, &mut report, options);
+}
+
+fn report_matches(input: &mut dyn Read, report: &mut MatchReport, options: &ReplacerOptions) {
     let mut read_buffer = [0; BUFFER_SIZE];
+    let mut replacer = StreamingReplacer::with_options(options.clone()).recording_matches();
+    loop {
+        let read_length = input
+            .read(&mut read_buffer)
+            .expect("Error reading from input file");
+        if read_length == 0 {
+            break;
+        }
+        report.record_chunk(&read_buffer[..read_length]);
+        replacer.push_chunk(&read_buffer[..read_length]);
+        report.write_matches(&mut replacer);
+    }
+    replacer.finish();
+    report.write_matches(&mut replacer);
+    report.flush();
+}
 
-    process_input(&mut stdin_lock, &mut stdout_lock, &mut read_buffer)
+/// Finds the timestamps in `input` without writing any output, passing those found in
+/// each chunk to `on_matches` as they are found.
+fn find_matches(
+    input: &mut dyn Read,
+    options: &ReplacerOptions,
+    mut on_matches: impl FnMut(Vec<TimestampMatch>),
+) {
+    let mut read_buffer = [0; BUFFER_SIZE];
+    let mut replacer = StreamingReplacer::with_options(options.clone()).recording_matches();
+    loop {
+        let read_length = input
+            .read(&mut read_buffer)
+            .expect("Error reading from input file");
+        if read_length == 0 {
+            break;
+        }
+        replacer.push_chunk(&read_buffer[..read_length]);
+        on_matches(replacer.take_matches());
+    }
+    replacer.finish();
+    on_matches(replacer.take_matches());
 }
 
-fn process_input(input: &mut Read, output: &mut Write, read_buffer: &mut [u8]) {
-    let mut data_buffer = Vec::new();
-    let mut tmp_buffer = Vec::new();
+/// Converts all of `input` into `output`. With `flush_each_chunk` set, output is
+/// flushed as soon as each chunk is converted, so that output from a live pipe is
+/// not held back. Each converted timestamp is also written to `report`, if given.
+fn process_input(
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+    read_buffer: &mut [u8],
+    replacer: &mut StreamingReplacer,
+    flush_each_chunk: bool,
+    mut report: Option<&mut MatchReport>,
+) -> Stats {
+    let start = std::time::Instant::now();
+    if report.is_some() {
+        replacer.state.matches = Some(Vec::new());
+    }
     loop {
         let read_length = input
             .read(read_buffer)
             .expect("Error reading from input file");
-        if read_length != 0 {
-            let initial_length = data_buffer.len();
-            data_buffer.extend_from_slice(&read_buffer);
-            let replacement = replace_epoch_timestamps_in_buffer(
-                &data_buffer,
-                read_length + initial_length,
-                read_length < read_buffer.len(),
-            );
-            let slice = replacement.data.as_slice();
-            output.write_all(slice).expect("Failed to write");
+        // only a zero-length read signals the end of input; short reads are
+        // common on pipes and must not cause a trailing digit run to be flushed
+        if read_length == 0 {
+            break;
+        }
+        if let Some(report) = report.as_mut() {
+            report.record_chunk(&read_buffer[..read_length]);
+        }
+        let replaced = replacer.push_chunk(&read_buffer[..read_length]);
+        output.write_all(&replaced).expect("Failed to write");
+        if flush_each_chunk {
+            output.flush().expect("Error flushing output");
+        }
+        if let Some(report) = report.as_mut() {
+            report.write_matches(replacer);
+        }
+    }
+    output
+        .write_all(&replacer.finish())
+        .expect("Failed to write");
+    output.flush().expect("Error flushing output");
+    if let Some(report) = report {
+        report.write_matches(replacer);
+        report.flush();
+    }
+    Stats {
+        elapsed: start.elapsed(),
+        ..replacer.stats().clone()
+    }
+}
+
+/// Replaces epoch timestamps in a stream of arbitrarily-sized chunks. A digit run at
+/// the end of a chunk is retained until the next chunk shows whether it continues,
+/// so timestamps straddling chunk boundaries are still converted. Only ASCII is ever
+/// replaced, so multi-byte UTF-8 sequences are written intact even when they straddle
+/// chunk boundaries.
+pub struct StreamingReplacer {
+    pending: Vec<u8>,
+    /// The incomplete line at the end of the input so far, when selecting lines.
+    line: Vec<u8>,
+    /// Replaces invalid UTF-8 in the input, if the options ask for it.
+    lossy_decoder: Option<LossyDecoder>,
+    options: ReplacerOptions,
+    state: ReplacerState,
+}
+
+impl StreamingReplacer {
+    pub fn new() -> StreamingReplacer {
+        StreamingReplacer::with_options(ReplacerOptions::new())
+    }
+
+    pub fn with_options(options: ReplacerOptions) -> StreamingReplacer {
+        StreamingReplacer {
+            pending: Vec::new(),
+            line: Vec::new(),
+            lossy_decoder: (options.invalid_utf8 == InvalidUtf8::Lossy).then(LossyDecoder::default),
+            options,
+            state: ReplacerState::new(),
+        }
+    }
+
+    /// Records each converted timestamp, to be collected with `take_matches`.
+    pub fn recording_matches(mut self) -> StreamingReplacer {
+        self.state.matches = Some(Vec::new());
+        self
+    }
+
+    /// Returns the timestamps converted since the last call, if recording is enabled.
+    pub fn take_matches(&mut self) -> Vec<TimestampMatch> {
+        self.state
+            .matches
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Returns the converted output for all data that can be resolved so far.
+    pub fn push_chunk(&mut self, chunk: &[u8]) -> Vec<u8> {
+        self.state.stats.bytes_read += chunk.len() as u64;
+        let output = match self.lossy_decoder.as_mut() {
+            Some(decoder) => {
+                let decoded = decoder.decode(chunk, false);
+                self.convert_chunk(&decoded)
+            }
+            None => self.convert_chunk(chunk),
+        };
+        self.state.stats.bytes_written += output.len() as u64;
+        output
+    }
+
+    /// Resolves any retained digits; call once after the last chunk has been pushed.
+    pub fn finish(&mut self) -> Vec<u8> {
+        let mut output = match self.lossy_decoder.as_mut() {
+            Some(decoder) => {
+                let decoded = decoder.decode(&[], true);
+                self.convert_chunk(&decoded)
+            }
+            None => Vec::new(),
+        };
+        if self.options.is_line_oriented() {
+            let line = std::mem::take(&mut self.line);
+            output.extend(self.select_line(&line));
+        } else {
+            output.extend(self.replace_pending(true));
+        }
+        self.state.stats.bytes_written += output.len() as u64;
+        output
+    }
+
+    fn convert_chunk(&mut self, chunk: &[u8]) -> Vec<u8> {
+        if self.options.is_line_oriented() {
+            self.push_lines(chunk)
+        } else {
+            self.pending.extend_from_slice(chunk);
+            self.replace_pending(false)
+        }
+    }
+
+    /// Converts each line completed by `chunk`, keeping those selected by the options.
+    fn push_lines(&mut self, chunk: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        for line_part in chunk.split_inclusive(|&byte| byte == ASCII_NEWLINE) {
+            self.line.extend_from_slice(line_part);
+            if line_part.ends_with(&[ASCII_NEWLINE]) {
+                let line = std::mem::take(&mut self.line);
+                output.extend(self.select_line(&line));
+            }
+        }
+        output
+    }
+
+    fn select_line(&mut self, line: &[u8]) -> Vec<u8> {
+        self.state.first_in_line = None;
+        let converted = match &self.options.input_format {
+            _ if !self.options.converts_line(line) => {
+                if let Some(&byte) = line.last() {
+                    self.state.preceding_byte = Some(byte);
+                }
+                self.state.offset += line.len() as u64;
+                line.to_vec()
+            }
+            InputFormat::Text if !self.options.detectors.is_empty() => {
+                replace_detected_timestamps(line, &self.options, &mut self.state)
+            }
+            InputFormat::Text => {
+                self.pending.extend_from_slice(line);
+                // a line is complete, so no digits need to be retained
+                self.replace_pending(true)
+            }
+            InputFormat::Json { keys } => {
+                replace_json_timestamps(line, keys, &self.options, &mut self.state)
+            }
+            InputFormat::Csv { columns } => {
+                replace_csv_timestamps(line, columns, &self.options, &mut self.state)
+            }
+            InputFormat::Logfmt { keys } => {
+                replace_logfmt_timestamps(line, keys, &self.options, &mut self.state)
+            }
+            InputFormat::Prometheus => {
+                replace_prometheus_timestamps(line, &self.options, &mut self.state)
+            }
+            InputFormat::JournalExport => {
+                replace_journal_fields(line, &self.options, &mut self.state)
+            }
+            InputFormat::PacketCapture => {
+                replace_packet_timestamps(line, &self.options, &mut self.state)
+            }
+            InputFormat::AccessLog { server } => {
+                replace_access_log_timestamps(line, server, &self.options, &mut self.state)
+            }
+            InputFormat::Syslog => replace_syslog_timestamps(line, &self.options, &mut self.state),
+            InputFormat::Zeek => replace_zeek_timestamps(line, &self.options, &mut self.state),
+            InputFormat::Cef => replace_cef_timestamps(line, &self.options, &mut self.state),
+            InputFormat::LinePrefix => {
+                replace_line_prefix_timestamps(line, &self.options, &mut self.state)
+            }
+            InputFormat::Dmesg { boot_time } => {
+                replace_dmesg_timestamps(line, boot_time, &self.options, &mut self.state)
+            }
+            InputFormat::GcLog { start_time } => {
+                replace_gc_log_timestamps(line, start_time, &self.options, &mut self.state)
+            }
+            InputFormat::SqlDump { columns } => {
+                replace_sql_timestamps(line, columns, &self.options, &mut self.state)
+            }
+            InputFormat::KafkaConsumer { payload } => {
+                replace_kafka_timestamps(line, *payload, &self.options, &mut self.state)
+            }
+        };
+        if self.options.selects_line(self.state.first_in_line) {
+            converted
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn replace_pending(&mut self, end_of_input: bool) -> Vec<u8> {
+        let replacement = match self.options.conversion {
+            Conversion::ToDatetime => replace_epoch_timestamps_in_state(
+                &self.pending,
+                end_of_input,
+                &self.options,
+                &mut self.state,
+            ),
+            Conversion::ToEpoch(unit) => replace_datetimes(&self.pending, unit, end_of_input),
+            Conversion::Normalize | Conversion::ToZone => {
+                normalize_datetimes(&self.pending, end_of_input, &self.options, Utc::now())
+            }
+        };
+        let consumed = self.pending.len() - replacement.left_over_data as usize;
+        self.pending.drain(..consumed);
+        replacement.data
+    }
+
+    /// The totals for the input so far; the elapsed time is left for the caller to set.
+    pub fn stats(&self) -> &Stats {
+        &self.state.stats
+    }
+}
+
+impl Default for StreamingReplacer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct ReplacementResult {
+    pub data: Vec<u8>,
+    pub left_over_data: u64,
+}
+
+/// A timestamp found in the input, located by its byte offset from the start of the
+/// (decompressed) stream.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimestampMatch {
+    pub offset: u64,
+    pub original: String,
+    pub date_time: DateTime<Utc>,
+}
+
+/// Replaces all complete epoch timestamps in `input`. Unless `end_of_input` is set,
+/// a trailing candidate (a run of digits, possibly with a fraction) is not written to
+/// `data`, as it may continue in the next chunk; its length is reported in
+/// `left_over_data` and it should be passed in again at the start of the following chunk.
+pub fn replace_epoch_timestamps(input: &[u8], end_of_input: bool) -> ReplacementResult {
+    replace_epoch_timestamps_with_options(input, end_of_input, &ReplacerOptions::new())
+}
+
+/// As `replace_epoch_timestamps`, rendering timestamps as configured by `options`.
+pub fn replace_epoch_timestamps_with_options(
+    input: &[u8],
+    end_of_input: bool,
+    options: &ReplacerOptions,
+) -> ReplacementResult {
+    replace_epoch_timestamps_in_state(input, end_of_input, options, &mut ReplacerState::new())
+}
+
+/// Returns `input` with its epoch timestamps replaced. Only ASCII is ever replaced, so
+/// the result of valid UTF-8 input is always valid UTF-8.
+pub fn replace_epoch_timestamps_str(input: &str) -> String {
+    replace_epoch_timestamps_str_with_options(input, &ReplacerOptions::new())
+}
+
+/// As `replace_epoch_timestamps_str`, converting timestamps as configured by `options`,
+/// including any input format and line selection.
+pub fn replace_epoch_timestamps_str_with_options(input: &str, options: &ReplacerOptions) -> String {
+    let mut replacer = StreamingReplacer::with_options(options.clone());
+    let mut output = replacer.push_chunk(input.as_bytes());
+    output.extend(replacer.finish());
+    String::from_utf8(output).expect("Conversion produced invalid UTF-8")
+}
+
+/// A `ReplacementResult` that borrows the input when nothing in it was replaced.
+pub struct CowReplacementResult<'a> {
+    pub data: Cow<'a, [u8]>,
+    pub left_over_data: u64,
+}
+
+/// As `replace_epoch_timestamps`, returning the converted data without copying it when
+/// `input` contains no timestamps.
+pub fn replace_epoch_timestamps_cow(input: &[u8], end_of_input: bool) -> CowReplacementResult<'_> {
+    replace_epoch_timestamps_cow_with_options(input, end_of_input, &ReplacerOptions::new())
+}
+
+/// As `replace_epoch_timestamps_cow`, rendering timestamps as configured by `options`.
+pub fn replace_epoch_timestamps_cow_with_options<'a>(
+    input: &'a [u8],
+    end_of_input: bool,
+    options: &ReplacerOptions,
+) -> CowReplacementResult<'a> {
+    let mut output = CowWriter {
+        input,
+        borrowed: 0,
+        owned: None,
+    };
+    let consumed = write_epoch_timestamps_in_state(
+        input,
+        end_of_input,
+        options,
+        &mut ReplacerState::new(),
+        &mut output,
+    )
+    .expect("Failed to write to buffer");
+    CowReplacementResult {
+        data: match output.owned {
+            Some(owned) => Cow::Owned(owned),
+            None => Cow::Borrowed(&input[..output.borrowed]),
+        },
+        left_over_data: (input.len() - consumed) as u64,
+    }
+}
+
+/// Collects output that is a prefix of `input` by reference, only copying it once
+/// anything else is written.
+struct CowWriter<'a> {
+    input: &'a [u8],
+    borrowed: usize,
+    owned: Option<Vec<u8>>,
+}
+
+impl Write for CowWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.owned {
+            Some(owned) => owned.extend_from_slice(buf),
+            // unchanged input is written as slices of it, so continuing the prefix is
+            // recognised by address
+            None if std::ptr::eq(buf.as_ptr(), self.input[self.borrowed..].as_ptr()) => {
+                self.borrowed += buf.len();
+            }
+            None => {
+                let mut owned = self.input[..self.borrowed].to_vec();
+                owned.extend_from_slice(buf);
+                self.owned = Some(owned);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// As `replace_epoch_timestamps`, writing the converted data to `writer` rather than
+/// returning it. Returns the length of the trailing candidate that was not written.
+pub fn replace_epoch_timestamps_to<W: Write>(
+    input: &[u8],
+    end_of_input: bool,
+    writer: &mut W,
+) -> std::io::Result<u64> {
+    replace_epoch_timestamps_to_with_options(input, end_of_input, &ReplacerOptions::new(), writer)
+}
+
+/// As `replace_epoch_timestamps_to`, rendering timestamps as configured by `options`.
+pub fn replace_epoch_timestamps_to_with_options<W: Write>(
+    input: &[u8],
+    end_of_input: bool,
+    options: &ReplacerOptions,
+    writer: &mut W,
+) -> std::io::Result<u64> {
+    let consumed = write_epoch_timestamps_in_state(
+        input,
+        end_of_input,
+        options,
+        &mut ReplacerState::new(),
+        writer,
+    )?;
+    Ok((input.len() - consumed) as u64)
+}
+
+/// Information carried from one converted timestamp to the next.
+struct ReplacerState {
+    first: Option<DateTime<Utc>>,
+    previous: Option<DateTime<Utc>>,
+    /// The last byte of the input already consumed, which precedes the next chunk.
+    preceding_byte: Option<u8>,
+    /// The number of input bytes consumed by earlier chunks.
+    offset: u64,
+    matches: Option<Vec<TimestampMatch>>,
+    stats: Stats,
+    /// The first timestamp converted in the current line; only reset when selecting lines.
+    first_in_line: Option<DateTime<Utc>>,
+    csv: CsvState,
+    journal: JournalState,
+    zeek: ZeekState,
+    gc_log: GcLogState,
+    sql: SqlState,
+    /// The end of the input already consumed, when key prefixes are required.
+    recent: Vec<u8>,
+}
+
+impl ReplacerState {
+    fn new() -> ReplacerState {
+        ReplacerState {
+            first: None,
+            previous: None,
+            preceding_byte: None,
+            offset: 0,
+            matches: None,
+            stats: Stats::default(),
+            first_in_line: None,
+            csv: CsvState::default(),
+            journal: JournalState::default(),
+            zeek: ZeekState::default(),
+            gc_log: GcLogState::default(),
+            sql: SqlState::default(),
+            recent: Vec::new(),
+        }
+    }
+}
+
+fn replace_epoch_timestamps_in_state(
+    input: &[u8],
+    end_of_input: bool,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> ReplacementResult {
+    let mut replaced: Vec<u8> = Vec::new();
+    let consumed =
+        write_epoch_timestamps_in_state(input, end_of_input, options, state, &mut replaced)
+            .expect("Failed to write to buffer");
+    ReplacementResult {
+        data: replaced,
+        left_over_data: (input.len() - consumed) as u64,
+    }
+}
+
+/// Writes `input` to `output` with its epoch timestamps replaced, returning the number
+/// of input bytes consumed. Unchanged runs of input are written without copying.
+fn write_epoch_timestamps_in_state<W: Write + ?Sized>(
+    input: &[u8],
+    end_of_input: bool,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+    output: &mut W,
+) -> std::io::Result<usize> {
+    let mut rendered: Vec<u8> = Vec::new();
+    let mut unchanged_start = 0;
+    let mut index = 0;
+    while index < input.len() {
+        if options.jwt_claims && input[index] == ASCII_LOWERCASE_E {
+            match scan_jwt(input, index, end_of_input, state) {
+                JwtCandidate::Token(length, claims) => {
+                    let token = &input[index..index + length];
+                    rendered.clear();
+                    append_jwt_claims(&claims, token, index, options, state, &mut rendered);
+                    output.write_all(&input[unchanged_start..index + length])?;
+                    output.write_all(&rendered)?;
+                    index += length;
+                    unchanged_start = index;
+                    continue;
+                }
+                JwtCandidate::Incomplete => break,
+                JwtCandidate::NotToken => {}
+            }
+        }
+        if options.duration_keys.is_some() {
+            match scan_duration(input, index, end_of_input, options, state) {
+                DurationCandidate::Duration(length, millis) => {
+                    rendered.clear();
+                    append_duration(millis, &mut rendered);
+                    output.write_all(&input[unchanged_start..index])?;
+                    output.write_all(&rendered)?;
+                    index += length;
+                    unchanged_start = index;
+                    continue;
+                }
+                DurationCandidate::Incomplete => break,
+                DurationCandidate::NotDuration => {}
+            }
+        }
+        if !may_start_timestamp(input[index], options) {
+            index += 1;
+            continue;
+        }
+        match scan_timestamp(input, index, end_of_input, options, state) {
+            Candidate::Timestamp(length, timestamp, kind)
+                if is_plausible(&timestamp, options)
+                    && is_delimited(input, index, length, options, state)
+                    && is_keyed(input, index, options, state)
+                    && !is_excluded(input, index, length, options, state)
+                    && is_confident(input, index, length, &timestamp, kind, options, state) =>
+            {
+                let original = &input[index..index + length];
+                // the whole token, including any quotes that are replaced with the value
+                let (token_start, token_end) = if options.quoted_timestamps
+                    == QuotedTimestamps::Replace
+                    && index > unchanged_start
+                    && is_quoted(input, index, length)
+                {
+                    (index - 1, index + length + 1)
+                } else {
+                    (index, index + length)
+                };
+                record_timestamp(&timestamp, original, index, options, state);
+                rendered.clear();
+                if !append_custom_format(&timestamp, original, options, &mut rendered) {
+                    if options.annotate || kind.is_identifier() {
+                        rendered.extend_from_slice(&input[token_start..token_end]);
+                        rendered.push(ASCII_SPACE);
+                    }
+                    append_epoch_timestamp(&timestamp, options, state, &mut rendered);
+                }
+                output.write_all(&input[unchanged_start..token_start])?;
+                output.write_all(&rendered)?;
+                index = token_end;
+                unchanged_start = index;
+            }
+            Candidate::Timestamp(length, _, _) | Candidate::NotTimestamp(length) => {
+                index += length;
+            }
+            Candidate::Incomplete => break,
+        }
+    }
+    // a quote is retained for the next chunk, in case it is replaced with the value after it
+    if options.quoted_timestamps == QuotedTimestamps::Replace
+        && !end_of_input
+        && index > unchanged_start
+        && is_quote(input[index - 1])
+    {
+        index -= 1;
+    }
+    output.write_all(&input[unchanged_start..index])?;
+
+    if index > 0 {
+        state.preceding_byte = Some(input[index - 1]);
+    }
+    let lookbehind = options.lookbehind();
+    if lookbehind > 0 {
+        // keep enough consumed input to match a key prefix and space before the next chunk
+        state.recent.extend_from_slice(&input[..index]);
+        let excess = state.recent.len().saturating_sub(lookbehind);
+        state.recent.drain(..excess);
+    }
+    state.offset += index as u64;
+    Ok(index)
+}
+
+/// Whether the candidate at `start` scores at least the minimum confidence, if any.
+fn is_confident(
+    input: &[u8],
+    start: usize,
+    length: usize,
+    timestamp: &EpochTimestamp,
+    kind: TimestampKind,
+    options: &ReplacerOptions,
+    state: &ReplacerState,
+) -> bool {
+    options.min_confidence == 0
+        || timestamp.date_time().is_some_and(|date_time| {
+            confidence(input, start, length, kind, date_time, options, state)
+                >= options.min_confidence
+        })
+}
+
+/// Whether the timestamp at `start` follows one of the required key prefixes, if any.
+fn is_keyed(input: &[u8], start: usize, options: &ReplacerOptions, state: &ReplacerState) -> bool {
+    match &options.key_prefixes {
+        Some(prefixes) => follows_prefix(input, start, prefixes, state),
+        None => true,
+    }
+}
+
+/// Returns the byte `back` positions before `start`, which may be in an earlier chunk
+/// if enough of it was kept.
+fn byte_before(input: &[u8], start: usize, back: usize, state: &ReplacerState) -> Option<u8> {
+    if back <= start {
+        Some(input[start - back])
+    } else {
+        let recent_index = state.recent.len().checked_sub(back - start)?;
+        Some(state.recent[recent_index])
+    }
+}
+
+/// Whether the timestamp at `start` follows one of `prefixes` and an optional space.
+fn follows_prefix<P: AsRef<str>>(
+    input: &[u8],
+    start: usize,
+    prefixes: &[P],
+    state: &ReplacerState,
+) -> bool {
+    let byte_before = |back: usize| byte_before(input, start, back, state);
+    let spaces = if byte_before(1) == Some(ASCII_SPACE) {
+        1
+    } else {
+        0
+    };
+    prefixes.iter().map(AsRef::as_ref).any(|prefix: &str| {
+        !prefix.is_empty()
+            && prefix
+                .bytes()
+                .rev()
+                .enumerate()
+                .all(|(position, byte)| byte_before(spaces + position + 1) == Some(byte))
+    })
+}
+
+fn is_delimited(
+    input: &[u8],
+    start: usize,
+    length: usize,
+    options: &ReplacerOptions,
+    state: &ReplacerState,
+) -> bool {
+    match &options.boundaries {
+        BoundaryPolicy::AnyNonDigit => true,
+        BoundaryPolicy::Delimited { before, after } => {
+            let preceding = if start > 0 {
+                Some(input[start - 1])
+            } else {
+                state.preceding_byte
+            };
+            let following = input.get(start + length).copied();
+            let is_quoted = preceding.is_some_and(is_quote) && following == preceding;
+            is_quoted
+                || (preceding.is_none_or(|byte| before.contains(&byte))
+                    && following.is_none_or(|byte| after.contains(&byte)))
+        }
+    }
+}
+
+fn is_quote(byte: u8) -> bool {
+    byte == ASCII_QUOTE || byte == ASCII_APOSTROPHE
+}
+
+/// Whether the candidate of `length` bytes at `start` is wrapped in matching quotes
+/// within `input`.
+fn is_quoted(input: &[u8], start: usize, length: usize) -> bool {
+    start > 0 && is_quote(input[start - 1]) && input.get(start + length) == Some(&input[start - 1])
+}
+
+/// An instant decoded from an epoch value, along with the number of sub-second
+/// digits that were present in the input.
+struct EpochTimestamp {
+    seconds: i64,
+    nanos: u32,
+    fractional_digits: usize,
+    /// The UTC offset written alongside the timestamp, in which it is rendered.
+    offset: Option<FixedOffset>,
+}
+
+impl EpochTimestamp {
+    fn date_time(&self) -> Option<DateTime<Utc>> {
+        Utc.timestamp_opt(self.seconds, self.nanos).single()
+    }
+
+    /// The instant that the timestamp is converted to, which is moved by any shift in
+    /// the options.
+    fn converted_date_time(&self, options: &ReplacerOptions) -> DateTime<Utc> {
+        let date_time = self.date_time().expect("Invalid timestamp");
+        options
+            .shift
+            .and_then(|shift| date_time.checked_add_signed(shift))
+            .unwrap_or(date_time)
+    }
+}
+
+/// Parses a value that must consist entirely of a plausible epoch timestamp, as found
+/// in a field of structured input.
+fn parse_epoch_value(value: &[u8], options: &ReplacerOptions) -> Option<EpochTimestamp> {
+    // a value has no surroundings, so LDAP attribute names are not seen
+    match scan_timestamp(value, 0, true, options, &ReplacerState::new()) {
+        // there is no room to annotate an identifier within a structured value
+        Candidate::Timestamp(length, timestamp, kind)
+            if length == value.len()
+                && is_plausible(&timestamp, options)
+                && !kind.is_identifier() =>
+        {
+            Some(timestamp)
+        }
+        _ => None,
+    }
+}
+
+fn is_plausible(timestamp: &EpochTimestamp, options: &ReplacerOptions) -> bool {
+    match timestamp.date_time() {
+        Some(date_time) => (options.min_year..=options.max_year).contains(&date_time.year()),
+        None => false,
+    }
+}
+
+enum Candidate {
+    Timestamp(usize, EpochTimestamp, TimestampKind),
+    NotTimestamp(usize),
+    Incomplete,
+}
+
+/// Whether a candidate timestamp can start with `byte`.
+fn may_start_timestamp(byte: u8, options: &ReplacerOptions) -> bool {
+    byte.is_ascii_digit()
+        || byte == ASCII_AT
+        || (byte == ASCII_HYPHEN && options.negative_timestamps)
+        || may_start_identifier(byte, options)
+}
+
+/// Whether an identifier of an enabled kind can start with `byte`.
+fn may_start_identifier(byte: u8, options: &ReplacerOptions) -> bool {
+    (byte.is_ascii_hexdigit() && (options.object_ids || options.uuid_timestamps))
+        || (byte.is_ascii_digit() && options.ulid_timestamps)
+}
+
+/// Classifies the candidate starting at `start`, which may be an `@`-prefixed epoch,
+/// including the optional formats that are enabled: a TAI64N label, a hexadecimal
+/// value if it starts with `0x`, a WebKit timestamp, .NET ticks, or a Windows FILETIME,
+/// which is an LDAP timestamp when it follows an Active Directory attribute name.
+fn scan_timestamp(
+    input: &[u8],
+    start: usize,
+    end_of_input: bool,
+    options: &ReplacerOptions,
+    state: &ReplacerState,
+) -> Candidate {
+    let first_byte = input.get(start).copied();
+    if first_byte == Some(ASCII_AT) {
+        if options.tai64n_timestamps {
+            match scan_tai64n_candidate(input, start, end_of_input) {
+                Candidate::NotTimestamp(_) => {}
+                candidate => return candidate,
+            }
+        }
+        return scan_at_prefixed_candidate(input, start, end_of_input);
+    }
+    if options.negative_timestamps && first_byte == Some(ASCII_HYPHEN) {
+        let preceding = if start > 0 {
+            Some(input[start - 1])
+        } else {
+            state.preceding_byte
+        };
+        // a hyphen within a word or number is not a sign
+        if preceding.is_some_and(|byte| byte.is_ascii_alphanumeric()) {
+            return Candidate::NotTimestamp(1);
+        }
+        return scan_negative_candidate(input, start, end_of_input);
+    }
+    if first_byte.is_some_and(|byte| may_start_identifier(byte, options)) {
+        match scan_identifier_candidate(input, start, end_of_input, options, state) {
+            Candidate::NotTimestamp(_) if !input[start].is_ascii_digit() => {
+                return Candidate::NotTimestamp(1)
+            }
+            Candidate::NotTimestamp(_) => {}
+            candidate => return candidate,
+        }
+    }
+    if options.hex_timestamps && first_byte == Some(ASCII_ZERO) {
+        if start + 1 == input.len() && !end_of_input {
+            return Candidate::Incomplete;
+        }
+        if has_hex_indicator(&input[start..]) {
+            return scan_hex_candidate(input, start, end_of_input);
+        }
+    }
+    if options.scientific_timestamps {
+        match scan_scientific_candidate(input, start, end_of_input) {
+            Candidate::NotTimestamp(_) => {}
+            candidate => return candidate,
+        }
+    }
+    if !options.digit_separators.is_empty() {
+        match scan_grouped_candidate(input, start, end_of_input, options) {
+            Candidate::NotTimestamp(_) => {}
+            candidate => return candidate,
+        }
+    }
+    let seconds_range = options.epoch_seconds_range();
+    match scan_candidate(input, start, end_of_input, seconds_range.as_ref()) {
+        Candidate::NotTimestamp(length) if is_tick_count(&input[start..start + length]) => {
+            let digits = &input[start..start + length];
+            let windows = tick_timestamp(digits, FILETIME_EPOCH_OFFSET_SECONDS);
+            // the epochs are centuries apart, so at most one reading is plausible
+            let dotnet = tick_timestamp(digits, DOTNET_EPOCH_OFFSET_SECONDS);
+            if options
+                .ldap_prefixes
+                .as_ref()
+                .is_some_and(|prefixes| follows_prefix(input, start, prefixes, state))
+            {
+                Candidate::Timestamp(length, windows, TimestampKind::Ldap)
+            } else if options.filetime_timestamps && is_plausible(&windows, options) {
+                Candidate::Timestamp(length, windows, TimestampKind::Filetime)
+            } else if options.dotnet_timestamps && is_plausible(&dotnet, options) {
+                Candidate::Timestamp(length, dotnet, TimestampKind::DotnetTicks)
+            } else {
+                scan_snowflake_candidate(digits, options)
+            }
+        }
+        Candidate::NotTimestamp(length)
+            if options.webkit_timestamps && is_webkit_timestamp(&input[start..start + length]) =>
+        {
+            let timestamp = webkit_timestamp(&input[start..start + length]);
+            Candidate::Timestamp(length, timestamp, TimestampKind::Webkit)
+        }
+        Candidate::NotTimestamp(length) => {
+            scan_snowflake_candidate(&input[start..start + length], options)
+        }
+        Candidate::Timestamp(length, timestamp, TimestampKind::Seconds)
+            if options.git_timestamps =>
+        {
+            scan_git_offset(input, start, length, timestamp, end_of_input)
+        }
+        candidate => candidate,
+    }
+}
+
+/// Classifies an epoch in scientific notation, such as `1.53021607e9` or
+/// `1.530216070317E12`, whose magnitude is that of epoch seconds, milliseconds or
+/// microseconds. Anything else is left to be scanned as usual.
+fn scan_scientific_candidate(input: &[u8], start: usize, end_of_input: bool) -> Candidate {
+    let integer_end = digit_run_end(input, start);
+    let fraction_end = if input.get(integer_end) == Some(&ASCII_DECIMAL_POINT) {
+        digit_run_end(input, integer_end + 1)
+    } else {
+        integer_end
+    };
+    let mut exponent_start = fraction_end + 1;
+    if input.get(exponent_start) == Some(&ASCII_PLUS) {
+        exponent_start += 1;
+    }
+    let exponent_end = digit_run_end(input, exponent_start.min(input.len()));
+    if exponent_end == input.len() && !end_of_input {
+        return Candidate::Incomplete;
+    }
+    if input[start] == ASCII_ZERO
+        || !matches!(
+            input.get(fraction_end),
+            Some(&ASCII_LOWERCASE_E) | Some(&ASCII_UPPERCASE_E)
+        )
+        || exponent_start >= exponent_end
+        || exponent_end - exponent_start > 2
+    {
+        return Candidate::NotTimestamp(1);
+    }
+    let exponent = ascii_to_integer(&input[exponent_start..exponent_end]) as usize;
+    let integer_digits = integer_end - start + exponent;
+    let kind = match integer_digits {
+        DIGITS_IN_EPOCH_SECOND_TIMESTAMP => TimestampKind::FractionalSeconds,
+        DIGITS_IN_EPOCH_MILLISECOND_TIMESTAMP => TimestampKind::Milliseconds,
+        DIGITS_IN_EPOCH_MICROSECOND_TIMESTAMP => TimestampKind::Microseconds,
+        _ => return Candidate::NotTimestamp(1),
+    };
+    let mut digits = input[start..integer_end].to_vec();
+    if fraction_end > integer_end {
+        digits.extend_from_slice(&input[integer_end + 1..fraction_end]);
+    }
+    digits.resize(digits.len().max(integer_digits), ASCII_ZERO);
+    // milliseconds and microseconds keep the precision of their unit
+    let fraction_end = if kind == TimestampKind::FractionalSeconds {
+        digits
+            .len()
+            .min(DIGITS_IN_EPOCH_SECOND_TIMESTAMP + MAX_FRACTIONAL_DIGITS)
+    } else {
+        integer_digits
+    };
+    let timestamp = fractional_epoch_timestamp(
+        &digits[..DIGITS_IN_EPOCH_SECOND_TIMESTAMP],
+        &digits[DIGITS_IN_EPOCH_SECOND_TIMESTAMP..fraction_end],
+    );
+    let kind = if timestamp.fractional_digits == 0 {
+        TimestampKind::Seconds
+    } else {
+        kind
+    };
+    Candidate::Timestamp(exponent_end - start, timestamp, kind)
+}
+
+/// Classifies an epoch whose digits are grouped in threes by one of `separators`, as in
+/// `1_530_216_070` or `1,530,216,070`. Anything else is left to be scanned as usual.
+fn scan_grouped_candidate(
+    input: &[u8],
+    start: usize,
+    end_of_input: bool,
+    options: &ReplacerOptions,
+) -> Candidate {
+    let separators = &options.digit_separators;
+    let mut digits = Vec::new();
+    let mut separator = None;
+    let mut group_length = 0;
+    let mut end = start;
+    loop {
+        while end < input.len() && input[end].is_ascii_digit() {
+            digits.push(input[end]);
+            group_length += 1;
+            end += 1;
+        }
+        let is_grouped = if separator.is_none() {
+            group_length <= DIGITS_IN_GROUP
+        } else {
+            group_length == DIGITS_IN_GROUP
+        };
+        match input.get(end) {
+            None if !end_of_input => return Candidate::Incomplete,
+            Some(&byte)
+                if is_grouped
+                    && separators.contains(&byte)
+                    && separator.is_none_or(|separator| separator == byte) =>
+            {
+                match input.get(end + 1) {
+                    None if !end_of_input => return Candidate::Incomplete,
+                    Some(next) if next.is_ascii_digit() => {
+                        separator = Some(byte);
+                        group_length = 0;
+                        end += 1;
+                    }
+                    _ => break,
+                }
+            }
+            _ => break,
+        }
+    }
+    if separator.is_none() || group_length != DIGITS_IN_GROUP {
+        return Candidate::NotTimestamp(1);
+    }
+    match scan_candidate(&digits, 0, true, options.epoch_seconds_range().as_ref()) {
+        Candidate::Timestamp(length, timestamp, kind) if length == digits.len() => {
+            Candidate::Timestamp(end - start, timestamp, kind)
+        }
+        _ => Candidate::NotTimestamp(1),
+    }
+}
+
+/// Extends epoch seconds that are followed by a UTC offset, as in git's raw
+/// `1530216070 +0100`, to include the offset, in which they are then rendered.
+fn scan_git_offset(
+    input: &[u8],
+    start: usize,
+    length: usize,
+    timestamp: EpochTimestamp,
+    end_of_input: bool,
+) -> Candidate {
+    let offset_start = start + length;
+    // the offset and the byte that follows it
+    let available = &input[offset_start..input.len().min(offset_start + GIT_OFFSET_LENGTH + 1)];
+    for (index, &byte) in available.iter().enumerate() {
+        let matches = match index {
+            0 => byte == ASCII_SPACE,
+            1 => byte == ASCII_PLUS || byte == ASCII_HYPHEN,
+            GIT_OFFSET_LENGTH => !byte.is_ascii_digit(),
+            _ => byte.is_ascii_digit(),
+        };
+        if !matches {
+            return Candidate::Timestamp(length, timestamp, TimestampKind::Seconds);
+        }
+    }
+    if available.len() <= GIT_OFFSET_LENGTH && !end_of_input {
+        return Candidate::Incomplete;
+    }
+    if available.len() < GIT_OFFSET_LENGTH {
+        return Candidate::Timestamp(length, timestamp, TimestampKind::Seconds);
+    }
+    let hours = ascii_to_integer(&available[2..4]) as i32;
+    let minutes = ascii_to_integer(&available[4..6]) as i32;
+    let sign = if available[1] == ASCII_HYPHEN { -1 } else { 1 };
+    match FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)) {
+        Some(offset) => {
+            let timestamp = EpochTimestamp {
+                offset: Some(offset),
+                ..timestamp
+            };
+            Candidate::Timestamp(
+                length + GIT_OFFSET_LENGTH,
+                timestamp,
+                TimestampKind::Seconds,
+            )
+        }
+        None => Candidate::Timestamp(length, timestamp, TimestampKind::Seconds),
+    }
+}
+
+/// Classifies an identifier that holds its creation time, of the kinds that are
+/// enabled, starting a word at `start`.
+fn scan_identifier_candidate(
+    input: &[u8],
+    start: usize,
+    end_of_input: bool,
+    options: &ReplacerOptions,
+    state: &ReplacerState,
+) -> Candidate {
+    let preceding = if start > 0 {
+        Some(input[start - 1])
+    } else {
+        state.preceding_byte
+    };
+    if preceding.is_some_and(|byte| byte.is_ascii_alphanumeric() || byte == ASCII_HYPHEN) {
+        return Candidate::NotTimestamp(1);
+    }
+    if options.uuid_timestamps {
+        match scan_uuid_candidate(input, start, end_of_input) {
+            Candidate::NotTimestamp(_) => {}
+            candidate => return candidate,
+        }
+    }
+    let mut word_end = start;
+    while word_end < input.len() && input[word_end].is_ascii_alphanumeric() {
+        word_end += 1;
+    }
+    if word_end == input.len() && !end_of_input {
+        return Candidate::Incomplete;
+    }
+    let word = &input[start..word_end];
+    if options.object_ids && word.len() == HEX_DIGITS_IN_OBJECT_ID {
+        return object_id_candidate(word);
+    }
+    if options.ulid_timestamps && word.len() == ULID_LENGTH {
+        return ulid_candidate(word);
+    }
+    Candidate::NotTimestamp(1)
+}
+
+/// Classifies a version 7 UUID, whose first 48 bits are its creation time in
+/// milliseconds. UUIDs of other versions are skipped one byte at a time.
+fn scan_uuid_candidate(input: &[u8], start: usize, end_of_input: bool) -> Candidate {
+    // the UUID and the byte that follows it
+    let available = &input[start..input.len().min(start + UUID_LENGTH + 1)];
+    for (index, &byte) in available.iter().enumerate() {
+        let matches = if index == UUID_LENGTH {
+            !byte.is_ascii_alphanumeric() && byte != ASCII_HYPHEN
+        } else if UUID_HYPHEN_POSITIONS.contains(&index) {
+            byte == ASCII_HYPHEN
+        } else if index == UUID_VERSION_POSITION {
+            byte == ASCII_SEVEN
+        } else if index == UUID_VARIANT_POSITION {
+            // the RFC 9562 variant has the top bits 10
+            byte.is_ascii_hexdigit() && hex_to_integer(&[byte]) >> 2 == 0b10
+        } else {
+            byte.is_ascii_hexdigit()
+        };
+        if !matches {
+            return Candidate::NotTimestamp(1);
+        }
+    }
+    if available.len() <= UUID_LENGTH && !end_of_input {
+        return Candidate::Incomplete;
+    }
+    if available.len() < UUID_LENGTH {
+        return Candidate::NotTimestamp(1);
+    }
+    let uuid = &input[start..start + UUID_LENGTH];
+    let millis = (hex_to_integer(&uuid[..8]) << 16 | hex_to_integer(&uuid[9..13])) as i64;
+    let timestamp = EpochTimestamp {
+        seconds: millis / 1000,
+        nanos: (millis % 1000 * NANOS_PER_MILLISECOND) as u32,
+        fractional_digits: 3,
+        offset: None,
+    };
+    Candidate::Timestamp(uuid.len(), timestamp, TimestampKind::Uuid7)
+}
+
+/// Classifies a word that may be a MongoDB ObjectId: 24 hexadecimal digits whose first
+/// eight are its creation time in seconds.
+fn object_id_candidate(word: &[u8]) -> Candidate {
+    if !word.iter().all(u8::is_ascii_hexdigit) {
+        return Candidate::NotTimestamp(1);
+    }
+    let timestamp = EpochTimestamp {
+        seconds: hex_to_integer(&word[..HEX_DIGITS_IN_EPOCH_SECOND_TIMESTAMP]) as i64,
+        nanos: 0,
+        fractional_digits: 0,
+        offset: None,
+    };
+    Candidate::Timestamp(word.len(), timestamp, TimestampKind::ObjectId)
+}
+
+/// Classifies a word that may be a ULID: 26 characters of Crockford's base 32, in
+/// either case, whose first ten are its creation time in milliseconds.
+fn ulid_candidate(word: &[u8]) -> Candidate {
+    let mut values = Vec::with_capacity(word.len());
+    for &byte in word {
+        match CROCKFORD_BASE32_ALPHABET
+            .bytes()
+            .position(|character| character == byte.to_ascii_uppercase())
+        {
+            Some(value) => values.push(value as i64),
+            None => return Candidate::NotTimestamp(1),
+        }
+    }
+    // a ULID's 128 bits leave room for only 0 to 7 in its first character
+    if values[0] > 7 {
+        return Candidate::NotTimestamp(1);
+    }
+    let millis = values[..ULID_TIMESTAMP_LENGTH]
+        .iter()
+        .fold(0, |millis, value| millis << 5 | value);
+    let timestamp = EpochTimestamp {
+        seconds: millis / 1000,
+        nanos: (millis % 1000 * NANOS_PER_MILLISECOND) as u32,
+        fractional_digits: 3,
+        offset: None,
+    };
+    Candidate::Timestamp(word.len(), timestamp, TimestampKind::Ulid)
+}
+
+/// Classifies a run of digits that was not recognised as any other kind of timestamp,
+/// which may be a snowflake ID if they are enabled. A snowflake that is in fact a tick
+/// count, say, is told apart only by the plausibility of its creation time.
+fn scan_snowflake_candidate(digits: &[u8], options: &ReplacerOptions) -> Candidate {
+    let epoch_millis = match options.snowflake_epoch {
+        Some(epoch_millis)
+            if DIGITS_IN_SNOWFLAKE_ID.contains(&digits.len())
+                && digits.iter().all(u8::is_ascii_digit) =>
+        {
+            epoch_millis
+        }
+        _ => return Candidate::NotTimestamp(digits.len()),
+    };
+    let millis = epoch_millis + (ascii_to_integer(digits) >> SNOWFLAKE_TIMESTAMP_SHIFT) as i64;
+    let timestamp = EpochTimestamp {
+        seconds: millis.div_euclid(1000),
+        nanos: (millis.rem_euclid(1000) * NANOS_PER_MILLISECOND) as u32,
+        fractional_digits: 3,
+        offset: None,
+    };
+    Candidate::Timestamp(digits.len(), timestamp, TimestampKind::Snowflake)
+}
+
+/// Classifies an `@`-prefixed TAI64N (or TAI64NA) label, as written by daemontools
+/// and s6. Anything else is skipped one byte at a time, so any digits are still seen.
+fn scan_tai64n_candidate(input: &[u8], start: usize, end_of_input: bool) -> Candidate {
+    let hex_start = start + 1;
+    let mut hex_end = hex_start;
+    while hex_end < input.len() && input[hex_end].is_ascii_hexdigit() {
+        hex_end += 1;
+    }
+    if hex_end == input.len() && !end_of_input {
+        return Candidate::Incomplete;
+    }
+    let hex_digits = &input[hex_start..hex_end];
+    if !HEX_DIGITS_IN_TAI64N_LABEL.contains(&hex_digits.len()) {
+        return Candidate::NotTimestamp(1);
+    }
+    let label = hex_to_integer(&hex_digits[..16]);
+    if label < TAI64_LABEL_BASE {
+        return Candidate::NotTimestamp(1);
+    }
+    let timestamp = EpochTimestamp {
+        seconds: (label - TAI64_LABEL_BASE) as i64 - TAI_OFFSET_SECONDS,
+        nanos: hex_to_integer(&hex_digits[16..24]) as u32,
+        fractional_digits: 9,
+        offset: None,
+    };
+    Candidate::Timestamp(hex_end - start, timestamp, TimestampKind::Tai64n)
+}
+
+/// Classifies an epoch directly preceded by `@`, as in systemd's `@1530216070`, which
+/// is replaced along with the `@`. Anything else is skipped one byte at a time.
+fn scan_at_prefixed_candidate(input: &[u8], start: usize, end_of_input: bool) -> Candidate {
+    let digits_start = start + 1;
+    if digits_start == input.len() {
+        return if end_of_input {
+            Candidate::NotTimestamp(1)
+        } else {
+            Candidate::Incomplete
+        };
+    }
+    if !input[digits_start].is_ascii_digit() {
+        return Candidate::NotTimestamp(1);
+    }
+    match scan_candidate(input, digits_start, end_of_input, None) {
+        Candidate::Timestamp(length, timestamp, kind) => {
+            Candidate::Timestamp(length + 1, timestamp, kind)
+        }
+        Candidate::NotTimestamp(_) => Candidate::NotTimestamp(1),
+        Candidate::Incomplete => Candidate::Incomplete,
+    }
+}
+
+/// Classifies an epoch with a leading minus sign, for dates before 1970. Anything else
+/// is skipped one byte at a time.
+fn scan_negative_candidate(input: &[u8], start: usize, end_of_input: bool) -> Candidate {
+    let digits_start = start + 1;
+    if digits_start == input.len() {
+        return if end_of_input {
+            Candidate::NotTimestamp(1)
+        } else {
+            Candidate::Incomplete
+        };
+    }
+    if !input[digits_start].is_ascii_digit() {
+        return Candidate::NotTimestamp(1);
+    }
+    // dates from 1938 to 1969 are a digit shorter than later epoch seconds
+    let digits_end = digit_run_end(input, digits_start);
+    if digits_end - digits_start == DIGITS_IN_EPOCH_SECOND_TIMESTAMP - 1
+        && (digits_end < input.len() || end_of_input)
+    {
+        let timestamp = EpochTimestamp {
+            seconds: -(ascii_to_integer(&input[digits_start..digits_end]) as i64),
+            nanos: 0,
+            fractional_digits: 0,
+            offset: None,
+        };
+        return Candidate::Timestamp(digits_end - start, timestamp, TimestampKind::Seconds);
+    }
+    match scan_candidate(input, digits_start, end_of_input, None) {
+        Candidate::Timestamp(length, timestamp, kind) => {
+            let (seconds, nanos) = if timestamp.nanos == 0 {
+                (-timestamp.seconds, 0)
+            } else {
+                (-timestamp.seconds - 1, 1_000_000_000 - timestamp.nanos)
+            };
+            let negated = EpochTimestamp {
+                seconds,
+                nanos,
+                fractional_digits: timestamp.fractional_digits,
+                offset: None,
+            };
+            Candidate::Timestamp(length + 1, negated, kind)
+        }
+        Candidate::NotTimestamp(_) => Candidate::NotTimestamp(1),
+        Candidate::Incomplete => Candidate::Incomplete,
+    }
+}
+
+fn scan_hex_candidate(input: &[u8], start: usize, end_of_input: bool) -> Candidate {
+    let hex_start = start + 2;
+    let mut hex_end = hex_start;
+    while hex_end < input.len() && input[hex_end].is_ascii_hexdigit() {
+        hex_end += 1;
+    }
+    if hex_end == input.len() && !end_of_input {
+        return Candidate::Incomplete;
+    }
+    let hex_digits = &input[hex_start..hex_end];
+    if hex_digits.len() == HEX_DIGITS_IN_EPOCH_SECOND_TIMESTAMP {
+        let timestamp = EpochTimestamp {
+            seconds: hex_to_integer(hex_digits) as i64,
+            nanos: 0,
+            fractional_digits: 0,
+            offset: None,
+        };
+        Candidate::Timestamp(hex_end - start, timestamp, TimestampKind::Seconds)
+    } else if HEX_DIGITS_IN_EPOCH_MILLISECOND_TIMESTAMP.contains(&hex_digits.len()) {
+        let millis = hex_to_integer(hex_digits) as i64;
+        let timestamp = EpochTimestamp {
+            seconds: millis / 1000,
+            nanos: (millis.rem(1000) * NANOS_PER_MILLISECOND) as u32,
+            fractional_digits: 3,
+            offset: None,
+        };
+        Candidate::Timestamp(hex_end - start, timestamp, TimestampKind::Milliseconds)
+    } else {
+        Candidate::NotTimestamp(hex_end - start)
+    }
+}
+
+/// Classifies the digit run at `start` by its magnitude within `seconds_range` if
+/// given and it has no leading zero, or otherwise by its number of digits.
+fn scan_candidate(
+    input: &[u8],
+    start: usize,
+    end_of_input: bool,
+    seconds_range: Option<&RangeInclusive<u64>>,
+) -> Candidate {
+    let digits_end = digit_run_end(input, start);
+    if digits_end == input.len() && !end_of_input {
+        return Candidate::Incomplete;
+    }
+    let digits = &input[start..digits_end];
+    // zero-padded values keep the unit of their width
+    let kind = match seconds_range {
+        Some(seconds_range) if digits.first() != Some(&ASCII_ZERO) => {
+            magnitude_kind(digits, seconds_range)
+        }
+        _ => digit_count_kind(digits),
+    };
+    let kind = match kind {
+        Some(kind) => kind,
+        None => return Candidate::NotTimestamp(digits.len()),
+    };
+    if kind == TimestampKind::Seconds
+        && digits_end < input.len()
+        && input[digits_end] == ASCII_DECIMAL_POINT
+    {
+        let fraction_start = digits_end + 1;
+        let fraction_end = digit_run_end(input, fraction_start);
+        if fraction_end == input.len() && !end_of_input {
+            return Candidate::Incomplete;
+        }
+        let fractional_digits = fraction_end - fraction_start;
+        if fractional_digits > MAX_FRACTIONAL_DIGITS {
+            return Candidate::NotTimestamp(fraction_end - start);
+        }
+        if fractional_digits != 0 {
+            let timestamp =
+                fractional_epoch_timestamp(digits, &input[fraction_start..fraction_end]);
+            return Candidate::Timestamp(
+                fraction_end - start,
+                timestamp,
+                TimestampKind::FractionalSeconds,
+            );
+        }
+    }
+
+    // the suffix of a Java long, as in `1530216070317L`, is converted with the value
+    let mut length = digits.len();
+    if matches!(
+        input.get(digits_end),
+        Some(&ASCII_UPPERCASE_L) | Some(&ASCII_LOWERCASE_L)
+    ) {
+        match input.get(digits_end + 1) {
+            None if !end_of_input => return Candidate::Incomplete,
+            Some(byte) if byte.is_ascii_alphanumeric() => {}
+            _ => length += 1,
+        }
+    }
+    Candidate::Timestamp(length, integer_epoch_timestamp(digits, kind), kind)
+}
+
+fn digit_count_kind(digits: &[u8]) -> Option<TimestampKind> {
+    match digits.len() {
+        DIGITS_IN_EPOCH_MICROSECOND_TIMESTAMP => Some(TimestampKind::Microseconds),
+        DIGITS_IN_EPOCH_MILLISECOND_TIMESTAMP => Some(TimestampKind::Milliseconds),
+        DIGITS_IN_EPOCH_SECOND_TIMESTAMP => Some(TimestampKind::Seconds),
+        _ => None,
+    }
+}
+
+/// Returns the unit in which `digits` falls within `seconds_range`, preferring the
+/// largest unit should the ranges overlap. Fewer than nine digits are never an epoch,
+/// however wide the range.
+fn magnitude_kind(digits: &[u8], seconds_range: &RangeInclusive<u64>) -> Option<TimestampKind> {
+    if !(MIN_MAGNITUDE_DIGITS..=DIGITS_IN_EPOCH_MICROSECOND_TIMESTAMP).contains(&digits.len()) {
+        return None;
+    }
+    let value = ascii_to_integer(digits);
+    [
+        (1, TimestampKind::Seconds),
+        (1_000, TimestampKind::Milliseconds),
+        (1_000_000, TimestampKind::Microseconds),
+    ]
+    .iter()
+    .find(|&&(scale, _)| {
+        (seconds_range.start() * scale..=seconds_range.end() * scale + scale - 1).contains(&value)
+    })
+    .map(|&(_, kind)| kind)
+}
+
+fn digit_run_end(input: &[u8], start: usize) -> usize {
+    let mut end = start;
+    while end < input.len() && input[end].is_ascii_digit() {
+        end += 1;
+    }
+    end
+}
+
+fn integer_epoch_timestamp(digits: &[u8], kind: TimestampKind) -> EpochTimestamp {
+    let timestamp: i64 = ascii_to_integer(digits) as i64;
+
+    let (seconds, nanos, fractional_digits): (i64, u32, usize) = match kind {
+        TimestampKind::Microseconds => (
+            timestamp / 1_000_000,
+            (timestamp.rem(1_000_000) * NANOS_PER_MICROSECOND) as u32,
+            6,
+        ),
+        TimestampKind::Milliseconds => (
+            timestamp / 1000,
+            (timestamp.rem(1000) * NANOS_PER_MILLISECOND) as u32,
+            3,
+        ),
+        TimestampKind::Seconds => (timestamp, 0, 0),
+        _ => panic!("Cannot handle {:?} as an integer", kind),
+    };
+    EpochTimestamp {
+        seconds,
+        nanos,
+        fractional_digits,
+        offset: None,
+    }
+}
+
+/// Decodes a count of 100-nanosecond intervals since an epoch `epoch_offset_seconds`
+/// before the Unix epoch.
+fn tick_timestamp(digits: &[u8], epoch_offset_seconds: i64) -> EpochTimestamp {
+    let ticks = ascii_to_integer(digits) as i64;
+    EpochTimestamp {
+        seconds: ticks / FILETIME_TICKS_PER_SECOND - epoch_offset_seconds,
+        nanos: (ticks.rem(FILETIME_TICKS_PER_SECOND) * NANOS_PER_FILETIME_TICK) as u32,
+        fractional_digits: 7,
+        offset: None,
+    }
+}
+
+/// Decodes a count of microseconds since 1601-01-01.
+fn webkit_timestamp(digits: &[u8]) -> EpochTimestamp {
+    let micros = ascii_to_integer(digits) as i64;
+    EpochTimestamp {
+        seconds: micros / 1_000_000 - FILETIME_EPOCH_OFFSET_SECONDS,
+        nanos: (micros.rem(1_000_000) * NANOS_PER_MICROSECOND) as u32,
+        fractional_digits: 6,
+        offset: None,
+    }
+}
+
+fn fractional_epoch_timestamp(digits: &[u8], fraction: &[u8]) -> EpochTimestamp {
+    let mut nanos = ascii_to_integer(fraction) as u32;
+    for _ in fraction.len()..MAX_FRACTIONAL_DIGITS {
+        nanos *= 10;
+    }
+    EpochTimestamp {
+        seconds: ascii_to_integer(digits) as i64,
+        nanos,
+        fractional_digits: fraction.len(),
+        offset: None,
+    }
+}
+
+/// Decodes a number of seconds, with an optional fraction, that have elapsed since
+/// `anchor`, such as an offset from boot or from the start of a process. Values too
+/// large to add to the anchor are not decoded.
+fn anchored_timestamp(anchor: &DateTime<Utc>, value: &[u8]) -> Option<EpochTimestamp> {
+    let (digits, fraction) = match value.iter().position(|&byte| byte == ASCII_DECIMAL_POINT) {
+        Some(point) => (&value[..point], &value[point + 1..]),
+        None => (value, &value[value.len()..]),
+    };
+    if digits.is_empty()
+        || digits.len() > MAX_ELAPSED_SECONDS_DIGITS
+        || fraction.len() > MAX_FRACTIONAL_DIGITS
+        || !digits.iter().chain(fraction).all(u8::is_ascii_digit)
+    {
+        return None;
+    }
+    let elapsed = fractional_epoch_timestamp(digits, fraction);
+    let nanos = anchor.timestamp_subsec_nanos() + elapsed.nanos;
+    let seconds = anchor
+        .timestamp()
+        .checked_add(elapsed.seconds)?
+        .checked_add((nanos / NANOS_PER_SECOND) as i64)?;
+    Some(EpochTimestamp {
+        seconds,
+        nanos: nanos % NANOS_PER_SECOND,
+        fractional_digits: elapsed.fractional_digits,
+        offset: None,
+    })
+}
+
+/// Accounts for the conversion of `original`, found at `index` in the current input.
+fn record_timestamp(
+    timestamp: &EpochTimestamp,
+    original: &[u8],
+    index: usize,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) {
+    let date_time = timestamp.converted_date_time(options);
+    state.stats.record_timestamp(date_time);
+    state.first_in_line.get_or_insert(date_time);
+    if let Some(matches) = &mut state.matches {
+        matches.push(TimestampMatch {
+            offset: state.offset + index as u64,
+            original: String::from_utf8_lossy(original).to_string(),
+            date_time,
+        });
+    }
+}
+
+/// Writes `line` with the range from `start` to `end` replaced by the rendering of the
+/// timestamp decoded from it, or unchanged if there is none, for the input formats that
+/// convert at most one field of each line.
+fn replace_line_timestamp(
+    line: &[u8],
+    converted: Option<(usize, usize, EpochTimestamp)>,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let mut replaced = Vec::new();
+    match converted {
+        Some((start, end, timestamp)) => {
+            let original = &line[start..end];
+            replaced.extend_from_slice(&line[..start]);
+            record_timestamp(&timestamp, original, start, options, state);
+            if !append_custom_format(&timestamp, original, options, &mut replaced) {
+                if options.annotate {
+                    replaced.extend_from_slice(original);
+                    replaced.push(ASCII_SPACE);
+                }
+                append_epoch_timestamp(&timestamp, options, state, &mut replaced);
+            }
+            replaced.extend_from_slice(&line[end..]);
+        }
+        None => replaced.extend_from_slice(line),
+    }
+
+    if let Some(&byte) = line.last() {
+        state.preceding_byte = Some(byte);
+    }
+    state.offset += line.len() as u64;
+    replaced
+}
+
+fn append_epoch_timestamp(
+    timestamp: &EpochTimestamp,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+    append_buffer: &mut Vec<u8>,
+) {
+    if options.color {
+        append_buffer.extend_from_slice(ANSI_HIGHLIGHT);
+    }
+    append_buffer.extend_from_slice(render_timestamp(timestamp, options, state).as_bytes());
+    if options.color {
+        append_buffer.extend_from_slice(ANSI_RESET);
+    }
+}
+
+/// Renders a timestamp, with the configured prefix and suffix but without colour.
+fn render_timestamp(
+    timestamp: &EpochTimestamp,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> String {
+    let date_time = timestamp.converted_date_time(options);
+    let delta = date_time.signed_duration_since(state.previous.unwrap_or(date_time));
+    let first = *state.first.get_or_insert(date_time);
+    state.previous = Some(date_time);
+
+    let timestamp_str = match options.mode {
+        TimestampMode::Absolute => absolute_timestamp(&date_time, timestamp, options),
+        TimestampMode::Delta => format_delta(delta),
+        TimestampMode::AbsoluteWithDelta => format!(
+            "{} {}",
+            absolute_timestamp(&date_time, timestamp, options),
+            format_delta(delta)
+        ),
+        TimestampMode::Anchor => format!("T{}", format_delta(date_time - first)),
+    };
+    format!("{}{}{}", options.prefix, timestamp_str, options.suffix)
+}
+
+fn absolute_timestamp(
+    date_time: &DateTime<Utc>,
+    timestamp: &EpochTimestamp,
+    options: &ReplacerOptions,
+) -> String {
+    let fractional_digits = match options.rounding {
+        Some(_) => 0,
+        None => options
+            .fractional_digits
+            .unwrap_or(timestamp.fractional_digits),
+    };
+    let timestamp = &EpochTimestamp {
+        fractional_digits,
+        ..*timestamp
+    };
+    if let Some(offset) = timestamp.offset {
+        return format_timestamp(&date_time.with_timezone(&offset), timestamp, options);
+    }
+    match options.zone {
+        OutputZone::Utc => format_timestamp(date_time, timestamp, options),
+        OutputZone::Local => format_timestamp(&date_time.with_timezone(&Local), timestamp, options),
+        OutputZone::Named(tz) => {
+            format_timestamp(&date_time.with_timezone(&tz), timestamp, options)
+        }
+    }
+}
+
+/// Formats a signed duration as `+HH:MM:SS.mmm`; hours are not wrapped at a day.
+fn format_delta(delta: chrono::Duration) -> String {
+    let sign = if delta < chrono::Duration::zero() {
+        ASCII_HYPHEN
+    } else {
+        ASCII_PLUS
+    };
+    let millis = delta.num_milliseconds().unsigned_abs();
+    format!(
+        "{}{:02}:{:02}:{:02}.{:03}",
+        sign as char,
+        millis / 3_600_000,
+        (millis / 60_000) % 60,
+        (millis / 1000) % 60,
+        millis % 1000
+    )
+}
+
+fn format_timestamp<Tz: TimeZone>(
+    date_time: &DateTime<Tz>,
+    timestamp: &EpochTimestamp,
+    options: &ReplacerOptions,
+) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let rounded;
+    let date_time = match options.rounding {
+        Some(rounding) => {
+            rounded = rounding.apply(date_time);
+            &rounded
+        }
+        None => date_time,
+    };
+    match &options.style {
+        OutputStyle::Default => default_format(date_time, timestamp),
+        OutputStyle::Rfc3339 => date_time.to_rfc3339_opts(rfc3339_precision(timestamp), true),
+        OutputStyle::Format(format) => date_time.format(format).to_string(),
+        OutputStyle::Epoch(unit) => epoch_value(date_time, *unit).to_string(),
+    }
+}
+
+/// The epoch value of `date_time` in `unit`, truncating any finer precision.
+fn epoch_value<Tz: TimeZone>(date_time: &DateTime<Tz>, unit: EpochUnit) -> i64 {
+    match unit {
+        EpochUnit::Seconds => date_time.timestamp(),
+        EpochUnit::Milliseconds => date_time.timestamp_millis(),
+        EpochUnit::Microseconds => date_time.timestamp_micros(),
+    }
+}
+
+/// RFC 3339 output is limited to whole seconds or 3, 6 or 9 fractional digits, so
+/// other precisions are widened to the next of these.
+fn rfc3339_precision(timestamp: &EpochTimestamp) -> SecondsFormat {
+    match timestamp.fractional_digits {
+        0 => SecondsFormat::Secs,
+        1..=3 => SecondsFormat::Millis,
+        4..=6 => SecondsFormat::Micros,
+        _ => SecondsFormat::Nanos,
+    }
+}
+
+/// Formats `date_time` with the same sub-second precision as the input timestamp,
+/// followed by the timezone abbreviation (or the UTC offset, for the local timezone).
+fn default_format<Tz: TimeZone>(date_time: &DateTime<Tz>, timestamp: &EpochTimestamp) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let mut formatted = date_time.format("%Y-%m-%d %H:%M:%S").to_string();
+    if timestamp.fractional_digits != 0 {
+        let fraction = format!("{:09}", date_time.timestamp_subsec_nanos());
+        formatted.push('.');
+        formatted.push_str(&fraction[..timestamp.fractional_digits]);
+    }
+    formatted.push_str(&date_time.format(" %Z").to_string());
+    formatted
+}
+
+fn is_tick_count(input: &[u8]) -> bool {
+    input.len() == DIGITS_IN_TICK_COUNT && input.iter().all(u8::is_ascii_digit)
+}
+
+fn is_webkit_timestamp(input: &[u8]) -> bool {
+    input.len() == DIGITS_IN_WEBKIT_TIMESTAMP && input.iter().all(u8::is_ascii_digit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "fs")]
+    use std::time::*;
+
+    #[test]
+    fn convert_to_hex() {
+        assert_eq!("0x9fbf1", to_hex_chars(654321))
+    }
+
+    #[test]
+    fn convert_from_hex() {
+        assert_eq!("654321", to_decimal_chars("9fbf1"))
+    }
+
+    #[test]
+    fn convert_from_hex_uppercase() {
+        assert_eq!("654321", to_decimal_chars("09FBF1"))
+    }
+
+    #[test]
+    fn convert_from_hex_with_leading_zero() {
+        assert_eq!("654321", to_decimal_chars("09fbf1"))
+    }
+
+    #[test]
+    fn convert_from_hex_with_leading_zero_and_hex_indicator() {
+        assert_eq!("654321", to_decimal_chars("0x09fbf1"))
+    }
+
+
+    #[test]
+    fn replace_valid_timestamp_with_millisecond_precision() {
+        let input = "1530216070317a";
+        let expected = "[2018-06-28 20:01:10.317 UTC]a";
+        let mut input_buffer = Vec::new();
+        input_buffer.extend_from_slice(input.as_bytes());
+        let response = replace_epoch_timestamps(&input_buffer, false);
+
+        assert_eq!(0, response.left_over_data);
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn replace_valid_timestamp_with_second_precision() {
+        let input = "1530216070a";
+        let expected = "[2018-06-28 20:01:10 UTC]a";
+        let mut input_buffer = Vec::new();
+        input_buffer.extend_from_slice(input.as_bytes());
+        let response = replace_epoch_timestamps(&input_buffer, false);
+
+        assert_eq!(0, response.left_over_data);
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn replace_valid_timestamp_with_microsecond_precision() {
+        let input = "1530216070317123a";
+        let expected = "[2018-06-28 20:01:10.317123 UTC]a";
+        let mut input_buffer = Vec::new();
+        input_buffer.extend_from_slice(input.as_bytes());
+        let response = replace_epoch_timestamps(&input_buffer, false);
+
+        assert_eq!(0, response.left_over_data);
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn replace_microsecond_timestamp_at_end_of_input() {
+        let input = "prefix1530216070000001";
+        let expected = "prefix[2018-06-28 20:01:10.000001 UTC]";
+        let mut input_buffer = Vec::new();
+        input_buffer.extend_from_slice(input.as_bytes());
+        let response = replace_epoch_timestamps(&input_buffer, true);
+
+        assert_eq!(0, response.left_over_data);
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn replace_fractional_second_timestamp() {
+        let input = "ts=1530216070.317 ts=1530216070.5 ts=1530216070.000000001 end";
+        let expected = "ts=[2018-06-28 20:01:10.317 UTC] ts=[2018-06-28 20:01:10.5 UTC] ts=[2018-06-28 20:01:10.000000001 UTC] end";
+        let mut input_buffer = Vec::new();
+        input_buffer.extend_from_slice(input.as_bytes());
+        let response = replace_epoch_timestamps(&input_buffer, false);
+
+        assert_eq!(0, response.left_over_data);
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn replace_second_timestamp_followed_by_full_stop() {
+        let input = "at 1530216070. Done";
+        let expected = "at [2018-06-28 20:01:10 UTC]. Done";
+        let mut input_buffer = Vec::new();
+        input_buffer.extend_from_slice(input.as_bytes());
+        let response = replace_epoch_timestamps(&input_buffer, true);
+
+        assert_eq!(0, response.left_over_data);
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn do_not_replace_fractional_timestamp_with_too_many_digits() {
+        let input = "1530216070.1234567890 ";
+        let mut input_buffer = Vec::new();
+        input_buffer.extend_from_slice(input.as_bytes());
+        let response = replace_epoch_timestamps(&input_buffer, false);
+
+        assert_eq!(0, response.left_over_data);
+        compare_bytes(input.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn indicate_trailing_fractional_timestamp() {
+        let input = "prefix1530216070.31";
+        let expected = "prefix";
+        let mut input_buffer = Vec::new();
+        input_buffer.extend_from_slice(input.as_bytes());
+        let response = replace_epoch_timestamps(&input_buffer, false);
+
+        assert_eq!(13, response.left_over_data);
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn replace_timestamps_using_configured_format() {
+        let input = "a 1530216070317 b 1530216070 c";
+        let expected = "a [2018-06-28T20:01:10.317Z] b [2018-06-28T20:01:10.000Z] c";
+        let options = ReplacerOptions::new()
+            .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+            .unwrap();
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        assert_eq!(0, response.left_over_data);
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn replace_timestamps_in_configured_timezone() {
+        let input = "a 1530216070317 b 1514808000 c";
+        let expected = "a [2018-06-28 16:01:10.317 EDT] b [2018-01-01 07:00:00 EST] c";
+        let options = ReplacerOptions::new().timezone("America/New_York").unwrap();
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn combine_timezone_with_format() {
+        let input = "1530216070317";
+        let expected = "[2018-06-28T21:01:10.317+01:00]";
+        let options = ReplacerOptions::new()
+            .timezone("Europe/London")
+            .unwrap()
+            .format("%Y-%m-%dT%H:%M:%S%.3f%:z")
+            .unwrap();
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn replace_timestamps_in_local_timezone() {
+        let local = Utc
+            .timestamp_opt(1530216070, 317_000_000)
+            .unwrap()
+            .with_timezone(&Local);
+        let expected = format!("[{}]", local.format("%Y-%m-%d %H:%M:%S%.3f %:z"));
+        let options = ReplacerOptions::new().zone(OutputZone::Local);
+        let response =
+            replace_epoch_timestamps_with_options("1530216070317".as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn reject_unknown_timezone() {
+        assert!(ReplacerOptions::new()
+            .timezone("Mars/Olympus_Mons")
+            .is_err());
+    }
+
+    #[test]
+    fn replace_timestamps_as_rfc3339() {
+        let input = "a 1530216070317 b 1530216070 c 1530216070.5 d 1530216070317123";
+        let expected = "a [2018-06-28T20:01:10.317Z] b [2018-06-28T20:01:10Z] c [2018-06-28T20:01:10.500Z] d [2018-06-28T20:01:10.317123Z]";
+        let options = ReplacerOptions::new().style(OutputStyle::Rfc3339);
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn replace_timestamps_as_rfc3339_with_offset() {
+        let expected = "[2018-06-28T21:01:10.317+01:00]";
+        let options = ReplacerOptions::new()
+            .style(OutputStyle::Rfc3339)
+            .timezone("Europe/London")
+            .unwrap();
+        let response =
+            replace_epoch_timestamps_with_options("1530216070317".as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn render_timestamps_with_fixed_precision() {
+        let input = "a 1530216070317 b 1530216070 c 1530216070317123";
+        let expected = "a [2018-06-28 20:01:10.317 UTC] b [2018-06-28 20:01:10.000 UTC] c [2018-06-28 20:01:10.317 UTC]";
+        let options = ReplacerOptions::new().fractional_digits(3).unwrap();
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
+        assert!(ReplacerOptions::new().fractional_digits(10).is_err());
+    }
+
+    #[test]
+    fn round_or_truncate_rendered_timestamps() {
+        let input = "a 1530216070817 b 1530217799";
+        let rendered = |rounding: Rounding| {
+            replace_epoch_timestamps_str_with_options(
+                input,
+                &ReplacerOptions::new().rounding(rounding),
+            )
+        };
+        assert_eq!(
+            "a [2018-06-28 20:01:11 UTC] b [2018-06-28 20:29:59 UTC]",
+            rendered(Rounding::Nearest(TimeUnit::Second))
+        );
+        assert_eq!(
+            "a [2018-06-28 20:01:00 UTC] b [2018-06-28 20:30:00 UTC]",
+            rendered(Rounding::Nearest(TimeUnit::Minute))
+        );
+        assert_eq!(
+            "a [2018-06-28 20:00:00 UTC] b [2018-06-28 20:00:00 UTC]",
+            rendered(Rounding::Truncate(TimeUnit::Hour))
+        );
+    }
+
+    #[test]
+    fn normalize_timestamps_to_one_unit() {
+        let input = "a 1530216070 b 1530216070.5 c 1530216070317123";
+        let expected = "a 1530216070000 b 1530216070500 c 1530216070317";
+        let options = ReplacerOptions::new()
+            .style(OutputStyle::Epoch(EpochUnit::Milliseconds))
+            .prefix("")
+            .suffix("");
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn replace_hexadecimal_timestamps() {
+        let input = "a 0x5b353e86 b 0x16447fc3cad c 0x5b353e8 d 0x5b353e86";
+        let expected = "a [2018-06-28 20:01:10 UTC] b [2018-06-28 20:01:10.317 UTC] c 0x5b353e8 d ";
+        let options = ReplacerOptions::new().hex_timestamps(true);
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), false, &options);
+
+        assert_eq!(10, response.left_over_data);
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn replace_tai64n_labels() {
+        let input = "@400000005b353e9002b47e38 multilog line @4000 @400000005b35";
+        let expected = "[2018-06-28 20:01:10.045383224 UTC] multilog line @4000 ";
+        let options = ReplacerOptions::new().tai64n_timestamps(true);
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), false, &options);
+
+        assert_eq!(13, response.left_over_data);
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn replace_at_prefixed_timestamps_with_their_prefix() {
+        let input = "journalctl --since @1530216070 user@host @42 @";
+        let expected = "journalctl --since [2018-06-28 20:01:10 UTC] user@host @42 ";
+        let response = replace_epoch_timestamps(input.as_bytes(), false);
+
+        assert_eq!(1, response.left_over_data);
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn annotate_snowflake_ids() {
+        let input = "id=461984425593279028 ts=1530216070 small=461984425";
+        let expected = "id=461984425593279028 [2018-06-28 20:01:10.317 UTC] ts=[2018-06-28 20:01:10 UTC] small=461984425";
+        let options = ReplacerOptions::new().snowflake_ids(DISCORD_SNOWFLAKE_EPOCH);
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
+        assert_eq!(
+            "1012425695051120682 [2018-06-28 20:01:10.317 UTC]",
+            String::from_utf8(
+                replace_epoch_timestamps_with_options(
+                    b"1012425695051120682",
+                    true,
+                    &ReplacerOptions::new().snowflake_ids(TWITTER_SNOWFLAKE_EPOCH)
+                )
+                .data
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn annotate_object_ids() {
+        let input =
+            "{_id: ObjectId(\"5b353e86e1382d1a9c0a1b2c\")} x5b353e86e1382d1a9c0a1b2c 5b353e86";
+        let expected = "{_id: ObjectId(\"5b353e86e1382d1a9c0a1b2c [2018-06-28 20:01:10 UTC]\")} x5b353e86e1382d1a9c0a1b2c 5b353e86";
+        let options = ReplacerOptions::new().object_ids(true);
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn annotate_version_7_uuids() {
+        let input = "a 016447fc-3cad-7a2b-8c3d-4e5f60718293 b 016447fc-3cad-4a2b-8c3d-4e5f60718293 c 016447FC-3CAD-7A2B-BC3D-4E5F60718293-x";
+        let expected = "a 016447fc-3cad-7a2b-8c3d-4e5f60718293 [2018-06-28 20:01:10.317 UTC] b 016447fc-3cad-4a2b-8c3d-4e5f60718293 c 016447FC-3CAD-7A2B-BC3D-4E5F60718293-x";
+        let options = ReplacerOptions::new().uuid_timestamps(true);
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn annotate_ulids() {
+        let input = "a 01CH3ZRF5DABCDEFGHJKMNPQRS b 01ch3zrf5dabcdefghjkmnpqrs c 01CH3ZRF5DABCDEFGHJKMNPQRI d 81CH3ZRF5DABCDEFGHJKMNPQRS";
+        let expected = "a 01CH3ZRF5DABCDEFGHJKMNPQRS [2018-06-28 20:01:10.317 UTC] b 01ch3zrf5dabcdefghjkmnpqrs [2018-06-28 20:01:10.317 UTC] c 01CH3ZRF5DABCDEFGHJKMNPQRI d 81CH3ZRF5DABCDEFGHJKMNPQRS";
+        let options = ReplacerOptions::new().ulid_timestamps(true);
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
 
-            if replacement.left_over_data != 0 {
-                for _ in 0..replacement.left_over_data {
-                    tmp_buffer.push(data_buffer.pop().expect("Invalid state"))
-                }
-                tmp_buffer.reverse();
-                data_buffer.clear();
-                data_buffer.extend(&tmp_buffer);
-            } else {
-                data_buffer.clear();
-            }
-        } else {
-            break;
-        }
+    #[test]
+    fn render_git_raw_dates_in_their_offset() {
+        let input =
+            "author a <a@b.c> 1530216070 +0100\ncommitter 1530216070 -0930 x 1530216070 +01";
+        let expected = "author a <a@b.c> [2018-06-28 21:01:10 +01:00]\ncommitter [2018-06-28 10:31:10 -09:30] x [2018-06-28 20:01:10 UTC] +01";
+        let options = ReplacerOptions::new().git_timestamps(true);
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
     }
-    output.flush().expect("Error flushing output")
-}
 
-pub struct ReplacementResult {
-    pub data: Vec<u8>,
-    pub left_over_data: u64,
-}
+    #[test]
+    fn replace_timestamps_with_java_long_suffix() {
+        let input = "a 1530216070317L b 1530216070l, c 1530216070Lx";
+        let expected = "a [2018-06-28 20:01:10.317 UTC] b [2018-06-28 20:01:10 UTC], c [2018-06-28 20:01:10 UTC]Lx";
+        let response = replace_epoch_timestamps(input.as_bytes(), true);
 
-fn replace_epoch_timestamps(input: &Vec<u8>, end_of_input: bool) -> ReplacementResult {
-    replace_epoch_timestamps_in_buffer(input, input.len(), end_of_input)
-}
+        compare_bytes(expected.as_bytes(), &response.data);
 
-fn replace_epoch_timestamps_in_buffer(
-    input: &Vec<u8>,
-    input_length: usize,
-    end_of_input: bool,
-) -> ReplacementResult {
-    let mut replaced: Vec<u8> = Vec::new();
-    let mut integer_accumulator = Vec::new();
-    for index in 0..input_length {
-        if input[index].is_ascii_digit() {
-            integer_accumulator.push(input[index]);
-        } else {
-            process_possible_timestamp(&mut integer_accumulator, &mut replaced);
-            replaced.push(input[index]);
-        }
+        let partial = replace_epoch_timestamps("a 1530216070L".as_bytes(), false);
+        assert_eq!(11, partial.left_over_data);
     }
-    if end_of_input {
-        process_possible_timestamp(&mut integer_accumulator, &mut replaced);
+
+    #[test]
+    fn replace_timestamps_with_grouped_digits() {
+        let input = "a 1_530_216_070 b 1,530,216,070,317 c 1530216070,1530216070 d 1_530,216_070 e 15_30_216_070";
+        let expected = "a [2018-06-28 20:01:10 UTC] b [2018-06-28 20:01:10.317 UTC] c [2018-06-28 20:01:10 UTC],[2018-06-28 20:01:10 UTC] d 1_530,216_070 e 15_30_216_070";
+        let options = ReplacerOptions::new().digit_separators("_,");
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
     }
 
-    if replaced.len() != 0 {
-        ReplacementResult {
-            data: replaced,
-            left_over_data: integer_accumulator.len() as u64,
-        }
-    } else {
-        ReplacementResult {
-            data: input.to_vec(),
-            left_over_data: integer_accumulator.len() as u64,
-        }
+    #[test]
+    fn replace_timestamps_in_scientific_notation() {
+        let input =
+            "a 1.53021607e9 b 1.530216070317E12 c 1.5302160703171234e+15 d 1.5e3 e 1.53021607";
+        let expected = "a [2018-06-28 20:01:10 UTC] b [2018-06-28 20:01:10.317 UTC] c [2018-06-28 20:01:10.317123 UTC] d 1.5e3 e 1.53021607";
+        let options = ReplacerOptions::new().scientific_timestamps(true);
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
     }
-}
 
-fn process_possible_timestamp(integer_accumulator: &mut Vec<u8>, replaced: &mut Vec<u8>) {
-    if is_epoch_millisecond_timestamp(&integer_accumulator)
-        || is_epoch_second_timestamp(&integer_accumulator)
-    {
-        append_epoch_timestamp(integer_accumulator, replaced)
+    #[test]
+    fn replace_negative_timestamps() {
+        let input = "a -315619200 b -0315619200.5 c x-315619200 d -1530216070317";
+        let expected = "a [1960-01-01 00:00:00 UTC] b [1959-12-31 23:59:59.5 UTC] c x-315619200 d [1921-07-06 03:58:49.683 UTC]";
+        let options = ReplacerOptions::new()
+            .negative_timestamps(true)
+            .min_year(1900);
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
     }
-}
 
-fn append_epoch_timestamp(integer_accumulator: &mut Vec<u8>, append_buffer: &mut Vec<u8>) {
-    let digit_count = integer_accumulator.len();
-    let timestamp: i64 = ascii_to_integer(integer_accumulator) as i64;
+    #[test]
+    fn classify_epochs_by_magnitude_or_digit_count() {
+        let input = "a 999999999 b 9530216070317 c 1530216070317 d 946684800000 e 4530216070";
+        let by_magnitude = replace_epoch_timestamps_with_options(
+            input.as_bytes(),
+            true,
+            &ReplacerOptions::new().classification(EpochClassification::Magnitude),
+        );
+        let by_digit_count = replace_epoch_timestamps_with_options(
+            input.as_bytes(),
+            true,
+            &ReplacerOptions::new().max_year(2300),
+        );
 
-    let nanos: u32 = match digit_count {
-        DIGITS_IN_EPOCH_MILLISECOND_TIMESTAMP => {
-            (timestamp.rem(1000) as i64 * NANOS_PER_MILLISECOND) as u32
-        }
-        DIGITS_IN_EPOCH_SECOND_TIMESTAMP => 0 as u32,
-        _ => panic!("Cannot handle {} digits", digit_count),
-    };
-    let seconds: i64 = match digit_count {
-        DIGITS_IN_EPOCH_MILLISECOND_TIMESTAMP => timestamp / 1000,
-        DIGITS_IN_EPOCH_SECOND_TIMESTAMP => timestamp,
-        _ => panic!("Cannot handle {} digits", digit_count),
-    };
+        compare_bytes(
+            "a [2001-09-09 01:46:39 UTC] b 9530216070317 c [2018-06-28 20:01:10.317 UTC] \
+             d [2000-01-01 00:00:00.000 UTC] e 4530216070"
+                .as_bytes(),
+            &by_magnitude.data,
+        );
+        compare_bytes(
+            "a 999999999 b [2272-01-01 10:14:30.317 UTC] c [2018-06-28 20:01:10.317 UTC] \
+             d 946684800000 e [2113-07-23 01:21:10 UTC]"
+                .as_bytes(),
+            &by_digit_count.data,
+        );
+    }
 
-    let date_time = Utc.timestamp(seconds, nanos);
-    let timestamp_str = format!("[{}]", date_time);
-    append_buffer.extend_from_slice(timestamp_str.as_bytes());
-    integer_accumulator.clear()
-}
+    #[test]
+    fn annotate_timestamps_with_converted_value() {
+        let input = "a 1530216070317 b 1530216070.5 c 42";
+        let expected = "a 1530216070317 [2018-06-28 20:01:10.317 UTC] b 1530216070.5 [2018-06-28 20:01:10.5 UTC] c 42";
+        let options = ReplacerOptions::new().annotate(true);
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
 
-fn is_epoch_millisecond_timestamp(input: &Vec<u8>) -> bool {
-    return input.len() == DIGITS_IN_EPOCH_MILLISECOND_TIMESTAMP;
-}
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
 
-fn is_epoch_second_timestamp(input: &Vec<u8>) -> bool {
-    return input.len() == DIGITS_IN_EPOCH_SECOND_TIMESTAMP;
-}
+    #[test]
+    fn replace_timestamps_with_delta_from_previous() {
+        let input = "a 1530216070317 b 1530216073531 c 1530212470 d 1530216070";
+        let expected = "a [+00:00:00.000] b [+00:00:03.214] c [-01:00:03.531] d [+01:00:00.000]";
+        let options = ReplacerOptions::new().mode(TimestampMode::Delta);
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
 
     #[test]
-    fn convert_to_hex() {
-        assert_eq!("0x9fbf1", to_hex_chars(654321))
+    fn stream_timestamps_with_absolute_time_and_delta() {
+        let options = ReplacerOptions::new().mode(TimestampMode::AbsoluteWithDelta);
+        let mut replacer = StreamingReplacer::with_options(options);
+        let mut output = Vec::new();
+
+        output.extend(replacer.push_chunk("1530216070317\n".as_bytes()));
+        output.extend(replacer.push_chunk("15302161".as_bytes()));
+        output.extend(replacer.push_chunk("03531\n".as_bytes()));
+        output.extend(replacer.finish());
+
+        compare_bytes(
+            "[2018-06-28 20:01:10.317 UTC +00:00:00.000]\n[2018-06-28 20:01:43.531 UTC +00:00:33.214]\n"
+                .as_bytes(),
+            &output,
+        );
     }
 
     #[test]
-    fn convert_from_hex() {
-        assert_eq!("654321", to_decimal_chars(&"9fbf1".to_string()))
+    fn replace_timestamps_with_offset_from_first() {
+        let input = "a 1530216070317 b 1530216382318 c 1530216070 d 1530219670317";
+        let expected =
+            "a [T+00:00:00.000] b [T+00:05:12.001] c [T-00:00:00.317] d [T+01:00:00.000]";
+        let options = ReplacerOptions::new().mode(TimestampMode::Anchor);
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
     }
 
     #[test]
-    fn convert_from_hex_uppercase() {
-        assert_eq!("654321", to_decimal_chars(&"09FBF1".to_string()))
+    fn replace_timestamps_with_configured_delimiters() {
+        let input = "a 1530216070 b 1530216070317";
+        let expected = "a (2018-06-28 20:01:10 UTC) b \x1b[1m2018-06-28 20:01:10.317 UTC\x1b[0m";
+        let options = ReplacerOptions::new().prefix("(").suffix(")");
+        let response =
+            replace_epoch_timestamps_with_options(&input.as_bytes()[..12], true, &options);
+        let ansi_options = ReplacerOptions::new().prefix("\x1b[1m").suffix("\x1b[0m");
+        let ansi_response =
+            replace_epoch_timestamps_with_options(&input.as_bytes()[12..], true, &ansi_options);
+
+        let mut output = response.data;
+        output.extend(ansi_response.data);
+        compare_bytes(expected.as_bytes(), &output);
     }
 
     #[test]
-    fn convert_from_hex_with_leading_zero() {
-        assert_eq!("654321", to_decimal_chars(&"09fbf1".to_string()))
+    fn replace_timestamps_without_delimiters() {
+        let input = "at 1530216070317.";
+        let expected = "at 2018-06-28 20:01:10.317 UTC.";
+        let options = ReplacerOptions::new().prefix("").suffix("");
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
     }
 
     #[test]
-    fn convert_from_hex_with_leading_zero_and_hex_indicator() {
-        assert_eq!("654321", to_decimal_chars(&"0x09fbf1".to_string()))
+    fn highlight_timestamps_with_colour() {
+        let input = "a 1530216070 b";
+        let expected = "a \x1b[36m[2018-06-28 20:01:10 UTC]\x1b[0m b";
+        let options = ReplacerOptions::new().color(true);
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
+
+        compare_bytes(expected.as_bytes(), &response.data);
     }
 
+    #[test]
+    fn leave_implausible_timestamps_untouched() {
+        let input = "order 9876543210 at 1530216070 ref 0000000001234 t 1530216070.5";
+        let expected = "order 9876543210 at [2018-06-28 20:01:10 UTC] ref 0000000001234 t [2018-06-28 20:01:10.5 UTC]";
+        let response = replace_epoch_timestamps(input.as_bytes(), true);
+
+        compare_bytes(expected.as_bytes(), &response.data);
+    }
 
     #[test]
-    fn replace_valid_timestamp_with_millisecond_precision() {
-        let input = "1530216070317a";
-        let expected = "[2018-06-28 20:01:10.317 UTC]a";
-        let mut input_buffer = Vec::new();
-        input_buffer.extend_from_slice(&input.as_bytes());
-        let response = replace_epoch_timestamps(&input_buffer, false);
+    fn replace_timestamps_within_configured_year_range() {
+        let input = "a 0946684800 b 1530216070 c 1230216070";
+        let expected = "a 0946684800 b 1530216070 c [2008-12-25 14:41:10 UTC]";
+        let options = ReplacerOptions::new().min_year(2001).max_year(2010);
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
 
-        assert_eq!(0, response.left_over_data);
         compare_bytes(expected.as_bytes(), &response.data);
     }
 
     #[test]
-    fn replace_valid_timestamp_with_second_precision() {
-        let input = "1530216070a";
-        let expected = "[2018-06-28 20:01:10 UTC]a";
-        let mut input_buffer = Vec::new();
-        input_buffer.extend_from_slice(&input.as_bytes());
-        let response = replace_epoch_timestamps(&input_buffer, false);
+    fn only_replace_delimited_timestamps() {
+        let input = "id1530216070x at 1530216070, (1530216070317) v1.1530216070 ts=1530216070.5";
+        let expected = "id1530216070x at [2018-06-28 20:01:10 UTC], ([2018-06-28 20:01:10.317 UTC]) v1.1530216070 ts=[2018-06-28 20:01:10.5 UTC]";
+        let options = ReplacerOptions::new().boundaries(BoundaryPolicy::delimited());
+        let response = replace_epoch_timestamps_with_options(input.as_bytes(), true, &options);
 
-        assert_eq!(0, response.left_over_data);
         compare_bytes(expected.as_bytes(), &response.data);
     }
 
+    #[test]
+    fn stream_delimited_timestamps_across_chunks() {
+        let options = ReplacerOptions::new().boundaries(BoundaryPolicy::delimited_by(" "));
+        let mut replacer = StreamingReplacer::with_options(options);
+        let mut output = Vec::new();
+
+        output.extend(replacer.push_chunk("a".as_bytes()));
+        output.extend(replacer.push_chunk("1530216070 ".as_bytes()));
+        output.extend(replacer.push_chunk("15302160".as_bytes()));
+        output.extend(replacer.push_chunk("70".as_bytes()));
+        output.extend(replacer.finish());
+
+        compare_bytes("a1530216070 [2018-06-28 20:01:10 UTC]".as_bytes(), &output);
+    }
+
+    #[test]
+    fn reject_invalid_format() {
+        assert!(ReplacerOptions::new().format("%Y-%Q").is_err());
+    }
+
     #[test]
     fn do_not_replace_millisecond_timestamp_at_end_of_input() {
         let input = "1530216070317";
-        let expected = "1530216070317";
+        let expected = "";
         let mut input_buffer = Vec::new();
-        input_buffer.extend_from_slice(&input.as_bytes());
+        input_buffer.extend_from_slice(input.as_bytes());
         let response = replace_epoch_timestamps(&input_buffer, false);
 
         assert_eq!(13, response.left_over_data);
@@ -302,9 +2821,9 @@ mod tests {
     #[test]
     fn do_not_replace_second_timestamp_at_end_of_input() {
         let input = "1530216070";
-        let expected = "1530216070";
+        let expected = "";
         let mut input_buffer = Vec::new();
-        input_buffer.extend_from_slice(&input.as_bytes());
+        input_buffer.extend_from_slice(input.as_bytes());
         let response = replace_epoch_timestamps(&input_buffer, false);
 
         assert_eq!(10, response.left_over_data);
@@ -316,7 +2835,7 @@ mod tests {
         let input = "1530216070317";
         let expected = "[2018-06-28 20:01:10.317 UTC]";
         let mut input_buffer = Vec::new();
-        input_buffer.extend_from_slice(&input.as_bytes());
+        input_buffer.extend_from_slice(input.as_bytes());
         let response = replace_epoch_timestamps(&input_buffer, true);
 
         assert_eq!(0, response.left_over_data);
@@ -328,7 +2847,7 @@ mod tests {
         let input = "1530216070";
         let expected = "[2018-06-28 20:01:10 UTC]";
         let mut input_buffer = Vec::new();
-        input_buffer.extend_from_slice(&input.as_bytes());
+        input_buffer.extend_from_slice(input.as_bytes());
         let response = replace_epoch_timestamps(&input_buffer, true);
 
         assert_eq!(0, response.left_over_data);
@@ -340,7 +2859,7 @@ mod tests {
         let input = "prefix1530216070317suffix";
         let expected = "prefix[2018-06-28 20:01:10.317 UTC]suffix";
         let mut input_buffer = Vec::new();
-        input_buffer.extend_from_slice(&input.as_bytes());
+        input_buffer.extend_from_slice(input.as_bytes());
         let response = replace_epoch_timestamps(&input_buffer, false);
 
         assert_eq!(0, response.left_over_data);
@@ -352,7 +2871,7 @@ mod tests {
         let input = "prefix1530216070suffix";
         let expected = "prefix[2018-06-28 20:01:10 UTC]suffix";
         let mut input_buffer = Vec::new();
-        input_buffer.extend_from_slice(&input.as_bytes());
+        input_buffer.extend_from_slice(input.as_bytes());
         let response = replace_epoch_timestamps(&input_buffer, false);
 
         assert_eq!(0, response.left_over_data);
@@ -364,7 +2883,7 @@ mod tests {
         let input = "prefix1530216070middle1530216070suffix";
         let expected = "prefix[2018-06-28 20:01:10 UTC]middle[2018-06-28 20:01:10 UTC]suffix";
         let mut input_buffer = Vec::new();
-        input_buffer.extend_from_slice(&input.as_bytes());
+        input_buffer.extend_from_slice(input.as_bytes());
         let response = replace_epoch_timestamps(&input_buffer, false);
 
         assert_eq!(0, response.left_over_data);
@@ -377,7 +2896,7 @@ mod tests {
         let expected =
             "prefix[2018-06-28 20:01:10.317 UTC]middle[2018-06-28 20:01:10.317 UTC]suffix";
         let mut input_buffer = Vec::new();
-        input_buffer.extend_from_slice(&input.as_bytes());
+        input_buffer.extend_from_slice(input.as_bytes());
         let response = replace_epoch_timestamps(&input_buffer, false);
 
         assert_eq!(0, response.left_over_data);
@@ -389,13 +2908,42 @@ mod tests {
         let input = "prefix15302160";
         let expected = "prefix";
         let mut input_buffer = Vec::new();
-        input_buffer.extend_from_slice(&input.as_bytes());
+        input_buffer.extend_from_slice(input.as_bytes());
         let response = replace_epoch_timestamps(&input_buffer, false);
 
         assert_eq!(8, response.left_over_data);
         compare_bytes(expected.as_bytes(), &response.data);
     }
 
+    #[test]
+    fn write_replaced_data_to_writer() {
+        let input = "a 1530216070 b 15302160";
+        let expected = "a [2018-06-28 20:01:10 UTC] b ";
+        let mut output = Vec::new();
+        let left_over_data = replace_epoch_timestamps_to(input.as_bytes(), false, &mut output)
+            .expect("Failed to write");
+
+        assert_eq!(8, left_over_data);
+        compare_bytes(expected.as_bytes(), &output);
+    }
+
+    #[test]
+    fn borrow_input_without_timestamps() {
+        let unchanged = replace_epoch_timestamps_cow(b"no timestamps 123", true);
+        let replaced = replace_epoch_timestamps_cow(b"at 1530216070 and 123", true);
+
+        assert!(matches!(
+            unchanged.data,
+            Cow::Borrowed(b"no timestamps 123")
+        ));
+        assert!(matches!(replaced.data, Cow::Owned(_)));
+        compare_bytes(
+            "at [2018-06-28 20:01:10 UTC] and 123".as_bytes(),
+            &replaced.data,
+        );
+    }
+
+    #[cfg(feature = "fs")]
     #[test]
     fn replace_in_file() {
         let mut open_options = OpenOptions::new();
@@ -409,16 +2957,17 @@ mod tests {
 
         for _ in 0..100 {
             test_data_file
-                .write(test_data.as_bytes())
+                .write_all(test_data.as_bytes())
                 .expect("Failed to write file");
-            expected.push_str(&"abcdef\nsome[2018-06-28 20:01:10 UTC]timestamp\nfoo\nprefix[2018-06-28 20:01:10.317 UTC]suffix\nbar\n\n".to_string());
+            expected.push_str("abcdef\nsome[2018-06-28 20:01:10 UTC]timestamp\nfoo\nprefix[2018-06-28 20:01:10.317 UTC]suffix\nbar\n\n");
         }
         test_data_file.flush().expect("Failed to flush file");
 
-        process_files(&[name]);
-        assert_file_content(name2 + &".depoch".to_string(), expected.as_bytes())
+        process_files(&[name], &ReplacerOptions::new());
+        assert_file_content(name2 + ".depoch", expected.as_bytes())
     }
 
+    #[cfg(feature = "fs")]
     #[test]
     fn replace_in_file_over_buffer_boundary() {
         let mut open_options = OpenOptions::new();
@@ -431,20 +2980,522 @@ mod tests {
 
         for _ in 0..BUFFER_SIZE - 4 {
             test_data_file
-                .write("a".as_bytes())
+                .write_all("a".as_bytes())
                 .expect("Failed to write file");
-            expected.push_str(&"a".to_string());
+            expected.push('a');
         }
         test_data_file
-            .write("1530216070317".as_bytes())
+            .write_all("1530216070317".as_bytes())
             .expect("Failed to write file");
-        expected.push_str(&"[2018-06-28 20:01:10.317 UTC]".to_string());
+        expected.push_str("[2018-06-28 20:01:10.317 UTC]");
         test_data_file.flush().expect("Failed to flush file");
 
-        process_files(&[name]);
-        assert_file_content(name2 + &".depoch".to_string(), expected.as_bytes())
+        process_files(&[name], &ReplacerOptions::new());
+        assert_file_content(name2 + ".depoch", expected.as_bytes())
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn replace_in_multiple_large_files() {
+        let timestamp = format!("{:?}", Instant::now());
+        let names: Vec<String> = (0..2)
+            .map(|index| format!("/tmp/{}-{}", timestamp, index))
+            .collect();
+        let test_data = "id=12345 at 1530216070317 took 8ms\n";
+        let mut expected: String = String::new();
+
+        for name in &names {
+            let mut test_data_file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(name)
+                .unwrap();
+            for _ in 0..(BUFFER_SIZE * 4) / test_data.len() + 1 {
+                test_data_file
+                    .write_all(test_data.as_bytes())
+                    .expect("Failed to write file");
+            }
+        }
+        for _ in 0..(BUFFER_SIZE * 4) / test_data.len() + 1 {
+            expected.push_str("id=12345 at [2018-06-28 20:01:10.317 UTC] took 8ms\n");
+        }
+
+        process_files(&names, &ReplacerOptions::new());
+        for name in names {
+            assert_file_content(name + ".depoch", expected.as_bytes())
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn replace_in_place_keeping_backup() {
+        let timestamp = format!("{:?}", Instant::now());
+        let name: String = "/tmp/".to_string() + &timestamp + "-in-place";
+        let test_data = "some1530216070timestamp\n";
+        let mut test_data_file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&name)
+            .unwrap();
+        test_data_file
+            .write_all(test_data.as_bytes())
+            .expect("Failed to write file");
+
+        process_files_in_place(
+            std::slice::from_ref(&name),
+            &ReplacerOptions::new(),
+            Some(".bak"),
+        );
+
+        assert_file_content(
+            name.clone(),
+            "some[2018-06-28 20:01:10 UTC]timestamp\n".as_bytes(),
+        );
+        assert_file_content(name.clone() + ".bak", test_data.as_bytes());
+        assert!(!temporary_path(Path::new(&name)).exists());
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn replace_in_gzip_file() {
+        let timestamp = format!("{:?}", Instant::now());
+        let name: String = "/tmp/".to_string() + &timestamp + "-app.log.gz";
+        let mut writer =
+            CompressedWriter::new(File::create(&name).unwrap(), Compression::Gzip).unwrap();
+        writer
+            .write_all("at 1530216070\n".as_bytes())
+            .expect("Failed to write file");
+        writer.finish().expect("Failed to write file");
+
+        process_files(std::slice::from_ref(&name), &ReplacerOptions::new());
+        process_files(
+            std::slice::from_ref(&name),
+            &ReplacerOptions::new().compress_output(true),
+        );
+
+        let expected = "at [2018-06-28 20:01:10 UTC]\n";
+        let stem = "/tmp/".to_string() + &timestamp + "-app.log.depoch";
+        assert_file_content(stem.clone(), expected.as_bytes());
+        let (mut compressed, compression) = open_input(File::open(stem + ".gz").unwrap()).unwrap();
+        let mut decompressed = Vec::new();
+        compressed.read_to_end(&mut decompressed).unwrap();
+        assert_eq!(Compression::Gzip, compression);
+        compare_bytes(expected.as_bytes(), &decompressed);
+    }
+
+    #[test]
+    fn replace_timestamps_split_across_short_reads() {
+        let input = "a1530216070317b12c1530216070\n";
+        let expected = "a[2018-06-28 20:01:10.317 UTC]b12c[2018-06-28 20:01:10 UTC]\n";
+        let mut reader = ChunkedReader {
+            data: input.as_bytes(),
+            chunk_size: 3,
+        };
+        let mut output = Vec::new();
+        let mut read_buffer = [0; BUFFER_SIZE];
+
+        process_input(
+            &mut reader,
+            &mut output,
+            &mut read_buffer,
+            &mut StreamingReplacer::new(),
+            true,
+            None,
+        );
+
+        compare_bytes(expected.as_bytes(), &output);
+    }
+
+    #[test]
+    fn retain_digits_that_are_not_timestamps() {
+        let input = "a123b456789012c";
+        let mut input_buffer = Vec::new();
+        input_buffer.extend_from_slice(input.as_bytes());
+        let response = replace_epoch_timestamps(&input_buffer, true);
+
+        assert_eq!(0, response.left_over_data);
+        compare_bytes(input.as_bytes(), &response.data);
+    }
+
+    #[test]
+    fn stream_timestamp_straddling_chunks() {
+        let mut replacer = StreamingReplacer::new();
+        let mut output = Vec::new();
+
+        output.extend(replacer.push_chunk("prefix153021".as_bytes()));
+        output.extend(replacer.push_chunk("6070".as_bytes()));
+        output.extend(replacer.push_chunk("317 suffix 153021607".as_bytes()));
+        output.extend(replacer.push_chunk("0".as_bytes()));
+        output.extend(replacer.finish());
+
+        compare_bytes(
+            "prefix[2018-06-28 20:01:10.317 UTC] suffix [2018-06-28 20:01:10 UTC]".as_bytes(),
+            &output,
+        );
+    }
+
+    #[test]
+    fn keep_multi_byte_sequences_intact_across_chunks() {
+        let input = "zeit\u{2013}1530216070 \u{1F552}1530216070317 caf\u{E9}";
+        for split in 1..input.len() {
+            let mut replacer = StreamingReplacer::new();
+            let mut output = replacer.push_chunk(&input.as_bytes()[..split]);
+            output.extend(replacer.push_chunk(&input.as_bytes()[split..]));
+            output.extend(replacer.finish());
+
+            assert_eq!(
+                "zeit\u{2013}[2018-06-28 20:01:10 UTC] \u{1F552}[2018-06-28 20:01:10.317 UTC] caf\u{E9}",
+                String::from_utf8(output).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn replace_timestamps_in_a_str() {
+        assert_eq!(
+            "\u{E9}t\u{E9} [2018-06-28 20:01:10 UTC]",
+            replace_epoch_timestamps_str("\u{E9}t\u{E9} 1530216070")
+        );
+        assert_eq!(
+            "{\"ts\": \"[2018-06-28T20:01:10Z]\"}\n",
+            replace_epoch_timestamps_str_with_options(
+                "{\"ts\": 1530216070}\n",
+                &ReplacerOptions::new()
+                    .style(OutputStyle::Rfc3339)
+                    .input_format(InputFormat::json())
+            )
+        );
+    }
+
+    #[test]
+    fn shift_timestamps_before_rendering() {
+        let shifted = |shift: i64, options: ReplacerOptions| {
+            replace_epoch_timestamps_str_with_options(
+                "a 1530216070317 b",
+                &options.shift(chrono::Duration::try_hours(shift).unwrap()),
+            )
+        };
+
+        assert_eq!(
+            "a [2018-06-28 18:01:10.317 UTC] b",
+            shifted(-2, ReplacerOptions::new())
+        );
+        assert_eq!(
+            "a 1530223270317 b",
+            shifted(
+                2,
+                ReplacerOptions::new()
+                    .style(OutputStyle::Epoch(EpochUnit::Milliseconds))
+                    .prefix("")
+                    .suffix("")
+            )
+        );
+        assert_eq!(
+            "a [2018-06-28 20:01:11.317 UTC] b",
+            replace_epoch_timestamps_str_with_options(
+                "a 1530216070817 b",
+                &ReplacerOptions::new().shift(chrono::Duration::try_milliseconds(500).unwrap()),
+            )
+        );
+    }
+
+    #[test]
+    fn replace_invalid_utf8_only_when_lossy() {
+        let input = [
+            "a\u{E9}".as_bytes(),
+            &[0xFF],
+            " 1530216070 \u{E9}".as_bytes(),
+            &[0xC3],
+        ]
+        .concat();
+        let convert = |options: ReplacerOptions| {
+            let mut replacer = StreamingReplacer::with_options(options);
+            let mut output = replacer.push_chunk(&input[..2]);
+            output.extend(replacer.push_chunk(&input[2..]));
+            output.extend(replacer.finish());
+            output
+        };
+
+        compare_bytes(
+            &[
+                "a\u{E9}".as_bytes(),
+                &[0xFF],
+                " [2018-06-28 20:01:10 UTC] \u{E9}".as_bytes(),
+                &[0xC3],
+            ]
+            .concat(),
+            &convert(ReplacerOptions::new()),
+        );
+        assert_eq!(
+            "a\u{E9}\u{FFFD} [2018-06-28 20:01:10 UTC] \u{E9}\u{FFFD}",
+            String::from_utf8(convert(
+                ReplacerOptions::new().invalid_utf8(InvalidUtf8::Lossy)
+            ))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn replace_quoted_timestamps_with_their_quotes() {
+        let options = ReplacerOptions::new().quoted_timestamps(QuotedTimestamps::Replace);
+        let mut replacer = StreamingReplacer::with_options(options);
+        let mut output = replacer.push_chunk("{\"ts\": \"1530216070317\", \"at\": \"".as_bytes());
+        output.extend(replacer.push_chunk("1530216070\", 'n': '1530216070\"}".as_bytes()));
+        output.extend(replacer.finish());
+
+        compare_bytes(
+            "{\"ts\": [2018-06-28 20:01:10.317 UTC], \"at\": [2018-06-28 20:01:10 UTC], \
+             'n': '[2018-06-28 20:01:10 UTC]\"}"
+                .as_bytes(),
+            &output,
+        );
+    }
+
+    #[test]
+    fn treat_quotes_as_delimiters() {
+        let options = ReplacerOptions::new().boundaries(BoundaryPolicy::delimited_by(" "));
+        let response = replace_epoch_timestamps_with_options(
+            "a '1530216070' b \"1530216070' c".as_bytes(),
+            true,
+            &options,
+        );
+
+        compare_bytes(
+            "a '[2018-06-28 20:01:10 UTC]' b \"1530216070' c".as_bytes(),
+            &response.data,
+        );
+    }
+
+    #[test]
+    fn stream_retains_digit_run_until_resolved() {
+        let mut replacer = StreamingReplacer::new();
+
+        compare_bytes("abc".as_bytes(), &replacer.push_chunk("abc1530".as_bytes()));
+        compare_bytes("".as_bytes(), &replacer.push_chunk("216070".as_bytes()));
+        compare_bytes("".as_bytes(), &replacer.push_chunk("".as_bytes()));
+        compare_bytes(
+            "153021607099!".as_bytes(),
+            &replacer.push_chunk("99!".as_bytes()),
+        );
+        compare_bytes("".as_bytes(), &replacer.finish());
+    }
+
+    #[test]
+    fn collect_stats_for_converted_input() {
+        let input = "a 1530216070 b 1530216000317 c 42\n";
+        let mut output = Vec::new();
+        let mut read_buffer = [0; BUFFER_SIZE];
+
+        let stats = process_input(
+            &mut input.as_bytes(),
+            &mut output,
+            &mut read_buffer,
+            &mut StreamingReplacer::new(),
+            false,
+            None,
+        );
+
+        assert_eq!(2, stats.timestamps_replaced);
+        assert_eq!(
+            Utc.timestamp_opt(1530216000, 317_000_000).single(),
+            stats.earliest
+        );
+        assert_eq!(Utc.timestamp_opt(1530216070, 0).single(), stats.latest);
+        assert_eq!(input.len() as u64, stats.bytes_read);
+        assert_eq!(output.len() as u64, stats.bytes_written);
+    }
+
+    #[test]
+    fn output_only_matching_lines() {
+        let mut replacer =
+            StreamingReplacer::with_options(ReplacerOptions::new().matching_only(true));
+        let mut output = Vec::new();
+
+        output.extend(replacer.push_chunk("skip 42\nkeep 1530".as_bytes()));
+        output.extend(replacer.push_chunk("216070 x\nskip\nlast 1530216070".as_bytes()));
+        output.extend(replacer.finish());
+
+        compare_bytes(
+            "keep [2018-06-28 20:01:10 UTC] x\nlast [2018-06-28 20:01:10 UTC]".as_bytes(),
+            &output,
+        );
+        assert_eq!(output.len() as u64, replacer.stats().bytes_written);
+    }
+
+    #[test]
+    fn output_only_lines_within_time_window() {
+        let options = ReplacerOptions::new()
+            .since(Utc.timestamp_opt(1530216000, 0).unwrap())
+            .until(Utc.timestamp_opt(1530216070, 0).unwrap());
+        let mut replacer = StreamingReplacer::with_options(options);
+        let mut output = Vec::new();
+
+        output.extend(replacer.push_chunk(
+            "before 1530215999 1530216010\nno timestamp\nstart 1530216000\n".as_bytes(),
+        ));
+        output.extend(replacer.push_chunk("end 1530216070317\nend 1530216070\n".as_bytes()));
+        output.extend(replacer.finish());
+
+        compare_bytes(
+            "start [2018-06-28 20:00:00 UTC]\nend [2018-06-28 20:01:10 UTC]\n".as_bytes(),
+            &output,
+        );
+    }
+
+    #[test]
+    fn replace_only_timestamps_after_key_prefixes() {
+        let options = ReplacerOptions::new().key_prefixes(
+            DEFAULT_KEY_PREFIXES
+                .iter()
+                .map(|prefix| prefix.to_string())
+                .collect(),
+        );
+        let mut replacer = StreamingReplacer::with_options(options);
+        let mut output = Vec::new();
+
+        output.extend(replacer.push_chunk("id=1530216070 ts=1530216070 {\"ti".as_bytes()));
+        output.extend(replacer.push_chunk("me\": 1530216070} epoch:".as_bytes()));
+        output.extend(replacer.push_chunk("1530216070 time:  1530216070".as_bytes()));
+        output.extend(replacer.finish());
+
+        compare_bytes(
+            "id=1530216070 ts=[2018-06-28 20:01:10 UTC] {\"time\": [2018-06-28 20:01:10 UTC]} \
+             epoch:[2018-06-28 20:01:10 UTC] time:  1530216070"
+                .as_bytes(),
+            &output,
+        );
+    }
+
+    #[test]
+    fn convert_lines_depending_on_patterns() {
+        let options = ReplacerOptions::new()
+            .only_if_line_matches("^(INFO|WARN)")
+            .unwrap()
+            .skip_if_line_matches("trace_id=")
+            .unwrap();
+        let mut replacer = StreamingReplacer::with_options(options);
+        let mut output = Vec::new();
+
+        output.extend(replacer.push_chunk("INFO 1530216070\nDEBUG 1530216070\n".as_bytes()));
+        output.extend(replacer.push_chunk("WARN trace_id=1530216070\nWARN 1530216070".as_bytes()));
+        output.extend(replacer.finish());
+
+        compare_bytes(
+            "INFO [2018-06-28 20:01:10 UTC]\nDEBUG 1530216070\n\
+             WARN trace_id=1530216070\nWARN [2018-06-28 20:01:10 UTC]"
+                .as_bytes(),
+            &output,
+        );
+    }
+
+    #[test]
+    fn record_matches_across_chunks() {
+        let mut replacer = StreamingReplacer::new().recording_matches();
+
+        replacer.push_chunk("a 1530216070 b 15302".as_bytes());
+        let first = replacer.take_matches();
+        replacer.push_chunk("16070.5 12345\n".as_bytes());
+        replacer.finish();
+
+        assert_eq!(
+            vec![TimestampMatch {
+                offset: 2,
+                original: "1530216070".to_string(),
+                date_time: Utc.timestamp_opt(1530216070, 0).unwrap(),
+            }],
+            first
+        );
+        assert_eq!(
+            vec![TimestampMatch {
+                offset: 15,
+                original: "1530216070.5".to_string(),
+                date_time: Utc.timestamp_opt(1530216070, 500_000_000).unwrap(),
+            }],
+            replacer.take_matches()
+        );
+    }
+
+    #[test]
+    fn report_matches_without_converting() {
+        let mut output = Vec::new();
+
+        report_matches(
+            &mut "x 1530216070317 y 99 z".as_bytes(),
+            &mut MatchReport::new("app.log", ReportFormat::Text, &mut output),
+            &ReplacerOptions::new(),
+        );
+
+        assert_eq!(
+            "app.log:2: 1530216070317 2018-06-28T20:01:10.317Z\n",
+            String::from_utf8_lossy(&output)
+        );
+    }
+
+    #[test]
+    fn flush_output_after_each_chunk() {
+        let mut reader = ChunkedReader {
+            data: "line 1530216070\nline 2\n".as_bytes(),
+            chunk_size: 8,
+        };
+        let mut output = FlushRecorder {
+            written: Vec::new(),
+            flushed: Vec::new(),
+        };
+        let mut read_buffer = [0; BUFFER_SIZE];
+
+        process_input(
+            &mut reader,
+            &mut output,
+            &mut read_buffer,
+            &mut StreamingReplacer::new(),
+            true,
+            None,
+        );
+
+        assert_eq!(
+            vec![
+                "line ",
+                "line [2018-06-28 20:01:10 UTC]\n",
+                "line [2018-06-28 20:01:10 UTC]\nline 2\n",
+                "line [2018-06-28 20:01:10 UTC]\nline 2\n",
+            ],
+            output.flushed
+        );
+    }
+
+    struct FlushRecorder {
+        written: Vec<u8>,
+        flushed: Vec<String>,
+    }
+
+    impl Write for FlushRecorder {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.flushed
+                .push(String::from_utf8(self.written.clone()).unwrap());
+            Ok(())
+        }
+    }
+
+    struct ChunkedReader<'a> {
+        data: &'a [u8],
+        chunk_size: usize,
+    }
+
+    impl Read for ChunkedReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let length = self.chunk_size.min(self.data.len()).min(buf.len());
+            buf[..length].copy_from_slice(&self.data[..length]);
+            self.data = &self.data[length..];
+            Ok(length)
+        }
     }
 
+    #[cfg(feature = "fs")]
     fn assert_file_content(file_name: String, expected: &[u8]) {
         let mut open_options = OpenOptions::new();
         open_options.read(true);
@@ -453,7 +3504,7 @@ mod tests {
         input_file
             .read_to_end(&mut buffer)
             .expect("Failed to read file");
-        compare_bytes_len(expected, &buffer.as_slice(), expected.len());
+        compare_bytes_len(expected, buffer.as_slice(), expected.len());
     }
 
     fn compare_bytes(a: &[u8], b: &[u8]) {
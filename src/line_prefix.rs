@@ -0,0 +1,61 @@
+use crate::packet::replace_token_timestamp;
+use crate::prometheus::{skip_whitespace, token_end};
+use crate::{ReplacerOptions, ReplacerState, ASCII_DECIMAL_POINT};
+
+/// Converts the fractional epoch seconds that start a line, such as the
+/// `1530216070.317` of `1530216070.317  1234  1240 I ActivityManager: ...` from
+/// `logcat -v epoch` or of `1530216070.317123 [0 127.0.0.1:6379] "GET" "key"` from
+/// `redis-cli MONITOR`. Lines without them, such as `--------- beginning of main`, and
+/// the PIDs, TIDs and command arguments that follow are written unchanged.
+pub(crate) fn replace_line_prefix_timestamps(
+    line: &[u8],
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let start = skip_whitespace(line, 0);
+    let end = token_end(line, start);
+    // whole numbers at the start of a line are not the epoch times of these formats
+    let end = if line[start..end].contains(&ASCII_DECIMAL_POINT) {
+        end
+    } else {
+        start
+    };
+    replace_token_timestamp(line, start, end, options, state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replace_epoch_timestamps_str_with_options;
+
+    fn replace(input: &str, preset: &str) -> String {
+        let options = ReplacerOptions::new().preset(preset).unwrap();
+        replace_epoch_timestamps_str_with_options(input, &options)
+    }
+
+    #[test]
+    fn convert_only_line_leading_times() {
+        assert_eq!(
+            "--------- beginning of main\n\
+             [2018-06-28 20:01:10.317 UTC]  1530  1530 I Zygote: started at 1530216070\n\
+             1530216070  2160  2160 W Tag: not an epoch time\n",
+            replace(
+                "--------- beginning of main\n\
+                 1530216070.317  1530  1530 I Zygote: started at 1530216070\n\
+                 1530216070  2160  2160 W Tag: not an epoch time\n",
+                "logcat"
+            )
+        );
+    }
+
+    #[test]
+    fn leave_redis_command_arguments() {
+        assert_eq!(
+            "[2018-06-28 20:01:10.317123 UTC] [0 127.0.0.1:51234] \"SET\" \"order:1530216070317\" \"1530216070\"\n",
+            replace(
+                "1530216070.317123 [0 127.0.0.1:51234] \"SET\" \"order:1530216070317\" \"1530216070\"\n",
+                "redis"
+            )
+        );
+    }
+}
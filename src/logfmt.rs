@@ -0,0 +1,147 @@
+use crate::formatter::append_custom_format;
+use crate::json::string_end;
+use crate::stats::json_string;
+use crate::{
+    parse_epoch_value, record_timestamp, render_timestamp, ReplacerOptions, ReplacerState,
+    ANSI_HIGHLIGHT, ANSI_RESET, ASCII_EQUALS, ASCII_QUOTE,
+};
+
+/// Converts the epoch values of the `key=value` pairs in a line of logfmt whose key is
+/// one of `keys`, writing everything else unchanged. A converted value is quoted if it
+/// was quoted in the input or would otherwise not be a single logfmt value.
+pub(crate) fn replace_logfmt_timestamps(
+    line: &[u8],
+    keys: &[String],
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let mut replaced = Vec::new();
+    let mut index = 0;
+    while index < line.len() {
+        let byte = line[index];
+        if byte.is_ascii_whitespace() {
+            replaced.push(byte);
+            index += 1;
+            continue;
+        }
+        if byte == ASCII_QUOTE || byte == ASCII_EQUALS {
+            let end = if byte == ASCII_QUOTE {
+                string_end(line, index)
+            } else {
+                index + 1
+            };
+            replaced.extend_from_slice(&line[index..end]);
+            index = end;
+            continue;
+        }
+        let key_end = token_end(line, index);
+        let key = &line[index..key_end];
+        replaced.extend_from_slice(key);
+        index = key_end;
+        if index == line.len() || line[index] != ASCII_EQUALS {
+            continue;
+        }
+        replaced.push(ASCII_EQUALS);
+        index += 1;
+
+        let quoted = index < line.len() && line[index] == ASCII_QUOTE;
+        let value_end = if quoted {
+            string_end(line, index)
+        } else {
+            token_end(line, index)
+        };
+        let value = &line[index..value_end];
+        let unquoted_value = if quoted && value.len() >= 2 && value.ends_with(&[ASCII_QUOTE]) {
+            &value[1..value.len() - 1]
+        } else {
+            value
+        };
+        let is_timestamp_key = keys.iter().any(|name| name.as_bytes() == key);
+        match parse_epoch_value(unquoted_value, options) {
+            Some(timestamp) if is_timestamp_key => {
+                let value_start = if unquoted_value.len() == value.len() {
+                    index
+                } else {
+                    index + 1
+                };
+                record_timestamp(&timestamp, unquoted_value, value_start, options, state);
+                if append_custom_format(&timestamp, unquoted_value, options, &mut replaced) {
+                    index = value_end;
+                    continue;
+                }
+                let rendered = render_timestamp(&timestamp, options, state);
+                if options.color {
+                    replaced.extend_from_slice(ANSI_HIGHLIGHT);
+                }
+                if quoted || token_end(rendered.as_bytes(), 0) != rendered.len() {
+                    replaced.extend_from_slice(json_string(&rendered).as_bytes());
+                } else {
+                    replaced.extend_from_slice(rendered.as_bytes());
+                }
+                if options.color {
+                    replaced.extend_from_slice(ANSI_RESET);
+                }
+            }
+            _ => replaced.extend_from_slice(value),
+        }
+        index = value_end;
+    }
+
+    if let Some(&byte) = line.last() {
+        state.preceding_byte = Some(byte);
+    }
+    state.offset += line.len() as u64;
+    replaced
+}
+
+/// Returns the end of the bare key or value starting at `start`.
+fn token_end(line: &[u8], start: usize) -> usize {
+    let mut end = start;
+    while end < line.len()
+        && !line[end].is_ascii_whitespace()
+        && line[end] != ASCII_EQUALS
+        && line[end] != ASCII_QUOTE
+    {
+        end += 1;
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{replace_epoch_timestamps_str_with_options, InputFormat};
+
+    fn replace(input: &str, options: ReplacerOptions) -> String {
+        replace_epoch_timestamps_str_with_options(
+            input,
+            &options.input_format(InputFormat::logfmt()),
+        )
+    }
+
+    #[test]
+    fn convert_only_values_of_timestamp_keys() {
+        assert_eq!(
+            "ts=\"[2018-06-28 20:01:10 UTC]\" level=info id=1530216070 deadline=\"[2018-06-28 20:01:10.317 UTC]\" msg=\"ts=1530216070\"\n",
+            replace(
+                "ts=1530216070 level=info id=1530216070 deadline=\"1530216070317\" msg=\"ts=1530216070\"\n",
+                ReplacerOptions::new()
+            )
+        );
+    }
+
+    #[test]
+    fn leave_bare_values_bare_when_possible() {
+        assert_eq!(
+            "time=2018-06-28T20:01:10Z key= =1530216070 time",
+            replace(
+                "time=1530216070 key= =1530216070 time",
+                ReplacerOptions::new()
+                    .format("%Y-%m-%dT%H:%M:%SZ")
+                    .unwrap()
+                    .prefix("")
+                    .suffix("")
+            )
+        );
+    }
+}
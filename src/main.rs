@@ -1,29 +1,1062 @@
-use nail;
+use chrono::{DateTime, TimeZone, Utc};
+use nail::{
+    BoundaryPolicy, BucketSize, Conversion, CsvColumn, EpochClassification, EpochUnit, InputFormat,
+    InvalidUtf8, OrderCheck, OutputStyle, OutputZone, QuotedTimestamps, RateCounter, RateFormat,
+    ReplacerOptions, ReportFormat, Rounding, SessionOutput, SkewMap, SplitPeriod, Stats, TimeUnit,
+    TimestampMode,
+};
 use std::env;
+use std::fs::File;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::process::{self, Command, Stdio};
+use std::time::Duration;
+
+const DEFAULT_PAGER: &str = "less";
+const DEFAULT_LESS: &str = "FRX";
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(250);
+const GAP_THRESHOLD: chrono::Duration = chrono::Duration::minutes(1);
+const RATE_STEP: chrono::Duration = chrono::Duration::seconds(1);
 
 fn main() {
     let args: Vec<String> = env::args().collect();
+    if wants_pager(&args) && std::io::stdout().is_terminal() {
+        run_in_pager(&args);
+    }
+
+    // with no command, or only options, nail behaves as a depoch filter so that it
+    // can be dropped straight into a pipeline
+    if args.len() == 1 || args[1].starts_with("--") {
+        let (_program, arguments) = args.split_at(1);
+        depoch(arguments);
+        return;
+    }
+
+    match args.get(1).unwrap().as_str() {
+        "depoch" => {
+            let (_program, arguments) = args.split_at(2);
+            depoch(arguments);
+        },
+        "merge" => {
+            let (_program, arguments) = args.split_at(2);
+            merge(arguments);
+        },
+        "histogram" => {
+            let (_program, arguments) = args.split_at(2);
+            histogram(arguments);
+        }
+        "gaps" => {
+            let (_program, arguments) = args.split_at(2);
+            gaps(arguments);
+        }
+        "lessopen" => {
+            let (_program, arguments) = args.split_at(2);
+            lessopen(arguments);
+        }
+        #[cfg(feature = "sqlite")]
+        "sqlite" => {
+            let (_program, arguments) = args.split_at(2);
+            sqlite(arguments);
+        }
+        "enhex" => {
+            let (_pre, post) = args.split_at(2);
+            nail::enhex(post);
+        },
+        "dehex" => {
+            let (_pre, post) = args.split_at(2);
+            nail::dehex(post);
+        },
+        // a file given without a command is converted, as `nail app.log.1.gz`
+        file if Path::new(file).exists() => {
+            let (_program, arguments) = args.split_at(1);
+            depoch(arguments);
+        }
+        command => exit_with_error(&format!("Unknown command or file: {}", command)),
+    }
+}
+
+/// Whether `--pager` is given, and not overridden by a later `--no-pager`.
+fn wants_pager(args: &[String]) -> bool {
+    args.iter()
+        .rev()
+        .find(|argument| *argument == "--pager" || *argument == "--no-pager")
+        .is_some_and(|argument| argument == "--pager")
+}
+
+/// Runs the command again with its output piped through `$PAGER`, or `less` by default,
+/// and exits with its status, or returns if the pager is empty or `cat`. As git does,
+/// `less` is run with `LESS=FRX` unless `LESS` is set, so that output fitting on one
+/// screen is printed as it is and colours are kept. The command is stopped if the pager
+/// quits before reading all of its output.
+fn run_in_pager(args: &[String]) {
+    let pager = env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.to_string());
+    let mut pager_words = pager.split_whitespace();
+    let pager_program = match pager_words.next() {
+        Some(program) if program != "cat" => program,
+        _ => return,
+    };
+    let mut pager_process = Command::new(pager_program)
+        .args(pager_words)
+        .env(
+            "LESS",
+            env::var("LESS").unwrap_or_else(|_| DEFAULT_LESS.to_string()),
+        )
+        .stdin(Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|error| exit_with_error(&format!("Cannot run {}: {}", pager, error)));
+    let executable = env::current_exe()
+        .unwrap_or_else(|error| exit_with_error(&format!("Cannot find nail: {}", error)));
+    let mut command = Command::new(executable)
+        .args(paged_arguments(&args[1..]))
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|error| exit_with_error(&format!("Cannot run nail: {}", error)));
 
-    if args.len() > 1 {
-        match args.get(1).unwrap().as_str() {
-            "depoch" => {
-                if args.len() > 2 {
-                    // iterate over files
-                    let (_program, files) = args.split_at(2);
-                    nail::process_files(files);
-                } else {
-                    nail::process_stdin();
+    let mut output = command.stdout.take().expect("Output is piped");
+    let mut pager_input = pager_process.stdin.take().expect("Input is piped");
+    let paged = std::io::copy(&mut output, &mut pager_input);
+    if paged.is_err() {
+        // the pager has quit, so the rest of the output is not wanted
+        command.kill().ok();
+    }
+    drop(pager_input);
+    drop(output);
+    let status = command.wait().expect("Failed to wait for nail");
+    pager_process.wait().ok();
+    process::exit(match paged {
+        Ok(_) => status.code().unwrap_or(1),
+        Err(_) => 0,
+    })
+}
+
+/// The arguments of the command run by `run_in_pager`. Its output is a pipe rather than
+/// the terminal, so automatic colour is decided here and passed on as `--color=always`.
+fn paged_arguments(args: &[String]) -> Vec<String> {
+    args.iter()
+        .map(|argument| match argument.as_str() {
+            "--color" | "--color=auto" => "--color=always".to_string(),
+            _ => argument.clone(),
+        })
+        .collect()
+}
+
+/// Command line settings for the depoch command that are not replacement options.
+struct DepochArguments {
+    options: ReplacerOptions,
+    files: Vec<String>,
+    in_place: bool,
+    backup: bool,
+    follow: bool,
+    /// Reports the timestamps found instead of converting, or alongside converting
+    /// when `report_to` is given.
+    report: Option<ReportFormat>,
+    report_to: Option<String>,
+    /// Writes just the timestamps found, one per line, and only once each if `unique`.
+    extract: bool,
+    unique: bool,
+    /// Counts the rate of timestamps over sliding windows instead of converting.
+    rate: Option<(RateCounter, RateFormat)>,
+    /// Reports the lines out of chronological order instead of converting, and exits
+    /// with a failure status if there are any.
+    check_order: Option<OrderCheck>,
+    /// Splits the converted output into sessions at gaps longer than the duration.
+    sessions: Option<(chrono::Duration, SessionOutput)>,
+    /// Converts into a file per period instead of a single output.
+    split_by: Option<SplitPeriod>,
+    sort: bool,
+    stats: Option<StatsFormat>,
+    /// Converts files to standard output, paged if it is a terminal, rather than to
+    /// sibling files.
+    pager: bool,
+}
+
+/// How the per-input summary requested with `--stats` is written to stderr.
+#[derive(Clone, Copy)]
+enum StatsFormat {
+    Text,
+    Json,
+}
+
+fn depoch(arguments: &[String]) {
+    let arguments = parse_depoch_arguments(arguments);
+    let options = &arguments.options;
+    let files = &arguments.files;
+    if let Some(path) = &arguments.report_to {
+        if arguments.in_place || arguments.follow || arguments.sort {
+            exit_with_error("--report-to cannot be used with --in-place, --follow or --sort");
+        }
+        let format = arguments.report.unwrap_or(ReportFormat::Json);
+        let mut report = File::create(path).unwrap_or_else(|error| {
+            exit_with_error(&format!("Unable to create {}: {}", path, error))
+        });
+        if files.is_empty() {
+            let stats = nail::process_stdin_with_report(options, format, &mut report);
+            report_stats(arguments.stats, &["(standard input)".to_string()], &[stats]);
+        } else {
+            let all_stats = nail::process_files_with_report(files, options, format, &mut report);
+            report_stats(arguments.stats, files, &all_stats);
+        }
+    } else if let Some(format) = arguments.report {
+        if files.is_empty() {
+            nail::report_stdin(options, format);
+        } else {
+            nail::report_files(files, options, format);
+        }
+    } else if arguments.extract {
+        if files.is_empty() {
+            nail::extract_stdin(options, arguments.unique);
+        } else {
+            nail::extract_files(files, options, arguments.unique);
+        }
+    } else if let Some((counter, format)) = arguments.rate {
+        if files.is_empty() {
+            nail::rate_stdin(options, counter, format);
+        } else {
+            nail::rate_files(files, options, counter, format);
+        }
+    } else if let Some(check) = arguments.check_order {
+        let out_of_order = if files.is_empty() {
+            nail::check_order_stdin(options, check)
+        } else {
+            nail::check_order_files(files, options, check)
+        };
+        if out_of_order > 0 {
+            process::exit(1);
+        }
+    } else if let Some((gap, output)) = arguments.sessions {
+        if files.is_empty() {
+            if output == SessionOutput::Files {
+                exit_with_error("--split-sessions requires at least one file");
+            }
+            nail::split_sessions_in_stdin(options, gap);
+        } else {
+            nail::split_sessions_in_files(files, options, gap, output);
+        }
+    } else if let Some(period) = arguments.split_by {
+        if files.is_empty() {
+            nail::split_stdin_by(options, period);
+        } else {
+            nail::split_files_by(files, options, period);
+        }
+    } else if arguments.follow {
+        if files.len() != 1 {
+            exit_with_error("--follow requires exactly one file");
+        }
+        nail::follow_file(&files[0], options, FOLLOW_POLL_INTERVAL);
+    } else if arguments.sort {
+        if arguments.in_place {
+            exit_with_error("--sort cannot be used with --in-place");
+        }
+        if files.is_empty() {
+            nail::sort_stdin(options);
+        } else {
+            nail::sort_files(files, options);
+        }
+    } else if arguments.in_place {
+        if files.is_empty() {
+            exit_with_error("--in-place requires at least one file");
+        }
+        let backup_suffix = if arguments.backup { Some(".bak") } else { None };
+        let all_stats = nail::process_files_in_place(files, options, backup_suffix);
+        report_stats(arguments.stats, files, &all_stats);
+    } else if arguments.pager && !files.is_empty() {
+        let all_stats = nail::process_files_to_stdout(files, options);
+        report_stats(arguments.stats, files, &all_stats);
+    } else if !files.is_empty() {
+        // iterate over files
+        let all_stats = nail::process_files(files, options);
+        report_stats(arguments.stats, files, &all_stats);
+    } else {
+        let stats = nail::process_stdin(options);
+        report_stats(arguments.stats, &["(standard input)".to_string()], &[stats]);
+    }
+}
+
+fn report_stats(format: Option<StatsFormat>, names: &[String], all_stats: &[Stats]) {
+    for (name, stats) in names.iter().zip(all_stats) {
+        match format {
+            Some(StatsFormat::Text) => eprintln!("{}: {}", name, stats),
+            Some(StatsFormat::Json) => eprintln!("{}", stats.to_json(name)),
+            None => {}
+        }
+    }
+}
+
+/// Merges files by timestamp, correcting the clock skew of their sources given with
+/// `--skew source=offset` or in a `--skew-map` file of such lines.
+fn merge(arguments: &[String]) {
+    let mut skew = SkewMap::default();
+    let mut depoch_arguments = Vec::new();
+    let mut iter = arguments.iter();
+    while let Some(argument) = iter.next() {
+        match argument.as_str() {
+            "--skew" => skew
+                .insert_entry(option_value(argument, iter.next()))
+                .unwrap_or_else(|error| exit_with_error(&error)),
+            "--skew-map" => {
+                let path = option_value(argument, iter.next());
+                let text = std::fs::read_to_string(path).unwrap_or_else(|error| {
+                    exit_with_error(&format!("Cannot read {}: {}", path, error))
+                });
+                skew.insert_lines(&text)
+                    .unwrap_or_else(|error| exit_with_error(&error));
+            }
+            _ => depoch_arguments.push(argument.clone()),
+        }
+    }
+    let arguments = parse_depoch_arguments(&depoch_arguments);
+    if arguments.files.is_empty() {
+        exit_with_error("merge requires at least one file");
+    }
+    nail::merge_files_with_skew(&arguments.files, &arguments.options, &skew);
+}
+
+/// Counts the timestamps in the input per `--bucket` of time, optionally with `--bars`.
+fn histogram(arguments: &[String]) {
+    let mut bucket_size = BucketSize::Hour;
+    let mut bars = false;
+    let mut depoch_arguments = Vec::new();
+    let mut iter = arguments.iter();
+    while let Some(argument) = iter.next() {
+        match argument.as_str() {
+            "--bucket" => {
+                bucket_size = match option_value(argument, iter.next()) {
+                    "minute" => BucketSize::Minute,
+                    "hour" => BucketSize::Hour,
+                    "day" => BucketSize::Day,
+                    other => exit_with_error(&format!("Unknown bucket size: {}", other)),
                 }
-            },
-            "enhex" => {
-                let (_pre, post) = args.split_at(2);
-                nail::enhex(post);
-            },
-            "dehex" => {
-                let (_pre, post) = args.split_at(2);
-                nail::dehex(post);
-            },
-            _ => println!("Unknown command: {}", args.get(1).unwrap())
+            }
+            "--bars" => bars = true,
+            _ => depoch_arguments.push(argument.clone()),
+        }
+    }
+    let arguments = parse_depoch_arguments(&depoch_arguments);
+    if arguments.files.is_empty() {
+        nail::histogram_stdin(&arguments.options, bucket_size, bars);
+    } else {
+        nail::histogram_files(&arguments.files, &arguments.options, bucket_size, bars);
+    }
+}
+
+/// Reports the gaps between timestamps that are longer than `--threshold`, one minute
+/// by default.
+fn gaps(arguments: &[String]) {
+    let mut threshold = GAP_THRESHOLD;
+    let mut depoch_arguments = Vec::new();
+    let mut iter = arguments.iter();
+    while let Some(argument) = iter.next() {
+        match argument.as_str() {
+            "--threshold" => {
+                threshold = parse_duration(option_value(argument, iter.next()));
+            }
+            _ => depoch_arguments.push(argument.clone()),
         }
     }
+    let arguments = parse_depoch_arguments(&depoch_arguments);
+    if arguments.files.is_empty() {
+        nail::find_gaps_in_stdin(&arguments.options, threshold);
+    } else {
+        nail::find_gaps_in_files(&arguments.files, &arguments.options, threshold);
+    }
+}
+
+/// Pages one file through `less` as a LESSOPEN input pipe, or with `--print-stanza`,
+/// prints the command that installs nail as one with the other options given.
+fn lessopen(arguments: &[String]) {
+    let print_stanza = arguments
+        .iter()
+        .any(|argument| argument == "--print-stanza");
+    let depoch_arguments: Vec<String> = arguments
+        .iter()
+        .filter(|argument| *argument != "--print-stanza")
+        .cloned()
+        .collect();
+    let parsed = parse_depoch_arguments(&depoch_arguments);
+    if print_stanza {
+        if !parsed.files.is_empty() {
+            exit_with_error("--print-stanza takes no files");
+        }
+        let executable = env::current_exe()
+            .unwrap_or_else(|error| exit_with_error(&format!("Cannot find nail: {}", error)));
+        println!(
+            "{}",
+            nail::lessopen_stanza(&executable.to_string_lossy(), &depoch_arguments)
+        );
+        return;
+    }
+    let file_name = match parsed.files.as_slice() {
+        [file_name] => file_name,
+        _ => exit_with_error("lessopen requires exactly one file"),
+    };
+    let stdout = std::io::stdout();
+    nail::less_preprocess(file_name, &parsed.options, &mut stdout.lock());
+}
+
+/// Converts columns of a SQLite table into a copy of the database given with `--output`,
+/// or into a CSV report on stdout.
+#[cfg(feature = "sqlite")]
+fn sqlite(arguments: &[String]) {
+    let mut table = None;
+    let mut columns = Vec::new();
+    let mut output = None;
+    let mut depoch_arguments = Vec::new();
+    let mut iter = arguments.iter();
+    while let Some(argument) = iter.next() {
+        match argument.as_str() {
+            "--table" => table = Some(option_value(argument, iter.next())),
+            "--column" => columns.push(option_value(argument, iter.next()).to_string()),
+            "--output" => output = Some(option_value(argument, iter.next())),
+            _ => depoch_arguments.push(argument.clone()),
+        }
+    }
+    let arguments = parse_depoch_arguments(&depoch_arguments);
+    let database = match arguments.files.as_slice() {
+        [database] => database,
+        _ => exit_with_error("sqlite requires exactly one database"),
+    };
+    let table = table.unwrap_or_else(|| exit_with_error("sqlite requires --table"));
+    if columns.is_empty() {
+        exit_with_error("sqlite requires at least one --column");
+    }
+    let stdout = std::io::stdout();
+    let mut stdout_lock = stdout.lock();
+    let output = match output {
+        Some(path) => nail::SqliteOutput::Copy(path),
+        None => nail::SqliteOutput::Csv(&mut stdout_lock),
+    };
+    nail::convert_sqlite_table(database, table, &columns, output, &arguments.options)
+        .unwrap_or_else(|error| exit_with_error(&error));
+}
+
+fn parse_depoch_arguments(arguments: &[String]) -> DepochArguments {
+    let mut options = ReplacerOptions::new();
+    let mut in_place = false;
+    let mut backup = false;
+    let mut follow = false;
+    let mut report = None;
+    let mut report_to = None;
+    let mut extract = false;
+    let mut unique = false;
+    let mut rate_window = None;
+    let mut check_order = None;
+    let mut session_gap = None;
+    let mut session_output = SessionOutput::Separators;
+    let mut split_by = None;
+    let mut rate_step = None;
+    let mut rate_format = RateFormat::Summary;
+    let mut sort = false;
+    let mut stats = None;
+    let mut line_selection = false;
+    let mut structured_input = false;
+    let mut csv = false;
+    let mut sql = false;
+    let mut csv_columns = Vec::new();
+    let mut dmesg = false;
+    let mut boot_time = None;
+    let mut gc_log = false;
+    let mut jvm_start = None;
+    let mut reverse = false;
+    let mut normalize_datetimes = false;
+    let mut rezone = false;
+    let mut pager = false;
+    let mut auto_color = false;
+    let mut unit = EpochUnit::Seconds;
+    let mut files = Vec::new();
+    let mut iter = arguments.iter();
+    while let Some(argument) = iter.next() {
+        match argument.as_str() {
+            "--reverse" => reverse = true,
+            "--normalize-datetimes" => normalize_datetimes = true,
+            "--rezone" => rezone = true,
+            "--pager" => pager = true,
+            "--no-pager" => pager = false,
+            "--in-place" => in_place = true,
+            "--backup" => backup = true,
+            "--follow" | "-f" => follow = true,
+            "--dry-run" => report = Some(ReportFormat::Text),
+            "--report" => {
+                let format = match option_value(argument, iter.next()) {
+                    "text" => ReportFormat::Text,
+                    "json" => ReportFormat::Json,
+                    other => exit_with_error(&format!("Unknown report format: {}", other)),
+                };
+                report = Some(format);
+            }
+            "--extract" => extract = true,
+            "--unique" => unique = true,
+            "--rate" => rate_window = Some(parse_duration(option_value(argument, iter.next()))),
+            "--session-gap" => {
+                session_gap = Some(parse_duration(option_value(argument, iter.next())))
+            }
+            "--split-by" => {
+                split_by = match option_value(argument, iter.next()) {
+                    "day" => Some(SplitPeriod::Day),
+                    "hour" => Some(SplitPeriod::Hour),
+                    other => exit_with_error(&format!("Unknown split period: {}", other)),
+                }
+            }
+            "--split-sessions" => session_output = SessionOutput::Files,
+            "--check-order" => check_order = Some(OrderCheck::Flag),
+            "--check-order=count" => check_order = Some(OrderCheck::Count),
+            "--rate-step" => rate_step = Some(parse_duration(option_value(argument, iter.next()))),
+            "--rate-format" => {
+                rate_format = match option_value(argument, iter.next()) {
+                    "summary" => RateFormat::Summary,
+                    "csv" => RateFormat::Csv,
+                    other => exit_with_error(&format!("Unknown rate format: {}", other)),
+                }
+            }
+            "--report-to" => report_to = Some(option_value(argument, iter.next()).to_string()),
+            "--sort" => sort = true,
+            "--matching-only" => {
+                line_selection = true;
+                options = options.matching_only(true)
+            }
+            "--stats" => stats = Some(StatsFormat::Text),
+            "--stats=json" => stats = Some(StatsFormat::Json),
+            "--unit" => unit = parse_unit(option_value(argument, iter.next())),
+            "--normalize" => {
+                // epoch values are written bare, so they can be read back as numbers
+                options = options
+                    .style(OutputStyle::Epoch(parse_unit(option_value(
+                        argument,
+                        iter.next(),
+                    ))))
+                    .prefix("")
+                    .suffix("")
+            }
+            "--format" => {
+                let format = option_value(argument, iter.next());
+                options = options
+                    .format(format)
+                    .unwrap_or_else(|error| exit_with_error(&error));
+            }
+            "--template" => {
+                let template = option_value(argument, iter.next());
+                options = options
+                    .template(template)
+                    .unwrap_or_else(|error| exit_with_error(&error));
+            }
+            "--tz" | "--timezone" => {
+                let timezone = option_value(argument, iter.next());
+                options = options
+                    .timezone(timezone)
+                    .unwrap_or_else(|error| exit_with_error(&error));
+            }
+            "--round" => {
+                options = options.rounding(Rounding::Nearest(parse_time_unit(option_value(
+                    argument,
+                    iter.next(),
+                ))))
+            }
+            "--truncate" => {
+                options = options.rounding(Rounding::Truncate(parse_time_unit(option_value(
+                    argument,
+                    iter.next(),
+                ))))
+            }
+            "--quoted" => {
+                let quoted_timestamps = match option_value(argument, iter.next()) {
+                    "keep" => QuotedTimestamps::Keep,
+                    "replace" => QuotedTimestamps::Replace,
+                    other => exit_with_error(&format!("Unknown quote handling: {}", other)),
+                };
+                options = options.quoted_timestamps(quoted_timestamps);
+            }
+            "--invalid-utf8" => {
+                let invalid_utf8 = match option_value(argument, iter.next()) {
+                    "pass-through" => InvalidUtf8::PassThrough,
+                    "lossy" => InvalidUtf8::Lossy,
+                    other => exit_with_error(&format!("Unknown invalid UTF-8 handling: {}", other)),
+                };
+                options = options.invalid_utf8(invalid_utf8);
+            }
+            "--style" => {
+                let style = match option_value(argument, iter.next()) {
+                    "default" => OutputStyle::Default,
+                    "rfc3339" => OutputStyle::Rfc3339,
+                    other => exit_with_error(&format!("Unknown output style: {}", other)),
+                };
+                options = options.style(style);
+            }
+            "--precision" => {
+                let value = option_value(argument, iter.next());
+                let digits = value
+                    .parse()
+                    .unwrap_or_else(|_| exit_with_error(&format!("Invalid precision: {}", value)));
+                options = options
+                    .fractional_digits(digits)
+                    .unwrap_or_else(|error| exit_with_error(&error));
+            }
+            "--delta" => options = options.mode(TimestampMode::Delta),
+            "--with-delta" => options = options.mode(TimestampMode::AbsoluteWithDelta),
+            "--anchor" => options = options.mode(TimestampMode::Anchor),
+            "--prefix" => options = options.prefix(&unescape(option_value(argument, iter.next()))),
+            "--suffix" => options = options.suffix(&unescape(option_value(argument, iter.next()))),
+            "--color" | "--color=auto" => auto_color = true,
+            "--color=always" => {
+                auto_color = false;
+                options = options.color(true)
+            }
+            "--color=never" => {
+                auto_color = false;
+                options = options.color(false)
+            }
+            "--min-year" => {
+                options = options.min_year(parse_year(option_value(argument, iter.next())))
+            }
+            "--max-year" => {
+                options = options.max_year(parse_year(option_value(argument, iter.next())))
+            }
+            "--classify-by-magnitude" => {
+                options = options.classification(EpochClassification::Magnitude)
+            }
+            "--hex" => options = options.hex_timestamps(true),
+            "--filetime" => options = options.filetime_timestamps(true),
+            "--ldap" => options = options.ldap_timestamps(true),
+            "--snowflake-epoch" => {
+                let epoch = option_value(argument, iter.next());
+                options = options.snowflake_ids(parse_snowflake_epoch(epoch))
+            }
+            "--digit-separators" => {
+                options = options.digit_separators(option_value(argument, iter.next()))
+            }
+            "--formats" => {
+                for format in option_value(argument, iter.next()).split(',') {
+                    options = match format {
+                        "hex" => options.hex_timestamps(true),
+                        "filetime" => options.filetime_timestamps(true),
+                        "ldap" => options.ldap_timestamps(true),
+                        "chrome" | "webkit" => options.webkit_timestamps(true),
+                        "tai64n" => options.tai64n_timestamps(true),
+                        "dotnet" => options.dotnet_timestamps(true),
+                        "negative" => options.negative_timestamps(true),
+                        "objectid" => options.object_ids(true),
+                        "uuid7" => options.uuid_timestamps(true),
+                        "ulid" => options.ulid_timestamps(true),
+                        "jwt" => options.jwt_claims(true),
+                        "git" => options.git_timestamps(true),
+                        "scientific" => options.scientific_timestamps(true),
+                        other => exit_with_error(&format!("Unknown timestamp format: {}", other)),
+                    }
+                }
+            }
+            "--strict-boundaries" => options = options.boundaries(BoundaryPolicy::delimited()),
+            "--boundary-chars" => {
+                let characters = unescape(option_value(argument, iter.next()));
+                options = options.boundaries(BoundaryPolicy::delimited_by(&characters))
+            }
+            "--since" => {
+                let since = option_value(argument, iter.next());
+                options = options.since(
+                    nail::parse_instant(since).unwrap_or_else(|error| exit_with_error(&error)),
+                );
+                line_selection = true;
+            }
+            "--until" => {
+                let until = option_value(argument, iter.next());
+                options = options.until(
+                    nail::parse_instant(until).unwrap_or_else(|error| exit_with_error(&error)),
+                );
+                line_selection = true;
+            }
+            "--json" => {
+                structured_input = true;
+                options = options.input_format(InputFormat::json())
+            }
+            "--json-keys" => {
+                let keys = option_value(argument, iter.next());
+                structured_input = true;
+                options = options.input_format(InputFormat::Json {
+                    keys: keys.split(',').map(|key| key.to_string()).collect(),
+                })
+            }
+            "--logfmt" => {
+                structured_input = true;
+                options = options.input_format(InputFormat::logfmt())
+            }
+            "--logfmt-keys" => {
+                let keys = option_value(argument, iter.next());
+                structured_input = true;
+                options = options.input_format(InputFormat::Logfmt {
+                    keys: keys.split(',').map(|key| key.to_string()).collect(),
+                })
+            }
+            "--prometheus" => {
+                structured_input = true;
+                options = options.input_format(InputFormat::Prometheus)
+            }
+            "--journal-export" => {
+                structured_input = true;
+                options = options.input_format(InputFormat::JournalExport)
+            }
+            "--packet-capture" => {
+                structured_input = true;
+                options = options.input_format(InputFormat::PacketCapture)
+            }
+            "--syslog" => {
+                structured_input = true;
+                options = options.input_format(InputFormat::Syslog)
+            }
+            "--zeek" => {
+                structured_input = true;
+                options = options.input_format(InputFormat::Zeek)
+            }
+            "--cef" => {
+                structured_input = true;
+                options = options.input_format(InputFormat::Cef)
+            }
+            "--dmesg" => {
+                structured_input = true;
+                dmesg = true;
+            }
+            "--boot-time" => {
+                boot_time = Some(parse_anchor_time(option_value(argument, iter.next())))
+            }
+            "--gc-log" => {
+                structured_input = true;
+                gc_log = true;
+            }
+            "--jvm-start" => {
+                jvm_start = Some(parse_anchor_time(option_value(argument, iter.next())))
+            }
+            "--preset" => {
+                let preset = option_value(argument, iter.next());
+                structured_input = true;
+                options = options
+                    .preset(preset)
+                    .unwrap_or_else(|error| exit_with_error(&error))
+            }
+            "--csv" => {
+                structured_input = true;
+                csv = true;
+                options = options.input_format(InputFormat::Csv {
+                    columns: csv_columns.clone(),
+                })
+            }
+            "--sql" => {
+                structured_input = true;
+                sql = true;
+                options = options.input_format(InputFormat::SqlDump {
+                    columns: csv_columns.clone(),
+                })
+            }
+            "--columns" => {
+                csv_columns = option_value(argument, iter.next())
+                    .split(',')
+                    .map(parse_csv_column)
+                    .collect();
+                if csv {
+                    options = options.input_format(InputFormat::Csv {
+                        columns: csv_columns.clone(),
+                    })
+                } else if sql {
+                    options = options.input_format(InputFormat::SqlDump {
+                        columns: csv_columns.clone(),
+                    })
+                }
+            }
+            "--keyed" => {
+                options = options.key_prefixes(
+                    nail::DEFAULT_KEY_PREFIXES
+                        .iter()
+                        .map(|prefix| prefix.to_string())
+                        .collect(),
+                )
+            }
+            "--key-prefixes" => {
+                let prefixes = option_value(argument, iter.next());
+                options = options.key_prefixes(
+                    prefixes
+                        .split(',')
+                        .map(|prefix| prefix.to_string())
+                        .collect(),
+                )
+            }
+            "--skip-phone-numbers" => options = options.skip_phone_numbers(true),
+            "--skip-card-numbers" => options = options.skip_card_numbers(true),
+            "--skip-key-prefixes" => {
+                let prefixes = option_value(argument, iter.next());
+                options = options.skip_key_prefixes(
+                    prefixes
+                        .split(',')
+                        .map(|prefix| prefix.to_string())
+                        .collect(),
+                )
+            }
+            "--skip-identifiers" => options = options.skip_identifiers(),
+            "--humanize-durations" => {
+                options = options.humanize_durations(
+                    nail::DEFAULT_DURATION_KEY_PREFIXES
+                        .iter()
+                        .map(|prefix| prefix.to_string())
+                        .collect(),
+                )
+            }
+            "--duration-keys" => {
+                let prefixes = option_value(argument, iter.next());
+                options = options.humanize_durations(
+                    prefixes
+                        .split(',')
+                        .map(|prefix| prefix.to_string())
+                        .collect(),
+                )
+            }
+            "--shift" => {
+                let shift = option_value(argument, iter.next());
+                options = options.shift(
+                    nail::parse_signed_duration(shift)
+                        .unwrap_or_else(|error| exit_with_error(&error)),
+                );
+            }
+            "--min-confidence" => {
+                let value = option_value(argument, iter.next());
+                let min_confidence = value
+                    .parse()
+                    .ok()
+                    .filter(|&confidence| confidence <= 100)
+                    .unwrap_or_else(|| exit_with_error(&format!("Invalid confidence: {}", value)));
+                options = options.min_confidence(min_confidence)
+            }
+            "--only-if-line-matches" => {
+                let pattern = option_value(argument, iter.next());
+                options = options
+                    .only_if_line_matches(pattern)
+                    .unwrap_or_else(|error| exit_with_error(&error));
+            }
+            "--skip-if-line-matches" => {
+                let pattern = option_value(argument, iter.next());
+                options = options
+                    .skip_if_line_matches(pattern)
+                    .unwrap_or_else(|error| exit_with_error(&error));
+            }
+            "--compress" => options = options.compress_output(true),
+            "--annotate" => options = options.annotate(true),
+            "--local" => options = options.zone(OutputZone::Local),
+            _ if argument.starts_with("--") => {
+                exit_with_error(&format!("Unknown option: {}", argument))
+            }
+            _ => files.push(argument.to_string()),
+        }
+    }
+    if dmesg {
+        let boot_time = boot_time.unwrap_or_else(|| {
+            nail::system_boot_time().unwrap_or_else(|error| exit_with_error(&error))
+        });
+        options = options.input_format(InputFormat::Dmesg { boot_time });
+    }
+    if gc_log {
+        options = options.input_format(InputFormat::GcLog {
+            start_time: jvm_start,
+        });
+    }
+    // these modes read human-readable datetimes rather than epoch values
+    let datetime_modes: Vec<&str> = [
+        (reverse, "--reverse"),
+        (normalize_datetimes, "--normalize-datetimes"),
+        (rezone, "--rezone"),
+    ]
+    .iter()
+    .filter(|(enabled, _)| *enabled)
+    .map(|&(_, mode)| mode)
+    .collect();
+    if datetime_modes.len() > 1 {
+        exit_with_error(&format!(
+            "{} cannot be combined",
+            datetime_modes.join(" and ")
+        ));
+    }
+    let datetime_mode = datetime_modes.first();
+    let reject_with_datetime_mode = |rejected: &str| {
+        if let Some(mode) = datetime_mode {
+            exit_with_error(&format!("{} cannot be used with {}", rejected, mode));
+        }
+    };
+    if report.is_some() || report_to.is_some() {
+        reject_with_datetime_mode("--dry-run, --report and --report-to");
+    }
+    if extract {
+        reject_with_datetime_mode("--extract");
+    }
+    let rate = rate_window.map(|window| {
+        // windows slide by a second unless they are shorter
+        let step = rate_step.unwrap_or_else(|| window.min(RATE_STEP));
+        let counter =
+            RateCounter::new(window, step).unwrap_or_else(|error| exit_with_error(&error));
+        (counter, rate_format)
+    });
+    let analyses = [
+        rate.is_some(),
+        check_order.is_some(),
+        session_gap.is_some(),
+        split_by.is_some(),
+    ];
+    if analyses.contains(&true) {
+        reject_with_datetime_mode("--rate, --check-order, --session-gap and --split-by");
+    }
+    if session_output == SessionOutput::Files && session_gap.is_none() {
+        exit_with_error("--split-sessions requires --session-gap");
+    }
+    if unique && !extract {
+        exit_with_error("--unique requires --extract");
+    }
+    if structured_input {
+        reject_with_datetime_mode("--json, --logfmt and --csv");
+    }
+    if line_selection {
+        reject_with_datetime_mode("--matching-only, --since and --until");
+    }
+    let conversion = if reverse {
+        Conversion::ToEpoch(unit)
+    } else if normalize_datetimes {
+        Conversion::Normalize
+    } else if rezone {
+        Conversion::ToZone
+    } else {
+        Conversion::ToDatetime
+    };
+    if auto_color {
+        // converted files are never coloured; only a terminal on stdout is
+        let to_stdout = files.is_empty() || pager;
+        options = options.color(to_stdout && std::io::stdout().is_terminal());
+    }
+    DepochArguments {
+        options: options.conversion(conversion),
+        files,
+        in_place,
+        backup,
+        follow,
+        report,
+        report_to,
+        extract,
+        unique,
+        rate,
+        check_order,
+        sessions: session_gap.map(|gap| (gap, session_output)),
+        split_by,
+        sort,
+        stats,
+        pager,
+    }
+}
+
+fn parse_csv_column(column: &str) -> CsvColumn {
+    match column.parse() {
+        Ok(0) => exit_with_error("CSV columns are numbered from 1"),
+        Ok(index) => CsvColumn::Index(index),
+        Err(_) => CsvColumn::Name(column.to_string()),
+    }
+}
+
+fn parse_duration(value: &str) -> chrono::Duration {
+    nail::parse_duration(value).unwrap_or_else(|error| exit_with_error(&error))
+}
+
+fn parse_time_unit(value: &str) -> TimeUnit {
+    match value {
+        "second" => TimeUnit::Second,
+        "minute" => TimeUnit::Minute,
+        "hour" => TimeUnit::Hour,
+        other => exit_with_error(&format!("Unknown rounding unit: {}", other)),
+    }
+}
+
+fn parse_unit(value: &str) -> EpochUnit {
+    match value {
+        "s" | "seconds" => EpochUnit::Seconds,
+        "ms" | "millis" => EpochUnit::Milliseconds,
+        "us" | "micros" => EpochUnit::Microseconds,
+        other => exit_with_error(&format!("Unknown epoch unit: {}", other)),
+    }
+}
+
+fn parse_snowflake_epoch(value: &str) -> i64 {
+    match value {
+        "twitter" => nail::TWITTER_SNOWFLAKE_EPOCH,
+        "discord" => nail::DISCORD_SNOWFLAKE_EPOCH,
+        millis => millis
+            .parse()
+            .unwrap_or_else(|_| exit_with_error(&format!("Invalid snowflake epoch: {}", millis))),
+    }
+}
+
+/// Parses an instant, or a number of seconds since the epoch such as the `btime` of
+/// `/proc/stat`.
+fn parse_anchor_time(value: &str) -> DateTime<Utc> {
+    match value.parse() {
+        Ok(seconds) => Utc
+            .timestamp_opt(seconds, 0)
+            .single()
+            .unwrap_or_else(|| exit_with_error(&format!("Invalid date/time: {}", value))),
+        Err(_) => nail::parse_instant(value).unwrap_or_else(|error| exit_with_error(&error)),
+    }
+}
+
+fn parse_year(value: &str) -> i32 {
+    value
+        .parse()
+        .unwrap_or_else(|_| exit_with_error(&format!("Invalid year: {}", value)))
+}
+
+fn option_value<'a>(option: &str, value: Option<&'a String>) -> &'a str {
+    match value {
+        Some(value) => value.as_str(),
+        None => exit_with_error(&format!("Missing value for option: {}", option)),
+    }
+}
+
+/// Expands `\e`, `\t`, `\n` and `\\` so that delimiters such as ANSI escape
+/// sequences can be passed on the command line.
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('e') => unescaped.push('\x1b'),
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colour_paged_output_when_colour_is_automatic() {
+        let args: Vec<String> = [
+            "--color",
+            "--pager",
+            "--color=auto",
+            "--color=never",
+            "a.log",
+        ]
+        .iter()
+        .map(|argument| argument.to_string())
+        .collect();
+        assert_eq!(
+            vec![
+                "--color=always",
+                "--pager",
+                "--color=always",
+                "--color=never",
+                "a.log"
+            ],
+            paged_arguments(&args)
+        );
+    }
 }
@@ -0,0 +1,425 @@
+use crate::compression::open_input;
+#[cfg(feature = "fs")]
+use crate::create_output;
+use crate::gaps::parse_signed_duration;
+use crate::{format_delta, ReplacerOptions, StreamingReplacer, ASCII_NEWLINE};
+use chrono::prelude::*;
+use chrono::Duration;
+#[cfg(feature = "fs")]
+use std::cmp::Reverse;
+#[cfg(feature = "fs")]
+use std::collections::BinaryHeap;
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+/// Interleaves the lines of several files into chronological order, converting their
+/// timestamps and tagging each line with the name of its file. Lines are ordered by
+/// their first timestamp; a line without one keeps its place after the line before
+/// it, so that multi-line entries such as stack traces stay together. Each file is
+/// expected to be in chronological order already.
+#[cfg(feature = "fs")]
+pub fn merge_files(files: &[String], options: &ReplacerOptions) {
+    merge_files_with_skew(files, options, &SkewMap::default());
+}
+
+/// Merges files as `merge_files` does, first correcting the timestamps of each file by
+/// the clock skew of its source in `skew`.
+#[cfg(feature = "fs")]
+pub fn merge_files_with_skew(files: &[String], options: &ReplacerOptions, skew: &SkewMap) {
+    let sources = files
+        .iter()
+        .map(|file_name| {
+            let (input, _) =
+                open_input(File::open(file_name).unwrap()).expect("Error reading from input file");
+            (file_name.as_str(), input)
+        })
+        .collect();
+    let stdout = std::io::stdout();
+    let mut stdout_lock = stdout.lock();
+    merge(sources, options, skew, &mut stdout_lock);
+}
+
+/// The corrections for the clock skew of the sources of merged files, each added to
+/// the timestamps of the files of one source. A source is named by the path of a file,
+/// its file name or the part of its file name before the first `.`, such as a host
+/// name for `web1.access.log`.
+#[derive(Clone, Debug, Default)]
+pub struct SkewMap {
+    corrections: Vec<(String, Duration)>,
+}
+
+impl SkewMap {
+    pub fn insert(&mut self, source: &str, correction: Duration) {
+        self.corrections.push((source.to_string(), correction));
+    }
+
+    /// Parses `source=correction` lines, such as `web1=-2s`, ignoring blank lines and
+    /// lines starting with `#`.
+    pub fn parse(text: &str) -> Result<SkewMap, String> {
+        let mut skew = SkewMap::default();
+        skew.insert_lines(text)?;
+        Ok(skew)
+    }
+
+    /// Adds the corrections of `source=correction` lines as `parse` reads them.
+    pub fn insert_lines(&mut self, text: &str) -> Result<(), String> {
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            self.insert_entry(line)?;
+        }
+        Ok(())
+    }
+
+    /// Adds a correction written as `source=correction`.
+    pub fn insert_entry(&mut self, entry: &str) -> Result<(), String> {
+        let (source, correction) = entry
+            .rsplit_once('=')
+            .ok_or_else(|| format!("Invalid clock skew correction: {}", entry))?;
+        self.insert(source.trim(), parse_signed_duration(correction.trim())?);
+        Ok(())
+    }
+
+    /// The correction for the source of `file_name`; a later entry for the same file
+    /// replaces an earlier one.
+    #[cfg(feature = "fs")]
+    fn correction(&self, file_name: &str) -> Option<Duration> {
+        let name = Path::new(file_name)
+            .file_name()
+            .map_or(file_name.into(), |name| name.to_string_lossy());
+        let stem = name.split('.').next().unwrap_or_default();
+        self.corrections
+            .iter()
+            .rev()
+            .find(|(source, _)| source == file_name || *source == name || source == stem)
+            .map(|&(_, correction)| correction)
+    }
+}
+
+#[cfg(feature = "fs")]
+fn merge(
+    inputs: Vec<(&str, Box<dyn Read + '_>)>,
+    options: &ReplacerOptions,
+    skew: &SkewMap,
+    output: &mut dyn Write,
+) {
+    let mut sources: Vec<LineSource> = inputs
+        .into_iter()
+        .map(|(name, input)| match skew.correction(name) {
+            Some(correction) => {
+                let shift = options.shift.unwrap_or_else(Duration::zero) + correction;
+                LineSource::new(name, input, &options.clone().shift(shift))
+            }
+            None => LineSource::new(name, input, options),
+        })
+        .collect();
+    let mut queue = BinaryHeap::new();
+    for (index, source) in sources.iter_mut().enumerate() {
+        if let Some(key) = source.advance() {
+            queue.push(Reverse((key, index)));
+        }
+    }
+    while let Some(Reverse((_, index))) = queue.pop() {
+        let source = &mut sources[index];
+        output
+            .write_all(source.tag.as_bytes())
+            .and_then(|_| output.write_all(&source.line))
+            .expect("Failed to write");
+        if let Some(key) = source.advance() {
+            queue.push(Reverse((key, index)));
+        }
+    }
+    output.flush().expect("Error flushing output");
+}
+
+/// Converts each file into a sibling `.depoch` file with its lines sorted by their
+/// first timestamp, for logs written out of order by asynchronous appenders. Lines
+/// without a timestamp stay after the line before them, and lines with equal
+/// timestamps keep their order. Each file is sorted in memory.
+#[cfg(feature = "fs")]
+pub fn sort_files(files: &[String], options: &ReplacerOptions) {
+    for file_name in files {
+        let (input, compression) =
+            open_input(File::open(file_name).unwrap()).expect("Error reading from input file");
+        let mut output = create_output(file_name, compression, options);
+        sort(input, options, &mut output);
+        output.finish().expect("Failed to write");
+    }
+}
+
+/// Sorts the lines of standard input as `sort_files` does, writing them to standard
+/// output once all input has been read.
+pub fn sort_stdin(options: &ReplacerOptions) {
+    let (input, _) = open_input(std::io::stdin().lock()).expect("Error reading from input file");
+    let stdout = std::io::stdout();
+    let mut stdout_lock = stdout.lock();
+    sort(input, options, &mut stdout_lock);
+}
+
+fn sort(input: Box<dyn Read + '_>, options: &ReplacerOptions, output: &mut dyn Write) {
+    let mut source = LineSource::new("", input, options);
+    let mut lines = Vec::new();
+    while let Some(key) = source.advance() {
+        lines.push((key, std::mem::take(&mut source.line)));
+    }
+    lines.sort_by_key(|(key, _)| *key);
+    for (_, line) in lines {
+        output.write_all(&line).expect("Failed to write");
+    }
+    output.flush().expect("Error flushing output");
+}
+
+/// How `check_order_files` reports the lines that are out of chronological order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OrderCheck {
+    /// Writes each line out of order, numbered and with how far back its timestamp is.
+    Flag,
+    /// Writes only the number of lines out of order.
+    Count,
+}
+
+/// Reports the lines of each file whose first timestamp is earlier than that of the
+/// timestamped line before it, as logs from multi-threaded appenders and shippers
+/// can be, and returns how many there are in all the files.
+#[cfg(feature = "fs")]
+pub fn check_order_files(files: &[String], options: &ReplacerOptions, check: OrderCheck) -> u64 {
+    let stdout = std::io::stdout();
+    let mut stdout_lock = stdout.lock();
+    files
+        .iter()
+        .map(|file_name| {
+            let (input, _) =
+                open_input(File::open(file_name).unwrap()).expect("Error reading from input file");
+            check_order(file_name, input, options, check, &mut stdout_lock)
+        })
+        .sum()
+}
+
+/// Reports the lines of standard input that are out of order, as `check_order_files`
+/// does.
+pub fn check_order_stdin(options: &ReplacerOptions, check: OrderCheck) -> u64 {
+    let (input, _) = open_input(std::io::stdin().lock()).expect("Error reading from input file");
+    let stdout = std::io::stdout();
+    let mut stdout_lock = stdout.lock();
+    check_order("", input, options, check, &mut stdout_lock)
+}
+
+fn check_order(
+    name: &str,
+    input: Box<dyn Read + '_>,
+    options: &ReplacerOptions,
+    check: OrderCheck,
+    output: &mut dyn Write,
+) -> u64 {
+    let mut source = LineSource::new(name, input, options);
+    let mut previous = None;
+    let mut lines = 0;
+    let mut out_of_order = 0;
+    while let Some(key) = source.advance() {
+        lines += 1;
+        if let (Some(key), Some(previous)) = (key, previous) {
+            if key < previous {
+                out_of_order += 1;
+                if check == OrderCheck::Flag {
+                    write!(
+                        output,
+                        "{}{}: {} ",
+                        source.tag,
+                        source.line_number,
+                        format_delta(key - previous)
+                    )
+                    .and_then(|_| output.write_all(&source.line))
+                    .expect("Failed to write");
+                }
+            }
+        }
+        previous = key;
+    }
+    if check == OrderCheck::Count {
+        writeln!(
+            output,
+            "{}{} of {} lines out of order",
+            source.tag, out_of_order, lines
+        )
+        .expect("Failed to write");
+    }
+    output.flush().expect("Error flushing output");
+    out_of_order
+}
+
+/// The lines of one input, converted one at a time.
+pub(crate) struct LineSource<'a> {
+    tag: String,
+    input: BufReader<Box<dyn Read + 'a>>,
+    replacer: StreamingReplacer,
+    /// The converted line that is next to be output.
+    pub(crate) line: Vec<u8>,
+    /// The sort key of the most recent line with a timestamp.
+    key: Option<DateTime<Utc>>,
+    /// The number of the line in the input that `line` was converted from.
+    line_number: u64,
+}
+
+impl<'a> LineSource<'a> {
+    pub(crate) fn new(
+        name: &str,
+        input: Box<dyn Read + 'a>,
+        options: &ReplacerOptions,
+    ) -> LineSource<'a> {
+        LineSource {
+            tag: if name.is_empty() {
+                String::new()
+            } else {
+                format!("{}: ", name)
+            },
+            input: BufReader::new(input),
+            replacer: StreamingReplacer::with_options(options.clone()).recording_matches(),
+            line: Vec::new(),
+            key: None,
+            line_number: 0,
+        }
+    }
+
+    /// Converts the next line that the options select, returning its sort key, or
+    /// `None` at the end of the input.
+    pub(crate) fn advance(&mut self) -> Option<Option<DateTime<Utc>>> {
+        let mut raw_line = Vec::new();
+        loop {
+            raw_line.clear();
+            let read_length = self
+                .input
+                .read_until(ASCII_NEWLINE, &mut raw_line)
+                .expect("Error reading from input file");
+            if read_length == 0 {
+                return None;
+            }
+            self.line_number += 1;
+            let mut converted = self.replacer.push_chunk(&raw_line);
+            if !raw_line.ends_with(&[ASCII_NEWLINE]) {
+                converted.extend(self.replacer.finish());
+                converted.push(ASCII_NEWLINE);
+            }
+            if let Some(first) = self.replacer.take_matches().first() {
+                self.key = Some(first.date_time);
+            }
+            if !converted.is_empty() {
+                self.line = converted;
+                return Some(self.key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn interleave_lines_by_timestamp() {
+        let first = "1530216070 a1\n1530216090 a2\n  continued\n";
+        let second = "header\n1530216080 b1\n1530216100 b2";
+        let mut output = Vec::new();
+
+        merge(
+            vec![
+                ("a.log", Box::new(first.as_bytes())),
+                ("b.log", Box::new(second.as_bytes())),
+            ],
+            &ReplacerOptions::new(),
+            &SkewMap::default(),
+            &mut output,
+        );
+
+        assert_eq!(
+            "b.log: header\n\
+             a.log: [2018-06-28 20:01:10 UTC] a1\n\
+             b.log: [2018-06-28 20:01:20 UTC] b1\n\
+             a.log: [2018-06-28 20:01:30 UTC] a2\n\
+             a.log:   continued\n\
+             b.log: [2018-06-28 20:01:40 UTC] b2\n",
+            String::from_utf8_lossy(&output)
+        );
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn correct_clock_skew_before_merging() {
+        let first = "1530216070 a1\n1530216090 a2\n";
+        let second = "1530216095 b1\n";
+        let skew = SkewMap::parse("# hosts\nweb2 = -20s\nweb3=+1h\n").unwrap();
+        let mut output = Vec::new();
+
+        merge(
+            vec![
+                ("logs/web1.log", Box::new(first.as_bytes())),
+                ("logs/web2.log", Box::new(second.as_bytes())),
+            ],
+            &ReplacerOptions::new(),
+            &skew,
+            &mut output,
+        );
+
+        assert_eq!(
+            "logs/web1.log: [2018-06-28 20:01:10 UTC] a1\n\
+             logs/web2.log: [2018-06-28 20:01:15 UTC] b1\n\
+             logs/web1.log: [2018-06-28 20:01:30 UTC] a2\n",
+            String::from_utf8_lossy(&output)
+        );
+        assert!(SkewMap::parse("web1 -2s").is_err());
+    }
+
+    #[test]
+    fn flag_or_count_lines_out_of_order() {
+        let input = "1530216090 c\n1530216070 a\n  continued\n1530216060 b\n1530216100 d\n";
+        let check = |check| {
+            let mut output = Vec::new();
+            let out_of_order = check_order(
+                "app.log",
+                Box::new(input.as_bytes()),
+                &ReplacerOptions::new(),
+                check,
+                &mut output,
+            );
+            (out_of_order, String::from_utf8(output).unwrap())
+        };
+
+        assert_eq!(
+            (
+                2,
+                "app.log: 2: -00:00:20.000 [2018-06-28 20:01:10 UTC] a\n\
+                 app.log: 4: -00:00:10.000 [2018-06-28 20:01:00 UTC] b\n"
+                    .to_string()
+            ),
+            check(OrderCheck::Flag)
+        );
+        assert_eq!(
+            (2, "app.log: 2 of 5 lines out of order\n".to_string()),
+            check(OrderCheck::Count)
+        );
+    }
+
+    #[test]
+    fn sort_lines_by_timestamp() {
+        let input = "1530216090 c\n1530216070 a\n  continued\n1530216080 b\n1530216070 a2";
+        let mut output = Vec::new();
+
+        sort(
+            Box::new(input.as_bytes()),
+            &ReplacerOptions::new(),
+            &mut output,
+        );
+
+        assert_eq!(
+            "[2018-06-28 20:01:10 UTC] a\n  continued\n\
+             [2018-06-28 20:01:10 UTC] a2\n\
+             [2018-06-28 20:01:20 UTC] b\n\
+             [2018-06-28 20:01:30 UTC] c\n",
+            String::from_utf8_lossy(&output)
+        );
+    }
+}
@@ -0,0 +1,372 @@
+use crate::reverse::{
+    parse_fraction, parse_offset, parse_time, scan_written_datetime, Cursor, Incomplete,
+    WrittenDateTime,
+};
+use crate::{
+    absolute_timestamp, digit_run_end, Conversion, EpochTimestamp, ReplacementResult,
+    ReplacerOptions, ASCII_COLON, ASCII_SLASH, ASCII_SPACE,
+};
+use chrono::prelude::*;
+use chrono::Duration;
+
+const MONTH_ABBREVIATIONS: [&[u8]; 12] = [
+    b"Jan", b"Feb", b"Mar", b"Apr", b"May", b"Jun", b"Jul", b"Aug", b"Sep", b"Oct", b"Nov", b"Dec",
+];
+const ANTE_MERIDIEM: &[&[u8]] = &[b" AM", b" am"];
+const POST_MERIDIEM: &[&[u8]] = &[b" PM", b" pm"];
+const HOURS_PER_MERIDIEM: u32 = 12;
+const SECONDS_PER_HOUR: i32 = 3600;
+/// The zone abbreviations that may follow a datetime, with their offsets in hours. Those
+/// shared by several zones, such as `IST`, are left out.
+const ZONE_ABBREVIATIONS: &[(&[u8], i32)] = &[
+    (b"GMT", 0),
+    (b"BST", 1),
+    (b"CEST", 2),
+    (b"CET", 1),
+    (b"EEST", 3),
+    (b"EET", 2),
+    (b"JST", 9),
+    (b"EDT", -4),
+    (b"EST", -5),
+    (b"CDT", -5),
+    (b"CST", -6),
+    (b"MDT", -6),
+    (b"MST", -7),
+    (b"PDT", -7),
+    (b"PST", -8),
+];
+
+/// Replaces human-readable datetimes in `input` with the configured style and zone, so
+/// that logs mixing formats line up. ISO 8601/RFC 3339 datetimes are recognised as
+/// `replace_datetimes` reads them, along with syslog's `Jun 28 20:01:10` and US-style
+/// `06/28/2018 20:01:10` or `6/28/2018 8:01:10 PM`, each optionally followed by an offset
+/// or a zone abbreviation such as ` PST`. Datetimes without either are taken to be UTC,
+/// or left unchanged when converting `Conversion::ToZone`. A syslog datetime, which has
+/// no year, is taken to be in the year before `now` if it would otherwise be more than a
+/// day after it. The prefix and suffix are not added.
+///
+/// As with `replace_epoch_timestamps`, a trailing candidate that may continue in the
+/// next chunk is reported in `left_over_data` unless `end_of_input` is set.
+pub(crate) fn normalize_datetimes(
+    input: &[u8],
+    end_of_input: bool,
+    options: &ReplacerOptions,
+    now: DateTime<Utc>,
+) -> ReplacementResult {
+    let zoned_only = options.conversion == Conversion::ToZone;
+    let mut replaced: Vec<u8> = Vec::new();
+    let mut index = 0;
+    while index < input.len() {
+        let byte = input[index];
+        let (run_end, scanned) = if byte.is_ascii_digit() {
+            let scanned =
+                scan_iso_datetime(input, index, end_of_input).and_then(|found| match found {
+                    Some(found) => Ok(Some(found)),
+                    None => scan_us_datetime(input, index, end_of_input),
+                });
+            (digit_run_end(input, index), scanned)
+        } else if byte.is_ascii_alphabetic() {
+            let scanned = if byte.is_ascii_uppercase() {
+                scan_syslog_datetime(input, index, end_of_input, now)
+            } else {
+                Ok(None)
+            };
+            (word_end(input, index), scanned)
+        } else {
+            replaced.push(byte);
+            index += 1;
+            continue;
+        };
+        match scanned {
+            Ok(Some(written)) => {
+                let offset_seconds = match written.offset_seconds {
+                    Some(offset_seconds) => Some(offset_seconds),
+                    None if zoned_only => None,
+                    None => Some(0),
+                };
+                let date_time = offset_seconds
+                    .and_then(FixedOffset::east_opt)
+                    .and_then(|offset| offset.from_local_datetime(&written.local).single());
+                let original = &input[index..index + written.length];
+                match date_time {
+                    Some(date_time) => append_normalized(
+                        &date_time,
+                        written.fractional_digits,
+                        options,
+                        &mut replaced,
+                    ),
+                    None => replaced.extend_from_slice(original),
+                }
+                index += written.length;
+            }
+            Ok(None) => {
+                if run_end == input.len() && !end_of_input {
+                    break;
+                }
+                // a datetime never starts part way through a number or word
+                replaced.extend_from_slice(&input[index..run_end]);
+                index = run_end;
+            }
+            Err(Incomplete) => break,
+        }
+    }
+
+    ReplacementResult {
+        data: replaced,
+        left_over_data: (input.len() - index) as u64,
+    }
+}
+
+fn append_normalized(
+    date_time: &DateTime<FixedOffset>,
+    fractional_digits: usize,
+    options: &ReplacerOptions,
+    append_buffer: &mut Vec<u8>,
+) {
+    let date_time = date_time.with_timezone(&Utc);
+    let timestamp = EpochTimestamp {
+        seconds: date_time.timestamp(),
+        nanos: date_time.timestamp_subsec_nanos(),
+        fractional_digits,
+        offset: None,
+    };
+    append_buffer.extend_from_slice(absolute_timestamp(&date_time, &timestamp, options).as_bytes());
+}
+
+fn word_end(input: &[u8], start: usize) -> usize {
+    let mut end = start;
+    while end < input.len() && input[end].is_ascii_alphabetic() {
+        end += 1;
+    }
+    end
+}
+
+/// Scans an ISO 8601 datetime, which may also be followed by a zone abbreviation.
+fn scan_iso_datetime(
+    input: &[u8],
+    start: usize,
+    end_of_input: bool,
+) -> Result<Option<WrittenDateTime>, Incomplete> {
+    let mut written = match scan_written_datetime(input, start, end_of_input)? {
+        Some(written) => written,
+        None => return Ok(None),
+    };
+    if written.offset_seconds.is_none() {
+        let mut cursor = Cursor::new(input, start + written.length, end_of_input);
+        written.offset_seconds = parse_zone_abbreviation(&mut cursor)?;
+        written.length = cursor.position - start;
+    }
+    Ok(Some(written))
+}
+
+/// Scans a syslog datetime such as `Jun 28 20:01:10` or `Jun  8 20:01:10.317`.
+fn scan_syslog_datetime(
+    input: &[u8],
+    start: usize,
+    end_of_input: bool,
+    now: DateTime<Utc>,
+) -> Result<Option<WrittenDateTime>, Incomplete> {
+    let mut cursor = Cursor::new(input, start, end_of_input);
+    let mut month = None;
+    for (index, abbreviation) in MONTH_ABBREVIATIONS.iter().enumerate() {
+        if cursor.accept_all(abbreviation)? {
+            month = Some(index as u32 + 1);
+            break;
+        }
+    }
+    let month = match month {
+        Some(month) => month,
+        None => return Ok(None),
+    };
+    // a day before the tenth is padded with a space rather than a zero
+    if cursor.accept(&[ASCII_SPACE])?.is_none() {
+        return Ok(None);
+    }
+    cursor.accept(&[ASCII_SPACE])?;
+    let day = match cursor.short_number()? {
+        Some(day) => day,
+        None => return Ok(None),
+    };
+    if cursor.accept(&[ASCII_SPACE])?.is_none() {
+        return Ok(None);
+    }
+    let (hour, minute, second) = match parse_time(&mut cursor)? {
+        Some(time) => time,
+        None => return Ok(None),
+    };
+    let (nanos, fractional_digits) = parse_fraction(&mut cursor)?;
+    let offset_seconds = parse_zone(&mut cursor)?;
+
+    let in_year = |year: i32| {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .and_then(|date| date.and_hms_nano_opt(hour, minute, second, nanos))
+    };
+    let now = now.naive_utc();
+    let local = match in_year(now.year()) {
+        Some(local) if local - now > Duration::days(1) => in_year(now.year() - 1),
+        Some(local) => Some(local),
+        // the 29th of February is only valid in some years
+        None => in_year(now.year() - 1),
+    };
+    Ok(local.map(|local| WrittenDateTime {
+        length: cursor.position - start,
+        local,
+        fractional_digits,
+        offset_seconds,
+    }))
+}
+
+/// Scans a US-style datetime such as `06/28/2018 20:01:10` or `6/28/2018 8:01:10 PM`.
+fn scan_us_datetime(
+    input: &[u8],
+    start: usize,
+    end_of_input: bool,
+) -> Result<Option<WrittenDateTime>, Incomplete> {
+    let mut cursor = Cursor::new(input, start, end_of_input);
+    let month = match cursor.short_number()? {
+        Some(month) => month,
+        None => return Ok(None),
+    };
+    if cursor.accept(&[ASCII_SLASH])?.is_none() {
+        return Ok(None);
+    }
+    let day = match cursor.short_number()? {
+        Some(day) => day,
+        None => return Ok(None),
+    };
+    if cursor.accept(&[ASCII_SLASH])?.is_none() {
+        return Ok(None);
+    }
+    let year = match cursor.number(4)? {
+        Some(year) => year,
+        None => return Ok(None),
+    };
+    if cursor.accept(&[ASCII_SPACE])?.is_none() {
+        return Ok(None);
+    }
+    let hour = match cursor.short_number()? {
+        Some(hour) => hour,
+        None => return Ok(None),
+    };
+    if cursor.accept(&[ASCII_COLON])?.is_none() {
+        return Ok(None);
+    }
+    let (minute, second) = match parse_minutes_and_seconds(&mut cursor)? {
+        Some(time) => time,
+        None => return Ok(None),
+    };
+    let (nanos, fractional_digits) = parse_fraction(&mut cursor)?;
+    let hour = match parse_meridiem(&mut cursor)? {
+        Some(_) if hour == 0 || hour > HOURS_PER_MERIDIEM => return Ok(None),
+        Some(false) => hour % HOURS_PER_MERIDIEM,
+        Some(true) => hour % HOURS_PER_MERIDIEM + HOURS_PER_MERIDIEM,
+        None => hour,
+    };
+    let offset_seconds = parse_zone(&mut cursor)?;
+
+    let local = NaiveDate::from_ymd_opt(year as i32, month, day)
+        .and_then(|date| date.and_hms_nano_opt(hour, minute, second, nanos));
+    Ok(local.map(|local| WrittenDateTime {
+        length: cursor.position - start,
+        local,
+        fractional_digits,
+        offset_seconds,
+    }))
+}
+
+/// Parses an optional offset, as `replace_datetimes` reads it, or zone abbreviation,
+/// returning it in seconds east of UTC.
+fn parse_zone(cursor: &mut Cursor) -> Result<Option<i32>, Incomplete> {
+    match parse_offset(cursor)? {
+        Some(offset_seconds) => Ok(Some(offset_seconds)),
+        None => parse_zone_abbreviation(cursor),
+    }
+}
+
+/// Parses an optional space and zone abbreviation such as ` PST`, returning its offset
+/// in seconds east of UTC.
+fn parse_zone_abbreviation(cursor: &mut Cursor) -> Result<Option<i32>, Incomplete> {
+    let zone_start = cursor.position;
+    if cursor.accept(&[ASCII_SPACE])?.is_none() {
+        return Ok(None);
+    }
+    for &(abbreviation, hours) in ZONE_ABBREVIATIONS {
+        if cursor.accept_all(abbreviation)?
+            && !cursor
+                .peek()?
+                .is_some_and(|byte| byte.is_ascii_alphabetic())
+        {
+            return Ok(Some(hours * SECONDS_PER_HOUR));
+        }
+        cursor.position = zone_start + 1;
+    }
+    cursor.position = zone_start;
+    Ok(None)
+}
+
+fn parse_minutes_and_seconds(cursor: &mut Cursor) -> Result<Option<(u32, u32)>, Incomplete> {
+    let minute = match cursor.number(2)? {
+        Some(minute) => minute,
+        None => return Ok(None),
+    };
+    if cursor.accept(&[ASCII_COLON])?.is_none() {
+        return Ok(None);
+    }
+    Ok(cursor.number(2)?.map(|second| (minute, second)))
+}
+
+/// Parses an optional ` AM` or ` PM`, returning whether it is after noon.
+fn parse_meridiem(cursor: &mut Cursor) -> Result<Option<bool>, Incomplete> {
+    for (meridiems, after_noon) in [(ANTE_MERIDIEM, false), (POST_MERIDIEM, true)] {
+        for meridiem in meridiems {
+            if cursor.accept_all(meridiem)? {
+                return Ok(Some(after_noon));
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OutputStyle;
+
+    fn normalize(input: &str, options: ReplacerOptions) -> String {
+        let now = Utc.with_ymd_and_hms(2019, 1, 1, 0, 0, 0).unwrap();
+        let response = normalize_datetimes(input.as_bytes(), true, &options, now);
+        assert_eq!(0, response.left_over_data);
+        String::from_utf8(response.data).unwrap()
+    }
+
+    #[test]
+    fn normalize_mixed_datetime_formats() {
+        let input = "a 2018-06-28T21:01:10+01:00 b Jun 28 20:01:10.317 c 6/28/2018 8:01:10 PM \
+                     d Dec 31 23:59:59 e 06/28/2018 20:01:10 Junk 12/31";
+        let options = ReplacerOptions::new().style(OutputStyle::Rfc3339);
+        assert_eq!(
+            "a 2018-06-28T20:01:10Z b 2018-06-28T20:01:10.317Z c 2018-06-28T20:01:10Z \
+             d 2018-12-31T23:59:59Z e 2018-06-28T20:01:10Z Junk 12/31",
+            normalize(input, options.clone())
+        );
+
+        let now = Utc.with_ymd_and_hms(2019, 1, 1, 0, 0, 0).unwrap();
+        let response = normalize_datetimes(b"at Jun 28 20:0", false, &options, now);
+        assert_eq!(11, response.left_over_data);
+    }
+
+    #[test]
+    fn convert_zoned_datetimes_to_the_output_zone() {
+        let input = "a Jun 28 12:01:10 PST b 2018-06-28 20:01:10 UTC c 2018-06-28T22:01:10 CEST \
+                     d 2018-06-28 20:01:10 e 6/28/2018 4:01:10 PM EDT f Jun 28 20:01:10 PSTX";
+        let options = ReplacerOptions::new()
+            .conversion(Conversion::ToZone)
+            .timezone("America/Los_Angeles")
+            .unwrap();
+        assert_eq!(
+            "a 2018-06-28 13:01:10 PDT b 2018-06-28 13:01:10 PDT c 2018-06-28 13:01:10 PDT \
+             d 2018-06-28 20:01:10 e 2018-06-28 13:01:10 PDT f Jun 28 20:01:10 PSTX",
+            normalize(input, options)
+        );
+    }
+}
@@ -0,0 +1,1008 @@
+use crate::detector::{DetectorChain, TimestampDetector};
+use crate::exclusion::PHONE_NUMBER_LOOKBEHIND;
+use crate::formatter::{CustomFormatter, TimestampFormatter};
+use crate::template::Template;
+use crate::{Conversion, EpochUnit, MAX_FRACTIONAL_DIGITS};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{
+    DateTime, Duration, DurationRound, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use regex::bytes::Regex;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::sync::Arc;
+
+const DEFAULT_MIN_YEAR: i32 = 2000;
+const DEFAULT_MAX_YEAR: i32 = 2040;
+const DEFAULT_JSON_KEYS: &[&str] = &["ts", "timestamp", "time", "created_at", "expires"];
+const DEFAULT_LOGFMT_KEYS: &[&str] = &["ts", "time", "deadline"];
+/// The keys of the epoch millisecond values in AWS CloudTrail and CloudWatch exports.
+const AWS_JSON_KEYS: &[&str] = &[
+    "eventTime",
+    "ingestionTime",
+    "timestamp",
+    "creationTime",
+    "firstEventTimestamp",
+    "lastEventTimestamp",
+    "lastIngestionTime",
+    "startTime",
+    "endTime",
+];
+
+/// Text that commonly introduces an epoch value in free-form logs.
+pub const DEFAULT_KEY_PREFIXES: &[&str] = &[
+    "ts=",
+    "time=",
+    "timestamp=",
+    "epoch=",
+    "\"ts\":",
+    "\"time\":",
+    "\"timestamp\":",
+    "ts:",
+    "time:",
+    "epoch:",
+];
+
+/// Keys whose values are identifiers rather than timestamps in business logs, matched
+/// as suffixes so that `id=` also covers `user_id=`.
+pub const DEFAULT_IDENTIFIER_KEY_PREFIXES: &[&str] = &[
+    "id=", "Id=", "ID=", "id\":", "Id\":", "ID\":", "id:", "order=", "account=",
+];
+
+/// Keys whose values are durations in milliseconds, such as `took 93211 ms`.
+pub const DEFAULT_DURATION_KEY_PREFIXES: &[&str] = &[
+    "took",
+    "took=",
+    "duration=",
+    "duration_ms=",
+    "elapsed=",
+    "elapsed_ms=",
+    "latency=",
+    "latency_ms=",
+    "\"took\":",
+    "\"duration_ms\":",
+];
+
+/// Active Directory attributes holding 100-nanosecond intervals since 1601-01-01.
+pub const LDAP_TIMESTAMP_ATTRIBUTES: &[&str] = &[
+    "lastLogonTimestamp",
+    "lastLogon",
+    "lastLogoff",
+    "pwdLastSet",
+    "badPasswordTime",
+    "lockoutTime",
+    "accountExpires",
+];
+
+/// The epoch of Twitter's snowflake IDs, in milliseconds since 1970-01-01.
+pub const TWITTER_SNOWFLAKE_EPOCH: i64 = 1_288_834_974_657;
+
+/// The epoch of Discord's snowflake IDs, 2015-01-01, in milliseconds since 1970-01-01.
+pub const DISCORD_SNOWFLAKE_EPOCH: i64 = 1_420_070_400_000;
+
+/// The timezone in which converted timestamps are displayed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputZone {
+    Utc,
+    /// The local timezone of the machine, rendered with its UTC offset.
+    Local,
+    /// A zone from the IANA database, such as `Europe/London`.
+    Named(Tz),
+}
+
+/// How converted timestamps are rendered.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OutputStyle {
+    /// `2018-06-28 20:01:10.317 UTC`, with the sub-second precision of the input.
+    Default,
+    /// Strict RFC 3339, e.g. `2018-06-28T20:01:10.317Z`.
+    Rfc3339,
+    /// A chrono strftime pattern.
+    Format(String),
+    /// The epoch value in the given unit, normalising inputs of mixed precision.
+    Epoch(EpochUnit),
+}
+
+/// How input that is not valid UTF-8 is handled. Timestamps are converted either way,
+/// and a valid multi-byte sequence is never split or altered.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InvalidUtf8 {
+    /// Invalid bytes are written unchanged.
+    PassThrough,
+    /// Each invalid sequence is replaced by U+FFFD, so that the output is valid UTF-8.
+    Lossy,
+}
+
+/// How the unit of an epoch value is decided.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EpochClassification {
+    /// By whether its value falls within the year range in seconds, milliseconds or
+    /// microseconds, so that `999999999` is seconds in 2001 but a 13-digit value
+    /// starting with 9 is not milliseconds.
+    Magnitude,
+    /// By its number of digits alone: 10 for seconds, 13 for milliseconds and 16 for
+    /// microseconds.
+    DigitCount,
+}
+
+/// What happens to the quotes around an epoch value, such as `"1530216070317"`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QuotedTimestamps {
+    /// The quotes are kept around the converted value.
+    Keep,
+    /// The quoted value is replaced entirely, quotes included.
+    Replace,
+}
+
+/// The unit that rendered timestamps are rounded or truncated to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeUnit {
+    Second,
+    Minute,
+    Hour,
+}
+
+impl TimeUnit {
+    fn duration(self) -> Duration {
+        match self {
+            TimeUnit::Second => Duration::seconds(1),
+            TimeUnit::Minute => Duration::minutes(1),
+            TimeUnit::Hour => Duration::hours(1),
+        }
+    }
+}
+
+/// How rendered timestamps are coarsened, for a timeline that only needs whole units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rounding {
+    /// To the nearest unit, with halves rounded up.
+    Nearest(TimeUnit),
+    /// To the start of the unit that the timestamp falls in.
+    Truncate(TimeUnit),
+}
+
+impl Rounding {
+    /// Rounds `date_time` in its own timezone, leaving it unchanged if it is out of range.
+    pub(crate) fn apply<Tz: TimeZone>(self, date_time: &DateTime<Tz>) -> DateTime<Tz> {
+        let rounded = match self {
+            Rounding::Nearest(unit) => date_time.clone().duration_round(unit.duration()),
+            Rounding::Truncate(unit) => date_time.clone().duration_trunc(unit.duration()),
+        };
+        rounded.unwrap_or_else(|_| date_time.clone())
+    }
+}
+
+/// Whether converted timestamps show the absolute time or the time elapsed since an
+/// earlier timestamp.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimestampMode {
+    Absolute,
+    /// The delta from the previously seen timestamp, e.g. `+00:00:03.214`.
+    Delta,
+    /// The absolute time followed by the delta from the previous timestamp.
+    AbsoluteWithDelta,
+    /// The offset from the first timestamp in the input, e.g. `T+00:05:12.001`.
+    Anchor,
+}
+
+/// Which characters may directly surround an epoch value for it to be converted.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BoundaryPolicy {
+    /// Any non-digit character delimits a timestamp, so `id1530216070x` is converted.
+    AnyNonDigit,
+    /// A timestamp must be at the start or end of the input, be preceded by one of
+    /// `before` and followed by one of `after`, or be wrapped in single or double quotes.
+    Delimited { before: Vec<u8>, after: Vec<u8> },
+}
+
+impl BoundaryPolicy {
+    /// Whitespace and the punctuation that commonly surrounds values in logs; letters,
+    /// `_`, `-` and `.` are excluded so that identifiers and version strings are left
+    /// alone.
+    pub fn delimited() -> BoundaryPolicy {
+        BoundaryPolicy::Delimited {
+            before: b" \t\r\n\"'`([{<=:,;|/".to_vec(),
+            after: b" \t\r\n\"'`)]}>,;|/.".to_vec(),
+        }
+    }
+
+    /// Allows the same set of characters on either side of a timestamp.
+    pub fn delimited_by(characters: &str) -> BoundaryPolicy {
+        BoundaryPolicy::Delimited {
+            before: characters.as_bytes().to_vec(),
+            after: characters.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// How each line of the input is structured, which limits where timestamps are
+/// looked for.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputFormat {
+    /// Free-form text, in which any digit run may be a timestamp.
+    Text,
+    /// JSON, in which only numbers that are the value of one of `keys` are converted,
+    /// at any depth. Converted values are written as JSON strings so that the line
+    /// remains valid, and are never annotated.
+    Json { keys: Vec<String> },
+    /// Comma-separated values, in which only fields in one of `columns` that consist
+    /// of an epoch value are converted; no columns selects every column. Quoting is
+    /// preserved, including quoted fields that span lines.
+    Csv { columns: Vec<CsvColumn> },
+    /// logfmt, in which only the values of `key=value` pairs whose key is one of `keys`
+    /// are converted.
+    Logfmt { keys: Vec<String> },
+    /// The Prometheus text exposition format, in which only the optional timestamp at
+    /// the end of each sample is converted, never its value.
+    Prometheus,
+    /// journald's export format, from `journalctl -o export`, in which the realtime
+    /// timestamps of each record and the epoch timestamps in its message are converted.
+    /// Binary fields are kept intact, with their lengths updated.
+    JournalExport,
+    /// The text output of tcpdump (`-tt`) or tshark (`-t e`), in which only the epoch
+    /// frame time at the start of each packet line is converted.
+    PacketCapture,
+    /// The access log of an HTTP server or proxy, in which fields such as nginx's `$msec`
+    /// are converted but quoted strings, counters and durations are not.
+    AccessLog { server: AccessLogServer },
+    /// RFC 5424 syslog, in which epoch values are converted in SD-PARAM values and the
+    /// MSG, but never in the header. Other lines are treated as free-form text.
+    Syslog,
+    /// A Zeek (formerly Bro) TSV log, in which only the columns whose type is `time` in
+    /// the `#types` header are converted.
+    Zeek,
+    /// ArcSight CEF or QRadar LEEF events, in which only the epoch values of time
+    /// extensions such as `rt`, `start`, `end` and `devTime` are converted.
+    Cef,
+    /// Lines that start with fractional epoch seconds, such as the output of
+    /// `logcat -v epoch` or `redis-cli MONITOR`, in which only those seconds are
+    /// converted and never the rest of the line.
+    LinePrefix,
+    /// Kernel messages from dmesg, in which the bracketed seconds since boot that start
+    /// each line, such as `[12345.678901]`, are converted relative to `boot_time`.
+    Dmesg { boot_time: DateTime<Utc> },
+    /// A JVM GC log, in which the uptime that starts each line is converted relative to
+    /// `start_time`, or to the start time implied by the first line with a date stamp.
+    GcLog { start_time: Option<DateTime<Utc>> },
+    /// A mysqldump or pg_dump text dump, in which only the values of `INSERT` statements
+    /// that are in one of `columns` are converted; no columns selects every column.
+    /// Columns are named as in the `INSERT` or `CREATE TABLE` statement.
+    SqlDump { columns: Vec<CsvColumn> },
+    /// The output of kafka-console-consumer with `print.timestamp=true`, in which the
+    /// record timestamp that starts each line is converted, along with the epoch
+    /// timestamps in each record's key and value if `payload` is set.
+    KafkaConsumer { payload: bool },
+}
+
+/// The program that wrote an access log, which decides the fields that hold counters
+/// and durations rather than timestamps.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AccessLogServer {
+    /// The combined log format of nginx or Apache, whose bracketed time is never
+    /// converted.
+    Combined,
+    /// HAProxy's HTTP or TCP log, whose Tq/Tw/Tc/Tr/Tt timers are durations.
+    Haproxy,
+    /// Envoy's default access log format, whose bracketed start time is converted when
+    /// it is an epoch, such as `%START_TIME(%s%3f)%`.
+    Envoy,
+}
+
+/// A CSV column, selected by its one-based position or by its name in the header line.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CsvColumn {
+    Index(usize),
+    Name(String),
+}
+
+impl InputFormat {
+    /// JSON with the key names commonly used for timestamps.
+    pub fn json() -> InputFormat {
+        InputFormat::Json {
+            keys: DEFAULT_JSON_KEYS
+                .iter()
+                .map(|key| key.to_string())
+                .collect(),
+        }
+    }
+
+    /// logfmt with the key names commonly used for timestamps.
+    pub fn logfmt() -> InputFormat {
+        InputFormat::Logfmt {
+            keys: DEFAULT_LOGFMT_KEYS
+                .iter()
+                .map(|key| key.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// A regular expression matched against each line of the input.
+#[derive(Clone, Debug)]
+pub struct LinePattern(Regex);
+
+impl LinePattern {
+    pub fn new(pattern: &str) -> Result<LinePattern, String> {
+        Regex::new(pattern)
+            .map(LinePattern)
+            .map_err(|error| format!("Invalid pattern: {}", error))
+    }
+
+    fn is_match(&self, line: &[u8]) -> bool {
+        self.0.is_match(line)
+    }
+}
+
+impl PartialEq for LinePattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+/// Configuration for how timestamps are detected and rendered, which lines are
+/// output, and how converted files are written, built up by chaining calls from
+/// `ReplacerOptions::new()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplacerOptions {
+    pub(crate) conversion: Conversion,
+    pub(crate) style: OutputStyle,
+    pub(crate) zone: OutputZone,
+    pub(crate) fractional_digits: Option<usize>,
+    pub(crate) rounding: Option<Rounding>,
+    pub(crate) annotate: bool,
+    pub(crate) mode: TimestampMode,
+    pub(crate) prefix: String,
+    pub(crate) suffix: String,
+    pub(crate) color: bool,
+    pub(crate) min_year: i32,
+    pub(crate) max_year: i32,
+    pub(crate) classification: EpochClassification,
+    pub(crate) boundaries: BoundaryPolicy,
+    pub(crate) hex_timestamps: bool,
+    pub(crate) filetime_timestamps: bool,
+    /// The attribute names, with separators, that introduce LDAP timestamps, if enabled.
+    pub(crate) ldap_prefixes: Option<Vec<String>>,
+    pub(crate) webkit_timestamps: bool,
+    pub(crate) tai64n_timestamps: bool,
+    pub(crate) dotnet_timestamps: bool,
+    pub(crate) negative_timestamps: bool,
+    /// The epoch, in milliseconds since 1970-01-01, of snowflake IDs to annotate, if enabled.
+    pub(crate) snowflake_epoch: Option<i64>,
+    pub(crate) object_ids: bool,
+    pub(crate) uuid_timestamps: bool,
+    pub(crate) ulid_timestamps: bool,
+    pub(crate) jwt_claims: bool,
+    pub(crate) git_timestamps: bool,
+    pub(crate) digit_separators: Vec<u8>,
+    pub(crate) scientific_timestamps: bool,
+    pub(crate) compress_output: bool,
+    pub(crate) matching_only: bool,
+    pub(crate) since: Option<DateTime<Utc>>,
+    pub(crate) until: Option<DateTime<Utc>>,
+    pub(crate) input_format: InputFormat,
+    pub(crate) key_prefixes: Option<Vec<String>>,
+    pub(crate) skip_phone_numbers: bool,
+    pub(crate) skip_card_numbers: bool,
+    /// The keys whose values are never converted, if any.
+    pub(crate) skip_key_prefixes: Option<Vec<String>>,
+    pub(crate) min_confidence: u8,
+    /// The keys whose values are rendered as durations rather than timestamps, if any.
+    pub(crate) duration_keys: Option<Vec<String>>,
+    /// Added to every timestamp that is converted.
+    pub(crate) shift: Option<Duration>,
+    pub(crate) invalid_utf8: InvalidUtf8,
+    pub(crate) quoted_timestamps: QuotedTimestamps,
+    pub(crate) only_if_line_matches: Option<LinePattern>,
+    pub(crate) skip_if_line_matches: Option<LinePattern>,
+    pub(crate) detectors: DetectorChain,
+    pub(crate) formatter: CustomFormatter,
+}
+
+impl ReplacerOptions {
+    pub fn new() -> ReplacerOptions {
+        ReplacerOptions {
+            conversion: Conversion::ToDatetime,
+            style: OutputStyle::Default,
+            zone: OutputZone::Utc,
+            fractional_digits: None,
+            rounding: None,
+            annotate: false,
+            mode: TimestampMode::Absolute,
+            prefix: "[".to_string(),
+            suffix: "]".to_string(),
+            color: false,
+            min_year: DEFAULT_MIN_YEAR,
+            max_year: DEFAULT_MAX_YEAR,
+            classification: EpochClassification::DigitCount,
+            boundaries: BoundaryPolicy::AnyNonDigit,
+            hex_timestamps: false,
+            filetime_timestamps: false,
+            ldap_prefixes: None,
+            webkit_timestamps: false,
+            tai64n_timestamps: false,
+            dotnet_timestamps: false,
+            negative_timestamps: false,
+            snowflake_epoch: None,
+            object_ids: false,
+            uuid_timestamps: false,
+            ulid_timestamps: false,
+            jwt_claims: false,
+            git_timestamps: false,
+            digit_separators: Vec::new(),
+            scientific_timestamps: false,
+            compress_output: false,
+            matching_only: false,
+            since: None,
+            until: None,
+            input_format: InputFormat::Text,
+            key_prefixes: None,
+            skip_phone_numbers: false,
+            skip_card_numbers: false,
+            skip_key_prefixes: None,
+            min_confidence: 0,
+            duration_keys: None,
+            shift: None,
+            invalid_utf8: InvalidUtf8::PassThrough,
+            quoted_timestamps: QuotedTimestamps::Keep,
+            only_if_line_matches: None,
+            skip_if_line_matches: None,
+            detectors: DetectorChain::default(),
+            formatter: CustomFormatter::default(),
+        }
+    }
+
+    pub fn conversion(mut self, conversion: Conversion) -> ReplacerOptions {
+        self.conversion = conversion;
+        self
+    }
+
+    pub fn style(mut self, style: OutputStyle) -> ReplacerOptions {
+        self.style = style;
+        self
+    }
+
+    /// Renders converted timestamps using a chrono strftime pattern such as
+    /// `%Y-%m-%dT%H:%M:%S%.3f%:z`.
+    pub fn format(self, format: &str) -> Result<ReplacerOptions, String> {
+        if StrftimeItems::new(format).any(|item| item == Item::Error) {
+            return Err(format!("Invalid format: {}", format));
+        }
+        Ok(self.style(OutputStyle::Format(format.to_string())))
+    }
+
+    pub fn zone(mut self, zone: OutputZone) -> ReplacerOptions {
+        self.zone = zone;
+        self
+    }
+
+    /// Renders every timestamp with `digits` fractional digits, from 0 to 9, padding or
+    /// truncating the precision of the input so that converted timestamps line up. By
+    /// default the precision of each input is kept.
+    pub fn fractional_digits(mut self, digits: usize) -> Result<ReplacerOptions, String> {
+        if digits > MAX_FRACTIONAL_DIGITS {
+            return Err(format!(
+                "Fractional digits must be between 0 and {}: {}",
+                MAX_FRACTIONAL_DIGITS, digits
+            ));
+        }
+        self.fractional_digits = Some(digits);
+        Ok(self)
+    }
+
+    /// Rounds or truncates rendered timestamps to a whole second, minute or hour, in
+    /// the output timezone, leaving out any fraction of a second. Timestamps are still
+    /// selected and counted at their full precision.
+    pub fn rounding(mut self, rounding: Rounding) -> ReplacerOptions {
+        self.rounding = Some(rounding);
+        self
+    }
+
+    /// Keeps the original epoch value and appends the converted form after it, e.g.
+    /// `1530216070317 [2018-06-28 20:01:10.317 UTC]`.
+    pub fn annotate(mut self, annotate: bool) -> ReplacerOptions {
+        self.annotate = annotate;
+        self
+    }
+
+    pub fn mode(mut self, mode: TimestampMode) -> ReplacerOptions {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the text written before each converted timestamp; defaults to `[`.
+    pub fn prefix(mut self, prefix: &str) -> ReplacerOptions {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Sets the text written after each converted timestamp; defaults to `]`.
+    pub fn suffix(mut self, suffix: &str) -> ReplacerOptions {
+        self.suffix = suffix.to_string();
+        self
+    }
+
+    /// Highlights converted timestamps, including their delimiters, with ANSI colour codes.
+    pub fn color(mut self, color: bool) -> ReplacerOptions {
+        self.color = color;
+        self
+    }
+
+    /// Epoch values before this year are left untouched, as they are more likely to be
+    /// some other number of the same length; defaults to 2000.
+    pub fn min_year(mut self, min_year: i32) -> ReplacerOptions {
+        self.min_year = min_year;
+        self
+    }
+
+    /// Epoch values after this year are left untouched; defaults to 2040.
+    pub fn max_year(mut self, max_year: i32) -> ReplacerOptions {
+        self.max_year = max_year;
+        self
+    }
+
+    /// Sets how the unit of an epoch value is decided; defaults to its number of
+    /// digits.
+    pub fn classification(mut self, classification: EpochClassification) -> ReplacerOptions {
+        self.classification = classification;
+        self
+    }
+
+    pub fn boundaries(mut self, boundaries: BoundaryPolicy) -> ReplacerOptions {
+        self.boundaries = boundaries;
+        self
+    }
+
+    /// Also converts `0x`-prefixed hexadecimal epochs, with 8 digits for seconds or 11
+    /// to 12 digits for milliseconds, such as `0x5b353f46`.
+    pub fn hex_timestamps(mut self, hex_timestamps: bool) -> ReplacerOptions {
+        self.hex_timestamps = hex_timestamps;
+        self
+    }
+
+    /// Also converts 18-digit Windows FILETIME values, counting 100-nanosecond
+    /// intervals since 1601-01-01, as found in forensic exports.
+    pub fn filetime_timestamps(mut self, filetime_timestamps: bool) -> ReplacerOptions {
+        self.filetime_timestamps = filetime_timestamps;
+        self
+    }
+
+    /// Also converts the 18-digit values of the Active Directory attributes in
+    /// `LDAP_TIMESTAMP_ATTRIBUTES`, such as `lastLogonTimestamp: 131746896703170000`.
+    /// Only values following one of the attribute names and a `:` or `=` are converted.
+    pub fn ldap_timestamps(mut self, ldap_timestamps: bool) -> ReplacerOptions {
+        self.ldap_prefixes = ldap_timestamps.then(|| {
+            LDAP_TIMESTAMP_ATTRIBUTES
+                .iter()
+                .flat_map(|name| vec![format!("{}:", name), format!("{}=", name)])
+                .collect()
+        });
+        self
+    }
+
+    /// Also converts 17-digit WebKit timestamps, counting microseconds since 1601-01-01,
+    /// as found in Chrome history and cache exports.
+    pub fn webkit_timestamps(mut self, webkit_timestamps: bool) -> ReplacerOptions {
+        self.webkit_timestamps = webkit_timestamps;
+        self
+    }
+
+    /// Also converts `@`-prefixed TAI64N labels, as written by daemontools' multilog and
+    /// s6-log, including their nanoseconds.
+    pub fn tai64n_timestamps(mut self, tai64n_timestamps: bool) -> ReplacerOptions {
+        self.tai64n_timestamps = tai64n_timestamps;
+        self
+    }
+
+    /// Also converts 18-digit .NET `DateTime` ticks, counting 100-nanosecond intervals
+    /// since 0001-01-01, such as `636658128703170000`.
+    pub fn dotnet_timestamps(mut self, dotnet_timestamps: bool) -> ReplacerOptions {
+        self.dotnet_timestamps = dotnet_timestamps;
+        self
+    }
+
+    /// Also converts epochs with a leading minus sign, such as `-315619200`, to dates
+    /// before 1970. These are only plausible if `min_year` is lowered to include them.
+    pub fn negative_timestamps(mut self, negative_timestamps: bool) -> ReplacerOptions {
+        self.negative_timestamps = negative_timestamps;
+        self
+    }
+
+    /// Also annotates 18- and 19-digit snowflake IDs with the creation time held in
+    /// their high bits, counted in milliseconds from `epoch_millis`, such as
+    /// `DISCORD_SNOWFLAKE_EPOCH`. The ID is kept, as if `annotate` were set.
+    pub fn snowflake_ids(mut self, epoch_millis: i64) -> ReplacerOptions {
+        self.snowflake_epoch = Some(epoch_millis);
+        self
+    }
+
+    /// Also annotates 24-character MongoDB ObjectIds with the creation time held in
+    /// their first four bytes. The ID is kept, as if `annotate` were set.
+    pub fn object_ids(mut self, object_ids: bool) -> ReplacerOptions {
+        self.object_ids = object_ids;
+        self
+    }
+
+    /// Also annotates version 7 UUIDs with the creation time held in their first 48
+    /// bits. Other versions of UUID are left alone. The UUID is kept, as if `annotate`
+    /// were set.
+    pub fn uuid_timestamps(mut self, uuid_timestamps: bool) -> ReplacerOptions {
+        self.uuid_timestamps = uuid_timestamps;
+        self
+    }
+
+    /// Also annotates 26-character ULIDs with the creation time held in their first ten
+    /// characters. The ULID is kept, as if `annotate` were set.
+    pub fn ulid_timestamps(mut self, ulid_timestamps: bool) -> ReplacerOptions {
+        self.ulid_timestamps = ulid_timestamps;
+        self
+    }
+
+    /// Also annotates JSON Web Tokens with the `iat`, `nbf` and `exp` claims of their
+    /// payload, such as `eyJ... iat=[2018-06-28 20:01:10 UTC]`. Tokens are not verified.
+    pub fn jwt_claims(mut self, jwt_claims: bool) -> ReplacerOptions {
+        self.jwt_claims = jwt_claims;
+        self
+    }
+
+    /// Converts epoch seconds followed by a UTC offset, as in git's raw dates such as
+    /// `1530216070 +0100`, as one value rendered in that offset rather than the
+    /// configured timezone.
+    pub fn git_timestamps(mut self, git_timestamps: bool) -> ReplacerOptions {
+        self.git_timestamps = git_timestamps;
+        self
+    }
+
+    /// Also converts epochs whose digits are grouped in threes by one of `separators`,
+    /// such as the underscores of `1_530_216_070` or the commas of `1,530,216,070`.
+    pub fn digit_separators(mut self, separators: &str) -> ReplacerOptions {
+        self.digit_separators = separators.bytes().collect();
+        self
+    }
+
+    /// Also converts epochs written in scientific notation, as some metrics pipelines
+    /// serialise them, such as `1.53021607e9` or `1.530216070317E12`.
+    pub fn scientific_timestamps(mut self, scientific_timestamps: bool) -> ReplacerOptions {
+        self.scientific_timestamps = scientific_timestamps;
+        self
+    }
+
+    /// Compresses converted files in the same format as their compressed input, rather
+    /// than writing them uncompressed.
+    pub fn compress_output(mut self, compress_output: bool) -> ReplacerOptions {
+        self.compress_output = compress_output;
+        self
+    }
+
+    /// Outputs only the lines in which at least one timestamp was converted.
+    pub fn matching_only(mut self, matching_only: bool) -> ReplacerOptions {
+        self.matching_only = matching_only;
+        self
+    }
+
+    /// Outputs only the lines whose first timestamp is at or after `since`.
+    pub fn since(mut self, since: DateTime<Utc>) -> ReplacerOptions {
+        self.since = Some(since);
+        self
+    }
+
+    /// Outputs only the lines whose first timestamp is at or before `until`.
+    pub fn until(mut self, until: DateTime<Utc>) -> ReplacerOptions {
+        self.until = Some(until);
+        self
+    }
+
+    /// Converts only timestamps directly preceded by one of `prefixes`, optionally
+    /// followed by a space, such as `ts=` or `"time":`. Applies to free-form text.
+    pub fn key_prefixes(mut self, prefixes: Vec<String>) -> ReplacerOptions {
+        self.key_prefixes = Some(prefixes);
+        self
+    }
+
+    /// Leaves digit runs that look like phone numbers unchanged: those directly preceded
+    /// by `+` or a country code such as `+44 `, or joined by a hyphen or parenthesis to
+    /// a group of up to four digits. Applies to free-form text.
+    pub fn skip_phone_numbers(mut self, skip_phone_numbers: bool) -> ReplacerOptions {
+        self.skip_phone_numbers = skip_phone_numbers;
+        self
+    }
+
+    /// Leaves digit runs that look like payment card numbers unchanged: 13 to 19 digits
+    /// that start with a major card network's leading digit and pass the Luhn check.
+    /// Applies to free-form text.
+    pub fn skip_card_numbers(mut self, skip_card_numbers: bool) -> ReplacerOptions {
+        self.skip_card_numbers = skip_card_numbers;
+        self
+    }
+
+    /// Leaves values directly preceded by one of `prefixes`, optionally followed by a
+    /// space, unchanged, such as `order=`. Applies to free-form text.
+    pub fn skip_key_prefixes(mut self, prefixes: Vec<String>) -> ReplacerOptions {
+        self.skip_key_prefixes = Some(prefixes);
+        self
+    }
+
+    /// Leaves phone numbers, card numbers and the values of the
+    /// `DEFAULT_IDENTIFIER_KEY_PREFIXES` unchanged, for logs full of business identifiers.
+    pub fn skip_identifiers(self) -> ReplacerOptions {
+        self.skip_phone_numbers(true)
+            .skip_card_numbers(true)
+            .skip_key_prefixes(
+                DEFAULT_IDENTIFIER_KEY_PREFIXES
+                    .iter()
+                    .map(|prefix| prefix.to_string())
+                    .collect(),
+            )
+    }
+
+    /// Converts only candidates whose confidence, out of 100, is at least
+    /// `min_confidence`. A candidate scores higher for having more than ten digits,
+    /// following a key such as `ts=`, standing apart from words and being in the past,
+    /// and lower for following a key such as `id=` or being a round number of seconds.
+    /// Applies to free-form text.
+    pub fn min_confidence(mut self, min_confidence: u8) -> ReplacerOptions {
+        self.min_confidence = min_confidence;
+        self
+    }
+
+    /// Renders numbers directly preceded by one of `prefixes`, optionally followed by a
+    /// space, as durations such as `1m 33.211s`, taking them as milliseconds and
+    /// replacing a following `ms` unit too. Applies to free-form text.
+    pub fn humanize_durations(mut self, prefixes: Vec<String>) -> ReplacerOptions {
+        self.duration_keys = Some(prefixes);
+        self
+    }
+
+    /// Sets whether the single or double quotes around an epoch value are kept or
+    /// replaced along with it; defaults to keeping them. Applies to free-form text.
+    pub fn quoted_timestamps(mut self, quoted_timestamps: QuotedTimestamps) -> ReplacerOptions {
+        self.quoted_timestamps = quoted_timestamps;
+        self
+    }
+
+    /// Moves every converted timestamp by `shift`, which may be negative, before it is
+    /// rendered, to correct a known clock skew or to anonymise when events happened.
+    /// Lines are selected by `since` and `until` and merged by their shifted timestamps.
+    pub fn shift(mut self, shift: Duration) -> ReplacerOptions {
+        self.shift = Some(shift);
+        self
+    }
+
+    /// Sets how input that is not valid UTF-8 is handled; defaults to passing it through.
+    pub fn invalid_utf8(mut self, invalid_utf8: InvalidUtf8) -> ReplacerOptions {
+        self.invalid_utf8 = invalid_utf8;
+        self
+    }
+
+    /// Converts timestamps only in lines that match `pattern`; other lines are output
+    /// unchanged.
+    pub fn only_if_line_matches(mut self, pattern: &str) -> Result<ReplacerOptions, String> {
+        self.only_if_line_matches = Some(LinePattern::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Leaves lines that match `pattern` unchanged.
+    pub fn skip_if_line_matches(mut self, pattern: &str) -> Result<ReplacerOptions, String> {
+        self.skip_if_line_matches = Some(LinePattern::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Adds a detector for timestamps in another format, which runs after any detectors
+    /// already added and before nail's own epoch scanning. Detectors apply to free-form
+    /// text, and cause it to be converted a line at a time.
+    pub fn detector(mut self, detector: Arc<dyn TimestampDetector>) -> ReplacerOptions {
+        self.detectors.push(detector);
+        self
+    }
+
+    /// Renders converted timestamps with `formatter`, in place of the built-in rendering.
+    pub fn formatter(mut self, formatter: Arc<dyn TimestampFormatter>) -> ReplacerOptions {
+        self.formatter = CustomFormatter::new(formatter);
+        self
+    }
+
+    /// Renders converted timestamps from `template`, such as `{orig} ({iso})`, in place
+    /// of the built-in rendering, as a formatter would. The placeholders are `{orig}`,
+    /// `{iso}`, `{epoch}`, `{epoch_ms}`, `{epoch_us}`, `{epoch_ns}` and `{relative}`,
+    /// such as `3 hours ago`; literal braces are written as `{{` and `}}`.
+    pub fn template(self, template: &str) -> Result<ReplacerOptions, String> {
+        Ok(self.formatter(Arc::new(Template::parse(template)?)))
+    }
+
+    pub fn input_format(mut self, input_format: InputFormat) -> ReplacerOptions {
+        self.input_format = input_format;
+        self
+    }
+
+    /// Configures the options for the logs of a well-known program: `nginx`, `apache`,
+    /// `haproxy`, `envoy`, `logcat`, `redis`, `cloudtrail`, `cloudwatch`, `kafka` or
+    /// `kafka-payload`, which also converts the keys and values of Kafka records.
+    pub fn preset(self, name: &str) -> Result<ReplacerOptions, String> {
+        let access_log = |server| InputFormat::AccessLog { server };
+        let input_format = match name {
+            "nginx" | "apache" => access_log(AccessLogServer::Combined),
+            "haproxy" => access_log(AccessLogServer::Haproxy),
+            "envoy" => access_log(AccessLogServer::Envoy),
+            "logcat" | "redis" => InputFormat::LinePrefix,
+            "cloudtrail" | "cloudwatch" => InputFormat::Json {
+                keys: AWS_JSON_KEYS.iter().map(|key| key.to_string()).collect(),
+            },
+            "kafka" => InputFormat::KafkaConsumer { payload: false },
+            "kafka-payload" => InputFormat::KafkaConsumer { payload: true },
+            _ => return Err(format!("Unknown preset: {}", name)),
+        };
+        Ok(self.input_format(input_format))
+    }
+
+    /// Displays timestamps in the named IANA timezone, e.g. `America/New_York`.
+    pub fn timezone(self, name: &str) -> Result<ReplacerOptions, String> {
+        let tz: Tz = name
+            .parse()
+            .map_err(|_| format!("Unknown timezone: {}", name))?;
+        Ok(self.zone(OutputZone::Named(tz)))
+    }
+
+    /// The number of bytes before a timestamp that are needed to match a prefix or
+    /// phone number, or to score its confidence, or 0 if none are required.
+    pub(crate) fn lookbehind(&self) -> usize {
+        let prefixes = self
+            .key_prefixes
+            .iter()
+            .chain(&self.ldap_prefixes)
+            .chain(&self.skip_key_prefixes)
+            .chain(&self.duration_keys)
+            .flatten()
+            .map(|prefix| prefix.len() + 1)
+            .max()
+            .unwrap_or(0);
+        let phone_numbers = if self.skip_phone_numbers {
+            PHONE_NUMBER_LOOKBEHIND
+        } else {
+            0
+        };
+        let scored_keys = if self.min_confidence > 0 {
+            DEFAULT_KEY_PREFIXES
+                .iter()
+                .chain(DEFAULT_IDENTIFIER_KEY_PREFIXES)
+                .map(|prefix| prefix.len() + 1)
+                .max()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        prefixes.max(phone_numbers).max(scored_keys)
+    }
+
+    /// The epoch seconds from the start of the minimum year to the end of the maximum
+    /// year, within which values are classified by magnitude, or `None` if they are
+    /// classified by their number of digits.
+    pub(crate) fn epoch_seconds_range(&self) -> Option<RangeInclusive<u64>> {
+        if self.classification == EpochClassification::DigitCount {
+            return None;
+        }
+        let start_of_year = |year: i32| {
+            NaiveDate::from_ymd_opt(year, 1, 1)
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map_or(0, |date_time| date_time.and_utc().timestamp().max(0) as u64)
+        };
+        let end = start_of_year(self.max_year.saturating_add(1)).saturating_sub(1);
+        Some(start_of_year(self.min_year)..=end)
+    }
+
+    /// Whether input is converted a line at a time, rather than as an unbroken stream.
+    pub(crate) fn is_line_oriented(&self) -> bool {
+        self.selects_lines()
+            || self.input_format != InputFormat::Text
+            || self.only_if_line_matches.is_some()
+            || self.skip_if_line_matches.is_some()
+            || !self.detectors.is_empty()
+    }
+
+    /// Whether timestamps in `line` are converted, according to the line patterns.
+    pub(crate) fn converts_line(&self, line: &[u8]) -> bool {
+        self.only_if_line_matches
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(line))
+            && !self
+                .skip_if_line_matches
+                .as_ref()
+                .is_some_and(|pattern| pattern.is_match(line))
+    }
+
+    /// Whether output is selected a line at a time, rather than passing all input through.
+    pub(crate) fn selects_lines(&self) -> bool {
+        self.matching_only || self.since.is_some() || self.until.is_some()
+    }
+
+    /// Whether a line is output, given the first timestamp converted in it. Lines
+    /// without a timestamp are never inside a time window.
+    pub(crate) fn selects_line(&self, first_timestamp: Option<DateTime<Utc>>) -> bool {
+        match first_timestamp {
+            Some(timestamp) => {
+                self.since.is_none_or(|since| timestamp >= since)
+                    && self.until.is_none_or(|until| timestamp <= until)
+            }
+            None => !self.selects_lines(),
+        }
+    }
+}
+
+impl Default for ReplacerOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses an instant such as `2018-06-28T19:00Z`, `2018-06-28 19:00:30.5+01:00` or
+/// `2018-06-28`. Seconds may be omitted, and an instant without an offset is in UTC.
+pub fn parse_instant(value: &str) -> Result<DateTime<Utc>, String> {
+    let invalid = || format!("Invalid date/time: {}", value);
+    let (local, offset) = match value.strip_suffix(['Z', 'z']) {
+        Some(local) => (local, Some(FixedOffset::east_opt(0).unwrap())),
+        None => split_offset(value).ok_or_else(invalid)?,
+    };
+    let naive = INSTANT_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(local, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(local, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .ok_or_else(invalid)?;
+    match offset {
+        Some(offset) => offset
+            .from_local_datetime(&naive)
+            .single()
+            .map(|date_time| date_time.with_timezone(&Utc))
+            .ok_or_else(invalid),
+        None => Ok(Utc.from_utc_datetime(&naive)),
+    }
+}
+
+const INSTANT_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Splits a trailing `+HH:MM` or `-HH:MM` offset from a date/time, which has none if
+/// the text after the time contains no sign.
+fn split_offset(value: &str) -> Option<(&str, Option<FixedOffset>)> {
+    let time_start = value.find(['T', 't', ' ']).unwrap_or(value.len());
+    match value[time_start..].rfind(['+', '-']) {
+        Some(sign_index) => {
+            let (local, offset) = value.split_at(time_start + sign_index);
+            let offset = FixedOffset::from_str(offset).ok()?;
+            Some((local, Some(offset)))
+        }
+        None => Some((value, None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_instants_with_and_without_offsets() {
+        let expected = Utc.with_ymd_and_hms(2018, 6, 28, 19, 0, 0).unwrap();
+
+        assert_eq!(Ok(expected), parse_instant("2018-06-28T19:00Z"));
+        assert_eq!(Ok(expected), parse_instant("2018-06-28 19:00:00"));
+        assert_eq!(Ok(expected), parse_instant("2018-06-28T20:00:00.000+01:00"));
+        assert_eq!(Ok(expected), parse_instant("2018-06-28T14:00-05:00"));
+        assert_eq!(
+            Ok(Utc.with_ymd_and_hms(2018, 6, 28, 0, 0, 0).unwrap()),
+            parse_instant("2018-06-28")
+        );
+    }
+
+    #[test]
+    fn reject_invalid_instants() {
+        assert!(parse_instant("2018-06-28T25:00Z").is_err());
+        assert!(parse_instant("yesterday").is_err());
+        assert!(parse_instant("2018-06-28T19:00+1").is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use crate::prometheus::{skip_whitespace, token_end};
+use crate::{parse_epoch_value, replace_line_timestamp, ReplacerOptions, ReplacerState};
+
+/// Converts the frame time of a line of tcpdump (`-tt`) or tshark (`-t e`) output: the
+/// first token of the line, or the second after a tshark frame number. Addresses,
+/// ports, sequence numbers and payload dumps are written unchanged.
+pub(crate) fn replace_packet_timestamps(
+    line: &[u8],
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let (start, end) = frame_time_range(line);
+    replace_token_timestamp(line, start, end, options, state)
+}
+
+/// Converts the token of `line` between `start` and `end` if it is an epoch value,
+/// writing the rest of the line unchanged.
+pub(crate) fn replace_token_timestamp(
+    line: &[u8],
+    start: usize,
+    end: usize,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let converted =
+        parse_epoch_value(&line[start..end], options).map(|timestamp| (start, end, timestamp));
+    replace_line_timestamp(line, converted, options, state)
+}
+
+/// Returns the range of the token that holds the frame time, if the line has one.
+fn frame_time_range(line: &[u8]) -> (usize, usize) {
+    let first_start = skip_whitespace(line, 0);
+    let first_end = token_end(line, first_start);
+    let first = &line[first_start..first_end];
+    if first.is_empty() || !first.iter().all(u8::is_ascii_digit) {
+        return (first_start, first_end);
+    }
+    // a bare integer is a tshark frame number, unless nothing numeric follows it
+    let second_start = skip_whitespace(line, first_end);
+    let second_end = token_end(line, second_start);
+    if second_start < second_end && line[second_start].is_ascii_digit() {
+        (second_start, second_end)
+    } else {
+        (first_start, first_end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{replace_epoch_timestamps_str_with_options, InputFormat};
+
+    fn replace(input: &str) -> String {
+        let options = ReplacerOptions::new().input_format(InputFormat::PacketCapture);
+        replace_epoch_timestamps_str_with_options(input, &options)
+    }
+
+    #[test]
+    fn convert_only_frame_times() {
+        assert_eq!(
+            "[2018-06-28 20:01:10.317123 UTC] IP 10.0.0.1.443 > 10.0.0.2.51234: seq 1530216070:1530216071\n\
+             \t0x0000:  4500 0034 1530 2160 7031 7123\n\
+             \x20   7 [2018-06-28 20:01:10.317123000 UTC] 10.0.0.1 \u{2192} 10.0.0.2 TCP 66 Seq=1530216070\n",
+            replace(
+                "1530216070.317123 IP 10.0.0.1.443 > 10.0.0.2.51234: seq 1530216070:1530216071\n\
+                 \t0x0000:  4500 0034 1530 2160 7031 7123\n\
+                 \x20   7 1530216070.317123000 10.0.0.1 \u{2192} 10.0.0.2 TCP 66 Seq=1530216070\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use crate::json::string_end;
+use crate::packet::replace_token_timestamp;
+use crate::{
+    replace_line_timestamp, ReplacerOptions, ReplacerState, ASCII_CLOSE_BRACE, ASCII_HASH,
+    ASCII_OPEN_BRACE, ASCII_QUOTE,
+};
+
+/// Converts the optional millisecond timestamp that ends a sample in the Prometheus
+/// text exposition format, such as `http_requests_total{code="200"} 1027 1530216070317`.
+/// Comments, metric names, labels and sample values are written unchanged.
+pub(crate) fn replace_prometheus_timestamps(
+    line: &[u8],
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    match timestamp_range(line) {
+        Some((start, end)) => replace_token_timestamp(line, start, end, options, state),
+        None => replace_line_timestamp(line, None, options, state),
+    }
+}
+
+/// Returns the range of the timestamp of a sample line, if it has one: the third token,
+/// after the metric name with its labels and the sample value.
+fn timestamp_range(line: &[u8]) -> Option<(usize, usize)> {
+    let name_start = skip_whitespace(line, 0);
+    if name_start == line.len() || line[name_start] == ASCII_HASH {
+        return None;
+    }
+    let mut index = name_start;
+    while index < line.len()
+        && !line[index].is_ascii_whitespace()
+        && line[index] != ASCII_OPEN_BRACE
+    {
+        index += 1;
+    }
+    if index < line.len() && line[index] == ASCII_OPEN_BRACE {
+        // label values may contain spaces and braces, within quotes
+        while index < line.len() && line[index] != ASCII_CLOSE_BRACE {
+            index = if line[index] == ASCII_QUOTE {
+                string_end(line, index)
+            } else {
+                index + 1
+            };
+        }
+        index += 1;
+    }
+    let value_start = skip_whitespace(line, index.min(line.len()));
+    let value_end = token_end(line, value_start);
+    let timestamp_start = skip_whitespace(line, value_end);
+    let timestamp_end = token_end(line, timestamp_start);
+    if value_start == value_end
+        || timestamp_start == timestamp_end
+        || skip_whitespace(line, timestamp_end) != line.len()
+    {
+        return None;
+    }
+    Some((timestamp_start, timestamp_end))
+}
+
+pub(crate) fn skip_whitespace(line: &[u8], start: usize) -> usize {
+    let mut index = start;
+    while index < line.len() && line[index].is_ascii_whitespace() {
+        index += 1;
+    }
+    index
+}
+
+pub(crate) fn token_end(line: &[u8], start: usize) -> usize {
+    let mut index = start;
+    while index < line.len() && !line[index].is_ascii_whitespace() {
+        index += 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{replace_epoch_timestamps_str_with_options, InputFormat};
+
+    fn replace(input: &str) -> String {
+        let options = ReplacerOptions::new().input_format(InputFormat::Prometheus);
+        replace_epoch_timestamps_str_with_options(input, &options)
+    }
+
+    #[test]
+    fn convert_only_sample_timestamps() {
+        assert_eq!(
+            "# HELP last_seen 1530216070317 as a value\n\
+             last_seen{job=\"a b} 1530216070317\"} 1530216070317 [2018-06-28 20:01:10.317 UTC]\n\
+             last_seen 1530216070317\n\
+             up 1 [2018-06-28 20:01:10.317 UTC]\r\n",
+            replace(
+                "# HELP last_seen 1530216070317 as a value\n\
+                 last_seen{job=\"a b} 1530216070317\"} 1530216070317 1530216070317\n\
+                 last_seen 1530216070317\n\
+                 up 1 1530216070317\r\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,239 @@
+use crate::compression::open_input;
+use crate::{find_matches, ReplacerOptions};
+use chrono::prelude::*;
+use chrono::Duration;
+use std::collections::BTreeMap;
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io::{Read, Write};
+
+const MILLIS_PER_SECOND: i64 = 1000;
+const SECONDS_PER_MINUTE: f64 = 60.0;
+const CSV_HEADER: &str = "window_start,window_end,events,events_per_second,events_per_minute";
+
+/// How the event rates over the input are written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RateFormat {
+    /// The total and average rate, and the busiest and quietest windows.
+    Summary,
+    /// A CSV line for each window, with a header line.
+    Csv,
+}
+
+/// The number of events in one sliding window.
+struct Window {
+    start: DateTime<Utc>,
+    events: u64,
+}
+
+/// Counts the timestamps found in the input over windows of a fixed length that slide
+/// forward by a fixed step, to show the rate of events over time.
+#[derive(Debug)]
+pub struct RateCounter {
+    window_millis: i64,
+    step_millis: i64,
+    /// The number of timestamps in each step, keyed by its start in epoch milliseconds.
+    counts: BTreeMap<i64, u64>,
+}
+
+impl RateCounter {
+    /// The window must be a whole number of steps.
+    pub fn new(window: Duration, step: Duration) -> Result<RateCounter, String> {
+        let window_millis = window.num_milliseconds();
+        let step_millis = step.num_milliseconds();
+        if step_millis <= 0 || window_millis <= 0 {
+            return Err("Rate windows and steps must be at least a millisecond".to_string());
+        }
+        if window_millis % step_millis != 0 {
+            return Err("A rate window must be a whole number of steps".to_string());
+        }
+        Ok(RateCounter {
+            window_millis,
+            step_millis,
+            counts: BTreeMap::new(),
+        })
+    }
+
+    pub fn record(&mut self, date_time: DateTime<Utc>) {
+        let step = date_time.timestamp_millis().div_euclid(self.step_millis) * self.step_millis;
+        *self.counts.entry(step).or_insert(0) += 1;
+    }
+
+    /// Counts the timestamps that `options` would convert in `input`.
+    pub fn record_input(&mut self, input: &mut dyn Read, options: &ReplacerOptions) {
+        find_matches(input, options, |matches| {
+            for timestamp_match in matches {
+                self.record(timestamp_match.date_time);
+            }
+        });
+    }
+
+    pub fn write(&self, format: RateFormat, output: &mut dyn Write) {
+        match format {
+            RateFormat::Summary => self.write_summary(output),
+            RateFormat::Csv => self.write_csv(output),
+        }
+    }
+
+    fn write_csv(&self, output: &mut dyn Write) {
+        writeln!(output, "{}", CSV_HEADER).expect("Failed to write");
+        for window in self.windows() {
+            let end = window.start + Duration::milliseconds(self.window_millis);
+            writeln!(
+                output,
+                "{},{},{},{:.3},{:.3}",
+                window.start.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                end.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                window.events,
+                self.per_second(window.events),
+                self.per_second(window.events) * SECONDS_PER_MINUTE
+            )
+            .expect("Failed to write");
+        }
+    }
+
+    fn write_summary(&self, output: &mut dyn Write) {
+        let (first, last) = match (self.counts.keys().next(), self.counts.keys().last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => {
+                writeln!(output, "0 events").expect("Failed to write");
+                return;
+            }
+        };
+        let events: u64 = self.counts.values().sum();
+        let span_millis = last + self.step_millis - first;
+        let average = events as f64 * MILLIS_PER_SECOND as f64 / span_millis as f64;
+        writeln!(
+            output,
+            "{} events from {} to {}, {:.3}/s ({:.3}/min) on average",
+            events,
+            date_time(first).to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            date_time(last + self.step_millis).to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            average,
+            average * SECONDS_PER_MINUTE
+        )
+        .expect("Failed to write");
+
+        let windows = self.windows();
+        // the earliest of equally busy or quiet windows is reported
+        let busiest = windows.iter().rev().max_by_key(|window| window.events);
+        let quietest = windows.iter().min_by_key(|window| window.events);
+        for (label, window) in [("busiest", busiest), ("quietest", quietest)] {
+            if let Some(window) = window {
+                writeln!(
+                    output,
+                    "{} {} window from {}: {} events, {:.3}/s ({:.3}/min)",
+                    label,
+                    describe_millis(self.window_millis),
+                    window.start.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                    window.events,
+                    self.per_second(window.events),
+                    self.per_second(window.events) * SECONDS_PER_MINUTE
+                )
+                .expect("Failed to write");
+            }
+        }
+    }
+
+    /// The windows that start at each step, from the one starting with the step of the
+    /// first timestamp to the earliest one that holds the last timestamp.
+    fn windows(&self) -> Vec<Window> {
+        let (first, last) = match (self.counts.keys().next(), self.counts.keys().last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => return Vec::new(),
+        };
+        let last_start = (last - self.window_millis + self.step_millis).max(first);
+        let step_count = |step: i64| self.counts.get(&step).copied().unwrap_or(0);
+        let mut events: u64 = (first..first + self.window_millis)
+            .step_by(self.step_millis as usize)
+            .map(step_count)
+            .sum();
+        let mut windows = Vec::new();
+        for start in (first..=last_start).step_by(self.step_millis as usize) {
+            windows.push(Window {
+                start: date_time(start),
+                events,
+            });
+            // slide the window on by one step
+            events = events - step_count(start) + step_count(start + self.window_millis);
+        }
+        windows
+    }
+
+    fn per_second(&self, events: u64) -> f64 {
+        events as f64 * MILLIS_PER_SECOND as f64 / self.window_millis as f64
+    }
+}
+
+/// Describes a window length as `90s` or, if it is not a whole number of seconds,
+/// as `1500ms`.
+fn describe_millis(millis: i64) -> String {
+    if millis % MILLIS_PER_SECOND == 0 {
+        format!("{}s", millis / MILLIS_PER_SECOND)
+    } else {
+        format!("{}ms", millis)
+    }
+}
+
+fn date_time(millis: i64) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(millis).unwrap()
+}
+
+/// Writes the rate of the timestamps in the files, counted together, to standard
+/// output.
+#[cfg(feature = "fs")]
+pub fn rate_files(
+    files: &[String],
+    options: &ReplacerOptions,
+    mut counter: RateCounter,
+    format: RateFormat,
+) {
+    for file_name in files {
+        let (mut input, _) =
+            open_input(File::open(file_name).unwrap()).expect("Error reading from input file");
+        counter.record_input(&mut input, options);
+    }
+    counter.write(format, &mut std::io::stdout().lock());
+}
+
+/// Writes the rate of the timestamps in standard input to standard output.
+pub fn rate_stdin(options: &ReplacerOptions, mut counter: RateCounter, format: RateFormat) {
+    let (mut input, _) =
+        open_input(std::io::stdin().lock()).expect("Error reading from input file");
+    counter.record_input(&mut input, options);
+    counter.write(format, &mut std::io::stdout().lock());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rates(input: &str, format: RateFormat) -> String {
+        let mut counter = RateCounter::new(Duration::seconds(60), Duration::seconds(30)).unwrap();
+        counter.record_input(&mut input.as_bytes(), &ReplacerOptions::new());
+        let mut output = Vec::new();
+        counter.write(format, &mut output);
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn write_rates_over_sliding_windows() {
+        let input = "1530216000 a\n1530216010 b\n1530216040 c\n1530216150317 d\n";
+        assert_eq!(
+            "window_start,window_end,events,events_per_second,events_per_minute\n\
+             2018-06-28T20:00:00Z,2018-06-28T20:01:00Z,3,0.050,3.000\n\
+             2018-06-28T20:00:30Z,2018-06-28T20:01:30Z,1,0.017,1.000\n\
+             2018-06-28T20:01:00Z,2018-06-28T20:02:00Z,0,0.000,0.000\n\
+             2018-06-28T20:01:30Z,2018-06-28T20:02:30Z,0,0.000,0.000\n\
+             2018-06-28T20:02:00Z,2018-06-28T20:03:00Z,1,0.017,1.000\n",
+            rates(input, RateFormat::Csv)
+        );
+        assert_eq!(
+            "4 events from 2018-06-28T20:00:00Z to 2018-06-28T20:03:00Z, 0.022/s (1.333/min) on average\n\
+             busiest 60s window from 2018-06-28T20:00:00Z: 3 events, 0.050/s (3.000/min)\n\
+             quietest 60s window from 2018-06-28T20:01:00Z: 0 events, 0.000/s (0.000/min)\n",
+            rates(input, RateFormat::Summary)
+        );
+        assert!(RateCounter::new(Duration::seconds(60), Duration::seconds(45)).is_err());
+    }
+}
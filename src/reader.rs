@@ -0,0 +1,100 @@
+use crate::{ReplacerOptions, Stats, StreamingReplacer, BUFFER_SIZE};
+use std::io::{self, Read};
+
+/// Converts epoch timestamps in the data read through it from `inner`.
+pub struct DepochReader<R: Read> {
+    inner: R,
+    replacer: StreamingReplacer,
+    read_buffer: Vec<u8>,
+    /// Converted data not yet returned to the caller, from `position` on.
+    converted: Vec<u8>,
+    position: usize,
+    finished: bool,
+}
+
+impl<R: Read> DepochReader<R> {
+    pub fn new(inner: R) -> DepochReader<R> {
+        DepochReader::with_options(inner, ReplacerOptions::new())
+    }
+
+    pub fn with_options(inner: R, options: ReplacerOptions) -> DepochReader<R> {
+        DepochReader {
+            inner,
+            replacer: StreamingReplacer::with_options(options),
+            read_buffer: vec![0; BUFFER_SIZE],
+            converted: Vec::new(),
+            position: 0,
+            finished: false,
+        }
+    }
+
+    /// The totals for the data read so far; the elapsed time is not set.
+    pub fn stats(&self) -> &Stats {
+        self.replacer.stats()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads from `inner` until some converted data is available or it is exhausted.
+    fn fill_converted(&mut self) -> io::Result<()> {
+        while self.position == self.converted.len() && !self.finished {
+            let bytes_read = match self.inner.read(&mut self.read_buffer) {
+                Ok(bytes_read) => bytes_read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            };
+            self.converted = if bytes_read == 0 {
+                // trailing digits are complete at the end of the input
+                self.finished = true;
+                self.replacer.finish()
+            } else {
+                self.replacer.push_chunk(&self.read_buffer[..bytes_read])
+            };
+            self.position = 0;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DepochReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.fill_converted()?;
+        let available = &self.converted[self.position..];
+        let length = available.len().min(buf.len());
+        buf[..length].copy_from_slice(&available[..length]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns at most three bytes per read, so timestamps span several reads.
+    struct TrickleReader<'a>(&'a [u8]);
+
+    impl Read for TrickleReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let length = self.0.len().min(buf.len()).min(3);
+            buf[..length].copy_from_slice(&self.0[..length]);
+            self.0 = &self.0[length..];
+            Ok(length)
+        }
+    }
+
+    #[test]
+    fn convert_timestamps_spanning_reads() {
+        let mut reader = DepochReader::new(TrickleReader(b"a 1530216070317 b\n1530216070"));
+        let mut output = String::new();
+        reader.read_to_string(&mut output).expect("Failed to read");
+
+        assert_eq!(
+            "a [2018-06-28 20:01:10.317 UTC] b\n[2018-06-28 20:01:10 UTC]",
+            output
+        );
+        assert_eq!(2, reader.stats().timestamps_replaced);
+    }
+}
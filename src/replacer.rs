@@ -0,0 +1,86 @@
+use crate::{
+    find_epoch_timestamps_with_options, EpochTimestamps, ReplacerOptions, StreamingReplacer,
+};
+
+/// Builds a `Replacer`; each setter of `ReplacerOptions` configures it, and `build`
+/// fixes the configuration.
+pub type ReplacerBuilder = ReplacerOptions;
+
+/// Converts complete inputs according to a fixed configuration. A `Replacer` is
+/// immutable, so it can be shared between threads and reused for any number of inputs;
+/// each input is converted independently, as if it were a whole stream.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replacer {
+    options: ReplacerOptions,
+}
+
+impl Replacer {
+    pub fn builder() -> ReplacerBuilder {
+        ReplacerOptions::new()
+    }
+
+    pub fn options(&self) -> &ReplacerOptions {
+        &self.options
+    }
+
+    /// Returns `input` with its timestamps converted.
+    pub fn replace(&self, input: &[u8]) -> Vec<u8> {
+        let mut replacer = self.streaming();
+        let mut output = replacer.push_chunk(input);
+        output.extend(replacer.finish());
+        output
+    }
+
+    /// Finds the epoch timestamps in `input` without rewriting it.
+    pub fn find<'a>(&self, input: &'a [u8]) -> EpochTimestamps<'a> {
+        find_epoch_timestamps_with_options(input, &self.options)
+    }
+
+    /// Returns a `StreamingReplacer` for converting an input that arrives in chunks.
+    pub fn streaming(&self) -> StreamingReplacer {
+        StreamingReplacer::with_options(self.options.clone())
+    }
+}
+
+impl ReplacerOptions {
+    pub fn build(self) -> Replacer {
+        Replacer { options: self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InputFormat, OutputStyle};
+
+    #[test]
+    fn reuse_replacer_across_inputs() {
+        let replacer = Replacer::builder()
+            .style(OutputStyle::Rfc3339)
+            .prefix("")
+            .suffix("")
+            .build();
+
+        assert_eq!(
+            b"at 2018-06-28T20:01:10Z".to_vec(),
+            replacer.replace(b"at 1530216070")
+        );
+        assert_eq!(
+            b"2018-06-28T20:01:10.317Z\n".to_vec(),
+            replacer.replace(b"1530216070317\n")
+        );
+    }
+
+    #[test]
+    fn replace_structured_input() {
+        let replacer = Replacer::builder()
+            .input_format(InputFormat::logfmt())
+            .build();
+
+        assert_eq!(
+            b"ts=\"[2018-06-28 20:01:10 UTC]\" id=1530216070".to_vec(),
+            replacer.replace(b"ts=1530216070 id=1530216070")
+        );
+        assert_eq!(1, replacer.find(b"ts=1530216070").count());
+    }
+}
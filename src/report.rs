@@ -0,0 +1,184 @@
+use crate::stats::json_string;
+use crate::{
+    scan_timestamp, Candidate, ReplacerOptions, ReplacerState, StreamingReplacer, TimestampKind,
+    TimestampMatch, ASCII_NEWLINE,
+};
+use chrono::SecondsFormat;
+use std::collections::VecDeque;
+use std::io::Write;
+
+/// How the timestamps found in an input are reported.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportFormat {
+    /// A `name:offset: original datetime` line for each timestamp.
+    Text,
+    /// A JSON object on its own line for each timestamp, with the fields `file`, `line`,
+    /// `byte_offset`, `original`, `kind` and `iso`. The `kind` is `null` for timestamps
+    /// that are not epoch values, such as dmesg uptimes.
+    Json,
+}
+
+/// Writes a record of each timestamp converted by a `StreamingReplacer` that records
+/// matches, as chunks of its input are pushed.
+pub(crate) struct MatchReport<'a> {
+    name: &'a str,
+    format: ReportFormat,
+    output: &'a mut dyn Write,
+    /// The offsets of the newlines pushed at or after the start of the unconverted input.
+    newlines: VecDeque<u64>,
+    pushed: u64,
+    /// The number of the line holding the start of the unconverted input.
+    line: u64,
+}
+
+impl<'a> MatchReport<'a> {
+    pub(crate) fn new(
+        name: &'a str,
+        format: ReportFormat,
+        output: &'a mut dyn Write,
+    ) -> MatchReport<'a> {
+        MatchReport {
+            name,
+            format,
+            output,
+            newlines: VecDeque::new(),
+            pushed: 0,
+            line: 1,
+        }
+    }
+
+    /// Notes the line breaks of `chunk`, which is about to be pushed to the replacer.
+    pub(crate) fn record_chunk(&mut self, chunk: &[u8]) {
+        let pushed = self.pushed;
+        self.newlines.extend(
+            chunk
+                .iter()
+                .enumerate()
+                .filter(|(_, &byte)| byte == ASCII_NEWLINE)
+                .map(|(index, _)| pushed + index as u64),
+        );
+        self.pushed += chunk.len() as u64;
+    }
+
+    /// Writes the timestamps that `replacer` has converted since the last call.
+    pub(crate) fn write_matches(&mut self, replacer: &mut StreamingReplacer) {
+        for timestamp_match in replacer.take_matches() {
+            self.advance_to(timestamp_match.offset);
+            let record = match self.format {
+                ReportFormat::Text => text_record(self.name, &timestamp_match),
+                ReportFormat::Json => {
+                    json_record(self.name, self.line, &timestamp_match, &replacer.options)
+                }
+            };
+            writeln!(self.output, "{}", record).expect("Failed to write");
+        }
+        // later timestamps are all in input that is not yet converted
+        self.advance_to(replacer.state.offset);
+    }
+
+    pub(crate) fn flush(&mut self) {
+        self.output.flush().expect("Error flushing output")
+    }
+
+    fn advance_to(&mut self, offset: u64) {
+        while self
+            .newlines
+            .front()
+            .is_some_and(|&newline| newline < offset)
+        {
+            self.newlines.pop_front();
+            self.line += 1;
+        }
+    }
+}
+
+fn text_record(name: &str, timestamp_match: &TimestampMatch) -> String {
+    format!(
+        "{}:{}: {} {}",
+        name,
+        timestamp_match.offset,
+        timestamp_match.original,
+        timestamp_match
+            .date_time
+            .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+    )
+}
+
+fn json_record(
+    name: &str,
+    line: u64,
+    timestamp_match: &TimestampMatch,
+    options: &ReplacerOptions,
+) -> String {
+    let kind = match original_kind(&timestamp_match.original, options) {
+        Some(kind) => json_string(kind_name(kind)),
+        None => "null".to_string(),
+    };
+    format!(
+        "{{\"file\":{},\"line\":{},\"byte_offset\":{},\"original\":{},\"kind\":{},\"iso\":{}}}",
+        json_string(name),
+        line,
+        timestamp_match.offset,
+        json_string(&timestamp_match.original),
+        kind,
+        json_string(
+            &timestamp_match
+                .date_time
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+        )
+    )
+}
+
+/// The kind of epoch value that `original` consists of entirely, if any.
+fn original_kind(original: &str, options: &ReplacerOptions) -> Option<TimestampKind> {
+    let original = original.as_bytes();
+    match scan_timestamp(original, 0, true, options, &ReplacerState::new()) {
+        Candidate::Timestamp(length, _, kind) if length == original.len() => Some(kind),
+        _ => None,
+    }
+}
+
+fn kind_name(kind: TimestampKind) -> &'static str {
+    match kind {
+        TimestampKind::Seconds => "seconds",
+        TimestampKind::Milliseconds => "milliseconds",
+        TimestampKind::Microseconds => "microseconds",
+        TimestampKind::FractionalSeconds => "fractional_seconds",
+        TimestampKind::Filetime => "filetime",
+        TimestampKind::Ldap => "ldap",
+        TimestampKind::Webkit => "webkit",
+        TimestampKind::DotnetTicks => "dotnet_ticks",
+        TimestampKind::Tai64n => "tai64n",
+        TimestampKind::Snowflake => "snowflake",
+        TimestampKind::ObjectId => "object_id",
+        TimestampKind::Uuid7 => "uuid7",
+        TimestampKind::Ulid => "ulid",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_timestamps_as_json_lines() {
+        let mut replacer = StreamingReplacer::new().recording_matches();
+        let mut output = Vec::new();
+        let mut report = MatchReport::new("app.log", ReportFormat::Json, &mut output);
+        for chunk in ["a 1530216070\nb\n", "c 15302", "16070317 \"d\"\n"] {
+            report.record_chunk(chunk.as_bytes());
+            replacer.push_chunk(chunk.as_bytes());
+            report.write_matches(&mut replacer);
+        }
+        replacer.finish();
+        report.write_matches(&mut replacer);
+
+        assert_eq!(
+            "{\"file\":\"app.log\",\"line\":1,\"byte_offset\":2,\"original\":\"1530216070\",\
+             \"kind\":\"seconds\",\"iso\":\"2018-06-28T20:01:10Z\"}\n\
+             {\"file\":\"app.log\",\"line\":3,\"byte_offset\":17,\"original\":\"1530216070317\",\
+             \"kind\":\"milliseconds\",\"iso\":\"2018-06-28T20:01:10.317Z\"}\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+}
@@ -0,0 +1,362 @@
+use crate::{
+    digit_run_end, epoch_value, EpochUnit, ReplacementResult, ASCII_CLOSE_BRACKET, ASCII_COLON,
+    ASCII_HYPHEN, ASCII_OPEN_BRACKET, ASCII_PLUS, ASCII_ZERO, MAX_FRACTIONAL_DIGITS,
+};
+use chrono::prelude::*;
+
+const DATE_TIME_SEPARATORS: &[u8] = b"Tt ";
+const FRACTION_SEPARATORS: &[u8] = b".,";
+const UTC_DESIGNATORS: &[u8] = b"Zz";
+const UTC_SUFFIX: &[u8] = b" UTC";
+
+/// Replaces ISO-8601/RFC-3339 style datetimes (e.g. `2018-06-28T20:01:10.317Z` or
+/// `2018-06-28 20:01:10+01:00`) in `input` with epoch values in the requested unit.
+/// Datetimes without an offset are taken to be UTC. A datetime in nail's own bracketed
+/// output format is replaced along with its brackets, so converted text round-trips.
+///
+/// As with `replace_epoch_timestamps`, a trailing candidate that may continue in the
+/// next chunk is reported in `left_over_data` unless `end_of_input` is set.
+pub fn replace_datetimes(input: &[u8], unit: EpochUnit, end_of_input: bool) -> ReplacementResult {
+    let mut replaced: Vec<u8> = Vec::new();
+    let mut index = 0;
+    while index < input.len() {
+        let byte = input[index];
+        if byte == ASCII_OPEN_BRACKET {
+            match scan_bracketed_datetime(input, index, end_of_input) {
+                Ok(Some((length, date_time, _))) => {
+                    append_epoch(&date_time, unit, &mut replaced);
+                    index += length;
+                }
+                Ok(None) => {
+                    replaced.push(byte);
+                    index += 1;
+                }
+                Err(Incomplete) => break,
+            }
+        } else if byte.is_ascii_digit() {
+            match scan_datetime(input, index, end_of_input) {
+                Ok(Some((length, date_time, _))) => {
+                    append_epoch(&date_time, unit, &mut replaced);
+                    index += length;
+                }
+                Ok(None) => {
+                    let digits_end = digit_run_end(input, index);
+                    if digits_end == input.len() && !end_of_input {
+                        break;
+                    }
+                    replaced.extend_from_slice(&input[index..digits_end]);
+                    index = digits_end;
+                }
+                Err(Incomplete) => break,
+            }
+        } else {
+            replaced.push(byte);
+            index += 1;
+        }
+    }
+
+    ReplacementResult {
+        data: replaced,
+        left_over_data: (input.len() - index) as u64,
+    }
+}
+
+fn append_epoch(date_time: &DateTime<FixedOffset>, unit: EpochUnit, append_buffer: &mut Vec<u8>) {
+    append_buffer.extend_from_slice(epoch_value(date_time, unit).to_string().as_bytes());
+}
+
+/// Signals that the input ended part way through a possible datetime.
+pub(crate) struct Incomplete;
+
+/// The length of a datetime found in the input, its value and the number of fractional
+/// second digits it was written with, or `None` if there is no datetime.
+pub(crate) type ScanResult = Result<Option<(usize, DateTime<FixedOffset>, usize)>, Incomplete>;
+
+fn scan_bracketed_datetime(input: &[u8], start: usize, end_of_input: bool) -> ScanResult {
+    if let Some((length, date_time, digits)) = scan_datetime(input, start + 1, end_of_input)? {
+        let mut cursor = Cursor::new(input, start + 1 + length, end_of_input);
+        if cursor.accept(&[ASCII_CLOSE_BRACKET])?.is_some() {
+            return Ok(Some((length + 2, date_time, digits)));
+        }
+    }
+    Ok(None)
+}
+
+pub(crate) fn scan_datetime(input: &[u8], start: usize, end_of_input: bool) -> ScanResult {
+    Ok(
+        scan_written_datetime(input, start, end_of_input)?.and_then(|written| {
+            let date_time = FixedOffset::east_opt(written.offset_seconds.unwrap_or(0))
+                .and_then(|offset| offset.from_local_datetime(&written.local).single())?;
+            Some((written.length, date_time, written.fractional_digits))
+        }),
+    )
+}
+
+/// A datetime as it is written in the input, along with its offset if one is given.
+pub(crate) struct WrittenDateTime {
+    pub(crate) length: usize,
+    pub(crate) local: NaiveDateTime,
+    pub(crate) fractional_digits: usize,
+    /// The offset east of UTC, in seconds.
+    pub(crate) offset_seconds: Option<i32>,
+}
+
+pub(crate) fn scan_written_datetime(
+    input: &[u8],
+    start: usize,
+    end_of_input: bool,
+) -> Result<Option<WrittenDateTime>, Incomplete> {
+    let mut cursor = Cursor::new(input, start, end_of_input);
+    let date = match parse_date(&mut cursor)? {
+        Some(date) => date,
+        None => return Ok(None),
+    };
+    if cursor.accept(DATE_TIME_SEPARATORS)?.is_none() {
+        return Ok(None);
+    }
+    let (hour, minute, second) = match parse_time(&mut cursor)? {
+        Some(time) => time,
+        None => return Ok(None),
+    };
+    let (nanos, fractional_digits) = parse_fraction(&mut cursor)?;
+    let offset_seconds = parse_offset(&mut cursor)?;
+
+    Ok(date
+        .and_hms_nano_opt(hour, minute, second, nanos)
+        .map(|local| WrittenDateTime {
+            length: cursor.position - start,
+            local,
+            fractional_digits,
+            offset_seconds,
+        }))
+}
+
+fn parse_date(cursor: &mut Cursor) -> Result<Option<NaiveDate>, Incomplete> {
+    let year = match cursor.number(4)? {
+        Some(year) => year,
+        None => return Ok(None),
+    };
+    if cursor.accept(&[ASCII_HYPHEN])?.is_none() {
+        return Ok(None);
+    }
+    let month = match cursor.number(2)? {
+        Some(month) => month,
+        None => return Ok(None),
+    };
+    if cursor.accept(&[ASCII_HYPHEN])?.is_none() {
+        return Ok(None);
+    }
+    let day = match cursor.number(2)? {
+        Some(day) => day,
+        None => return Ok(None),
+    };
+    Ok(NaiveDate::from_ymd_opt(year as i32, month, day))
+}
+
+pub(crate) fn parse_time(cursor: &mut Cursor) -> Result<Option<(u32, u32, u32)>, Incomplete> {
+    let hour = match cursor.number(2)? {
+        Some(hour) => hour,
+        None => return Ok(None),
+    };
+    if cursor.accept(&[ASCII_COLON])?.is_none() {
+        return Ok(None);
+    }
+    let minute = match cursor.number(2)? {
+        Some(minute) => minute,
+        None => return Ok(None),
+    };
+    if cursor.accept(&[ASCII_COLON])?.is_none() {
+        return Ok(None);
+    }
+    let second = match cursor.number(2)? {
+        Some(second) => second,
+        None => return Ok(None),
+    };
+    Ok(Some((hour, minute, second)))
+}
+
+/// Parses an optional fraction of a second, returning it in nanoseconds along with its
+/// number of digits. Digits beyond nanosecond precision are consumed but ignored.
+pub(crate) fn parse_fraction(cursor: &mut Cursor) -> Result<(u32, usize), Incomplete> {
+    let fraction_start = cursor.position;
+    if cursor.accept(FRACTION_SEPARATORS)?.is_none() {
+        return Ok((0, 0));
+    }
+    let mut nanos = 0;
+    let mut fractional_digits = 0;
+    while let Some(digit) = cursor.digit()? {
+        if fractional_digits < MAX_FRACTIONAL_DIGITS {
+            nanos = nanos * 10 + digit;
+        }
+        fractional_digits += 1;
+    }
+    if fractional_digits == 0 {
+        cursor.position = fraction_start;
+        return Ok((0, 0));
+    }
+    for _ in fractional_digits..MAX_FRACTIONAL_DIGITS {
+        nanos *= 10;
+    }
+    Ok((nanos, fractional_digits.min(MAX_FRACTIONAL_DIGITS)))
+}
+
+/// Parses an optional `Z`, ` UTC` or `+HH:MM`/`-HHMM` offset, returning it in seconds east
+/// of UTC.
+pub(crate) fn parse_offset(cursor: &mut Cursor) -> Result<Option<i32>, Incomplete> {
+    let offset_start = cursor.position;
+    if cursor.accept(UTC_DESIGNATORS)?.is_some() || cursor.accept_all(UTC_SUFFIX)? {
+        return Ok(Some(0));
+    }
+    let sign = match cursor.accept(&[ASCII_PLUS, ASCII_HYPHEN])? {
+        Some(ASCII_HYPHEN) => -1,
+        Some(_) => 1,
+        None => return Ok(None),
+    };
+    if let Some(hours) = cursor.number(2)? {
+        cursor.accept(&[ASCII_COLON])?;
+        if let Some(minutes) = cursor.number(2)? {
+            return Ok(Some(sign * (hours * 3600 + minutes * 60) as i32));
+        }
+    }
+    cursor.position = offset_start;
+    Ok(None)
+}
+
+pub(crate) struct Cursor<'a> {
+    input: &'a [u8],
+    pub(crate) position: usize,
+    end_of_input: bool,
+}
+
+impl<'a> Cursor<'a> {
+    pub(crate) fn new(input: &'a [u8], position: usize, end_of_input: bool) -> Cursor<'a> {
+        Cursor {
+            input,
+            position,
+            end_of_input,
+        }
+    }
+
+    pub(crate) fn peek(&self) -> Result<Option<u8>, Incomplete> {
+        match self.input.get(self.position) {
+            Some(&byte) => Ok(Some(byte)),
+            None if self.end_of_input => Ok(None),
+            None => Err(Incomplete),
+        }
+    }
+
+    /// Consumes the next byte if it is one of `accepted`.
+    pub(crate) fn accept(&mut self, accepted: &[u8]) -> Result<Option<u8>, Incomplete> {
+        match self.peek()? {
+            Some(byte) if accepted.contains(&byte) => {
+                self.position += 1;
+                Ok(Some(byte))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Consumes `expected` if the input continues with exactly that sequence.
+    pub(crate) fn accept_all(&mut self, expected: &[u8]) -> Result<bool, Incomplete> {
+        let start = self.position;
+        for &byte in expected {
+            if self.accept(&[byte])?.is_none() {
+                self.position = start;
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn digit(&mut self) -> Result<Option<u32>, Incomplete> {
+        match self.peek()? {
+            Some(byte) if byte.is_ascii_digit() => {
+                self.position += 1;
+                Ok(Some((byte - ASCII_ZERO) as u32))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub(crate) fn number(&mut self, digits: usize) -> Result<Option<u32>, Incomplete> {
+        let mut value = 0;
+        for _ in 0..digits {
+            match self.digit()? {
+                Some(digit) => value = value * 10 + digit,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(value))
+    }
+
+    /// Consumes a number of one digit, or of two if the input continues with another.
+    pub(crate) fn short_number(&mut self) -> Result<Option<u32>, Incomplete> {
+        let first = match self.digit()? {
+            Some(digit) => digit,
+            None => return Ok(None),
+        };
+        Ok(Some(match self.digit()? {
+            Some(second) => first * 10 + second,
+            None => first,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{replace_epoch_timestamps_str_with_options, Conversion, ReplacerOptions};
+
+    fn replace(input: &str, unit: EpochUnit) -> String {
+        let options = ReplacerOptions::new().conversion(Conversion::ToEpoch(unit));
+        replace_epoch_timestamps_str_with_options(input, &options)
+    }
+
+    #[test]
+    fn replace_rfc3339_datetimes_with_epoch_seconds() {
+        assert_eq!(
+            "from 1530216070 to 1530216070 until 1530212470.",
+            replace(
+                "from 2018-06-28T20:01:10Z to 2018-06-28T20:01:10.317z until 2018-06-28T20:01:10+01:00.",
+                EpochUnit::Seconds
+            )
+        );
+    }
+
+    #[test]
+    fn replace_datetimes_with_epoch_milliseconds() {
+        assert_eq!(
+            "a=1530216070317, b=1530237670000",
+            replace(
+                "a=2018-06-28 20:01:10.317, b=2018-06-28T20:01:10-0600",
+                EpochUnit::Milliseconds
+            )
+        );
+    }
+
+    #[test]
+    fn round_trip_depoch_output() {
+        assert_eq!(
+            "id 42 at 1530216070317 and 1530216070000",
+            replace(
+                "id 42 at [2018-06-28 20:01:10.317 UTC] and [2018-06-28 20:01:10 UTC]",
+                EpochUnit::Milliseconds
+            )
+        );
+    }
+
+    #[test]
+    fn leave_invalid_dates_and_plain_numbers_untouched() {
+        let input = "2018-13-28T20:01:10Z [2018-06-28] 20180628 1234-56";
+        assert_eq!(input, replace(input, EpochUnit::Seconds));
+    }
+
+    #[test]
+    fn indicate_trailing_partial_datetime() {
+        let input = "at 2018-06-28T20:01:1";
+        let response = replace_datetimes(input.as_bytes(), EpochUnit::Seconds, false);
+
+        assert_eq!(18, response.left_over_data);
+        assert_eq!("at ".as_bytes(), response.data.as_slice());
+    }
+}
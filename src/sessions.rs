@@ -0,0 +1,181 @@
+use crate::compression::open_input;
+#[cfg(feature = "fs")]
+use crate::compression::{strip_extension, CompressedWriter, Compression};
+#[cfg(feature = "fs")]
+use crate::create_output;
+use crate::gaps::{describe_gap, Gap, GapFinder};
+use crate::merge::LineSource;
+use crate::ReplacerOptions;
+use chrono::Duration;
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io::{Read, Write};
+
+/// Where the sessions of an input, split at gaps in its timestamps, are written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SessionOutput {
+    /// The converted output, with a separator line describing each gap.
+    Separators,
+    /// A converted file for each session, named with its number from 1, as in
+    /// `app.log.session-2.depoch`.
+    Files,
+}
+
+/// Converts each file, splitting it into sessions wherever the timestamps of its lines
+/// are more than `gap` apart, as `gaps` would report them.
+#[cfg(feature = "fs")]
+pub fn split_sessions_in_files(
+    files: &[String],
+    options: &ReplacerOptions,
+    gap: Duration,
+    output: SessionOutput,
+) {
+    for file_name in files {
+        let (input, compression) =
+            open_input(File::open(file_name).unwrap()).expect("Error reading from input file");
+        match output {
+            SessionOutput::Separators => {
+                let mut writer = SeparatedSessions(create_output(file_name, compression, options));
+                split_sessions(input, options, gap, &mut writer);
+                writer.0.finish().expect("Failed to write");
+            }
+            SessionOutput::Files => {
+                let mut writer = SessionFiles::new(file_name, compression, options);
+                split_sessions(input, options, gap, &mut writer);
+                writer.output.finish().expect("Failed to write");
+            }
+        }
+    }
+}
+
+/// Converts standard input to standard output, with a separator line wherever its
+/// timestamps are more than `gap` apart.
+pub fn split_sessions_in_stdin(options: &ReplacerOptions, gap: Duration) {
+    let (input, _) = open_input(std::io::stdin().lock()).expect("Error reading from input file");
+    let stdout = std::io::stdout();
+    let mut writer = SeparatedSessions(stdout.lock());
+    split_sessions(input, options, gap, &mut writer);
+    writer.0.flush().expect("Error flushing output");
+}
+
+fn split_sessions(
+    input: Box<dyn Read + '_>,
+    options: &ReplacerOptions,
+    gap: Duration,
+    writer: &mut dyn SessionWriter,
+) {
+    let mut source = LineSource::new("", input, options);
+    let mut gaps = GapFinder::new(gap);
+    while let Some(key) = source.advance() {
+        if let Some(gap) = key.and_then(|date_time| gaps.record(date_time)) {
+            writer.start_session(&gap);
+        }
+        writer.write_line(&source.line);
+    }
+}
+
+/// Receives the converted lines of an input that is split into sessions.
+trait SessionWriter {
+    /// Starts a new session, which begins after `gap`.
+    fn start_session(&mut self, gap: &Gap);
+
+    fn write_line(&mut self, line: &[u8]);
+}
+
+/// Writes all sessions to one output, with a separator line between them.
+struct SeparatedSessions<W: Write>(W);
+
+impl<W: Write> SessionWriter for SeparatedSessions<W> {
+    fn start_session(&mut self, gap: &Gap) {
+        writeln!(self.0, "-- {} --", describe_gap(gap)).expect("Failed to write");
+    }
+
+    fn write_line(&mut self, line: &[u8]) {
+        self.0.write_all(line).expect("Failed to write");
+    }
+}
+
+/// Writes each session to a file of its own, alongside the input file.
+#[cfg(feature = "fs")]
+struct SessionFiles<'a> {
+    file_name: &'a str,
+    compression: Compression,
+    options: &'a ReplacerOptions,
+    session: u64,
+    output: CompressedWriter<File>,
+}
+
+#[cfg(feature = "fs")]
+impl<'a> SessionFiles<'a> {
+    fn new(
+        file_name: &'a str,
+        compression: Compression,
+        options: &'a ReplacerOptions,
+    ) -> SessionFiles<'a> {
+        SessionFiles {
+            file_name,
+            compression,
+            options,
+            session: 1,
+            output: create_session_output(file_name, 1, compression, options),
+        }
+    }
+}
+
+#[cfg(feature = "fs")]
+impl SessionWriter for SessionFiles<'_> {
+    fn start_session(&mut self, _gap: &Gap) {
+        self.session += 1;
+        let next =
+            create_session_output(self.file_name, self.session, self.compression, self.options);
+        std::mem::replace(&mut self.output, next)
+            .finish()
+            .expect("Failed to write");
+    }
+
+    fn write_line(&mut self, line: &[u8]) {
+        self.output.write_all(line).expect("Failed to write");
+    }
+}
+
+/// Creates the output file of a session, which is named as if the input had a
+/// `.session-N` suffix.
+#[cfg(feature = "fs")]
+fn create_session_output(
+    file_name: &str,
+    session: u64,
+    compression: Compression,
+    options: &ReplacerOptions,
+) -> CompressedWriter<File> {
+    let session_name = format!(
+        "{}.session-{}{}",
+        strip_extension(file_name, compression),
+        session,
+        compression.extension()
+    );
+    create_output(&session_name, compression, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separate_sessions_at_gaps() {
+        let input = "1530216070 a\n  continued\n1530216100 b\n1530216700 c\n1530216710 d\n";
+        let mut writer = SeparatedSessions(Vec::new());
+        split_sessions(
+            Box::new(input.as_bytes()),
+            &ReplacerOptions::new(),
+            Duration::try_minutes(5).unwrap(),
+            &mut writer,
+        );
+
+        assert_eq!(
+            "[2018-06-28 20:01:10 UTC] a\n  continued\n[2018-06-28 20:01:40 UTC] b\n\
+             -- no events for 00:10:00 between 2018-06-28T20:01:40Z and 2018-06-28T20:11:40Z --\n\
+             [2018-06-28 20:11:40 UTC] c\n[2018-06-28 20:11:50 UTC] d\n",
+            String::from_utf8(writer.0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use crate::compression::{open_input, strip_extension, CompressedWriter, Compression};
+use crate::merge::LineSource;
+use crate::{open_output, ReplacerOptions};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{Read, Write};
+
+/// The period of time whose lines are written to each file by `split_files_by`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SplitPeriod {
+    Day,
+    Hour,
+}
+
+impl SplitPeriod {
+    fn label_format(self) -> &'static str {
+        match self {
+            SplitPeriod::Day => "%Y-%m-%d",
+            SplitPeriod::Hour => "%Y-%m-%dT%H",
+        }
+    }
+}
+
+/// Converts each file into a file per day or hour, in UTC, named after it as in
+/// `app.log.2018-06-28.depoch` or `app.log.2018-06-28T20.depoch`. Each line is written
+/// to the file of its first timestamp; a line without one goes with the line before
+/// it, and lines before the first timestamp go with the first line that has one.
+pub fn split_files_by(files: &[String], options: &ReplacerOptions, period: SplitPeriod) {
+    for file_name in files {
+        let (input, compression) =
+            open_input(File::open(file_name).unwrap()).expect("Error reading from input file");
+        split_by(file_name, input, compression, options, period);
+    }
+}
+
+/// Converts standard input into a file per day or hour, as `split_files_by` does,
+/// named after the period alone, as in `2018-06-28.depoch`.
+pub fn split_stdin_by(options: &ReplacerOptions, period: SplitPeriod) {
+    let (input, _) = open_input(std::io::stdin().lock()).expect("Error reading from input file");
+    split_by("", input, Compression::None, options, period);
+}
+
+fn split_by(
+    file_name: &str,
+    input: Box<dyn Read + '_>,
+    compression: Compression,
+    options: &ReplacerOptions,
+    period: SplitPeriod,
+) {
+    let mut source = LineSource::new("", input, options);
+    let mut output: Option<(String, CompressedWriter<File>)> = None;
+    // files written earlier in this run are appended to when lines return to them
+    let mut created = HashSet::new();
+    let mut undated = Vec::new();
+    while let Some(key) = source.advance() {
+        let label = match key {
+            Some(date_time) => date_time.format(period.label_format()).to_string(),
+            None => {
+                undated.extend_from_slice(&source.line);
+                continue;
+            }
+        };
+        if output.as_ref().is_none_or(|(current, _)| *current != label) {
+            if let Some((_, previous)) = output.take() {
+                previous.finish().expect("Failed to write");
+            }
+            let append = !created.insert(label.clone());
+            let writer = open_output(
+                &period_file_name(file_name, &label, compression),
+                compression,
+                options,
+                append,
+            );
+            output = Some((label, writer));
+        }
+        let (_, writer) = output.as_mut().unwrap();
+        writer
+            .write_all(&std::mem::take(&mut undated))
+            .and_then(|_| writer.write_all(&source.line))
+            .expect("Failed to write");
+    }
+    match output {
+        Some((_, writer)) => {
+            writer.finish().expect("Failed to write");
+        }
+        // an input without timestamps is converted as a whole
+        None if !file_name.is_empty() => {
+            let mut writer = open_output(file_name, compression, options, false);
+            writer.write_all(&undated).expect("Failed to write");
+            writer.finish().expect("Failed to write");
+        }
+        None => {}
+    }
+}
+
+/// The input name from which the output file of the period `label` is named.
+fn period_file_name(file_name: &str, label: &str, compression: Compression) -> String {
+    if file_name.is_empty() {
+        return label.to_string();
+    }
+    format!(
+        "{}.{}{}",
+        strip_extension(file_name, compression),
+        label,
+        compression.extension()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn split_lines_into_a_file_per_hour() {
+        let file_name = format!("/tmp/{:?}-app.log", Instant::now());
+        let input = "header\n1530216070 a\n  continued\n1530219600 b\n1530216080 late\n";
+        split_by(
+            &file_name,
+            Box::new(input.as_bytes()),
+            Compression::None,
+            &ReplacerOptions::new(),
+            SplitPeriod::Hour,
+        );
+
+        let read = |hour: &str| {
+            std::fs::read_to_string(format!("{}.2018-06-28T{}.depoch", file_name, hour)).unwrap()
+        };
+        assert_eq!(
+            "header\n[2018-06-28 20:01:10 UTC] a\n  continued\n[2018-06-28 20:01:20 UTC] late\n",
+            read("20")
+        );
+        assert_eq!("[2018-06-28 21:00:00 UTC] b\n", read("21"));
+    }
+}
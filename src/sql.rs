@@ -0,0 +1,313 @@
+use crate::formatter::append_custom_format;
+use crate::prometheus::skip_whitespace;
+use crate::{
+    parse_epoch_value, record_timestamp, render_timestamp, CsvColumn, ReplacerOptions,
+    ReplacerState, ANSI_HIGHLIGHT, ANSI_RESET, ASCII_APOSTROPHE, ASCII_BACKSLASH,
+    ASCII_CLOSE_PAREN, ASCII_COMMA, ASCII_QUOTE,
+};
+use std::collections::HashMap;
+
+const ASCII_OPEN_PAREN: u8 = 40;
+const ASCII_BACKTICK: u8 = 96;
+const INSERT_INTO: &str = "INSERT INTO ";
+const CREATE_TABLE: &str = "CREATE TABLE ";
+const IF_NOT_EXISTS: &str = "IF NOT EXISTS ";
+const VALUES: &str = "VALUES";
+/// The words that start the lines of a table definition that are not columns.
+const CONSTRAINT_KEYWORDS: &[&str] = &[
+    "PRIMARY",
+    "KEY",
+    "UNIQUE",
+    "CONSTRAINT",
+    "INDEX",
+    "FOREIGN",
+    "CHECK",
+    "FULLTEXT",
+    "SPATIAL",
+];
+
+/// Progress through a SQL dump, carried from one line to the next.
+#[derive(Default)]
+pub(crate) struct SqlState {
+    /// The names of the columns of each table defined so far, by table name.
+    tables: HashMap<Vec<u8>, Vec<Vec<u8>>>,
+    /// The table whose definition is being read, if any.
+    creating: Option<Vec<u8>>,
+}
+
+/// Converts the values of an `INSERT INTO ... VALUES` statement of a mysqldump or
+/// pg_dump text dump that are in one of `columns` and consist entirely of an epoch value,
+/// which may be quoted; no columns selects every column. Columns are named by the
+/// statement's column list or, failing that, by the `CREATE TABLE` statement of its
+/// table. Converted values are written as SQL strings, and every other line is written
+/// unchanged.
+pub(crate) fn replace_sql_timestamps(
+    line: &[u8],
+    columns: &[CsvColumn],
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let start = skip_whitespace(line, 0);
+    let statement = &line[start..];
+    let mut replaced = Vec::new();
+    if let Some(table) = state.sql.creating.take() {
+        if statement.first() != Some(&ASCII_CLOSE_PAREN) {
+            if let Some(column) = column_definition(statement) {
+                state
+                    .sql
+                    .tables
+                    .entry(table.clone())
+                    .or_default()
+                    .push(column);
+            }
+            state.sql.creating = Some(table);
+        }
+    } else if let Some(definition) = strip_keyword(statement, CREATE_TABLE) {
+        let definition = strip_keyword(definition, IF_NOT_EXISTS).unwrap_or(definition);
+        let table = identifier(&definition[..name_end(definition)]);
+        state.sql.tables.insert(table.clone(), Vec::new());
+        state.sql.creating = Some(table);
+    } else if let Some(insert) = strip_keyword(statement, INSERT_INTO) {
+        let insert_start = line.len() - insert.len();
+        replace_insert_values(line, insert_start, columns, options, state, &mut replaced);
+    }
+    if replaced.is_empty() {
+        replaced.extend_from_slice(line);
+    }
+
+    if let Some(&byte) = line.last() {
+        state.preceding_byte = Some(byte);
+    }
+    state.offset += line.len() as u64;
+    replaced
+}
+
+/// Writes `line` with the selected values of the INSERT statement whose table name
+/// starts at `start` converted, unless it has no `VALUES`.
+fn replace_insert_values(
+    line: &[u8],
+    start: usize,
+    columns: &[CsvColumn],
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+    append_buffer: &mut Vec<u8>,
+) {
+    let table_end = start + name_end(&line[start..]);
+    let table = identifier(&line[start..table_end]);
+    let mut names = state.sql.tables.get(&table).cloned().unwrap_or_default();
+    let mut index = skip_whitespace(line, table_end);
+    if line.get(index) == Some(&ASCII_OPEN_PAREN) {
+        let close = match line[index..]
+            .iter()
+            .position(|&byte| byte == ASCII_CLOSE_PAREN)
+        {
+            Some(close) => index + close,
+            None => return,
+        };
+        names = line[index + 1..close]
+            .split(|&byte| byte == ASCII_COMMA)
+            .map(|name| identifier(name.trim_ascii()))
+            .collect();
+        index = skip_whitespace(line, close + 1);
+    }
+    if strip_keyword(&line[index..], VALUES).is_none() {
+        return;
+    }
+    let selected = select_columns(columns, &names);
+
+    let mut written = 0;
+    let mut depth = 0;
+    let mut field = 0;
+    let mut field_start = index;
+    while index < line.len() {
+        match line[index] {
+            ASCII_APOSTROPHE => {
+                index = string_end(line, index);
+                continue;
+            }
+            ASCII_OPEN_PAREN => {
+                depth += 1;
+                if depth == 1 {
+                    field = 0;
+                    field_start = index + 1;
+                }
+            }
+            ASCII_COMMA | ASCII_CLOSE_PAREN if depth == 1 => {
+                if columns.is_empty() || selected.contains(&field) {
+                    let value_start = skip_whitespace(line, field_start);
+                    let value_end = value_start + line[value_start..index].trim_ascii_end().len();
+                    if append_value(
+                        line,
+                        value_start,
+                        value_end,
+                        written,
+                        options,
+                        state,
+                        append_buffer,
+                    ) {
+                        written = value_end;
+                    }
+                }
+                field += 1;
+                field_start = index + 1;
+                if line[index] == ASCII_CLOSE_PAREN {
+                    depth -= 1;
+                }
+            }
+            ASCII_CLOSE_PAREN if depth > 1 => depth -= 1,
+            _ => {}
+        }
+        index += 1;
+    }
+    append_buffer.extend_from_slice(&line[written..]);
+}
+
+/// Writes the value between `start` and `end` as a SQL string if it is an epoch value,
+/// preceded by the unchanged input since `written`, and returns whether it was.
+fn append_value(
+    line: &[u8],
+    start: usize,
+    end: usize,
+    written: usize,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+    append_buffer: &mut Vec<u8>,
+) -> bool {
+    let field = &line[start..end];
+    let quoted = field.len() >= 2
+        && field[0] == ASCII_APOSTROPHE
+        && field[field.len() - 1] == ASCII_APOSTROPHE;
+    let value = if quoted {
+        &field[1..field.len() - 1]
+    } else {
+        field
+    };
+    let timestamp = match parse_epoch_value(value, options) {
+        Some(timestamp) => timestamp,
+        None => return false,
+    };
+    append_buffer.extend_from_slice(&line[written..start]);
+    let value_start = start + if quoted { 1 } else { 0 };
+    record_timestamp(&timestamp, value, value_start, options, state);
+    if append_custom_format(&timestamp, value, options, append_buffer) {
+        return true;
+    }
+    let rendered = render_timestamp(&timestamp, options, state);
+    if options.color {
+        append_buffer.extend_from_slice(ANSI_HIGHLIGHT);
+    }
+    append_buffer.push(ASCII_APOSTROPHE);
+    append_buffer.extend_from_slice(rendered.replace('\'', "''").as_bytes());
+    append_buffer.push(ASCII_APOSTROPHE);
+    if options.color {
+        append_buffer.extend_from_slice(ANSI_RESET);
+    }
+    true
+}
+
+fn select_columns(columns: &[CsvColumn], names: &[Vec<u8>]) -> Vec<usize> {
+    columns
+        .iter()
+        .filter_map(|column| match column {
+            CsvColumn::Index(index) => index.checked_sub(1),
+            CsvColumn::Name(name) => names.iter().position(|field| field == name.as_bytes()),
+        })
+        .collect()
+}
+
+/// Returns the name of the column defined by a line of a `CREATE TABLE` statement, or
+/// `None` if the line defines a key or constraint.
+fn column_definition(line: &[u8]) -> Option<Vec<u8>> {
+    let name = &line[..name_end(line)];
+    let is_constraint = CONSTRAINT_KEYWORDS
+        .iter()
+        .any(|keyword| name.eq_ignore_ascii_case(keyword.as_bytes()));
+    if name.is_empty() || is_constraint {
+        return None;
+    }
+    Some(identifier(name))
+}
+
+/// Returns `bytes` after `keyword`, which is matched regardless of case.
+fn strip_keyword<'a>(bytes: &'a [u8], keyword: &str) -> Option<&'a [u8]> {
+    let length = keyword.len();
+    if bytes.len() >= length && bytes[..length].eq_ignore_ascii_case(keyword.as_bytes()) {
+        Some(&bytes[length..])
+    } else {
+        None
+    }
+}
+
+/// Returns the end of the possibly quoted table or column name that starts `bytes`.
+fn name_end(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .position(|&byte| byte.is_ascii_whitespace() || byte == ASCII_OPEN_PAREN)
+        .unwrap_or(bytes.len())
+}
+
+/// Removes the backticks or double quotes from a name such as `` `db`.`table` ``.
+fn identifier(name: &[u8]) -> Vec<u8> {
+    name.iter()
+        .filter(|&&byte| byte != ASCII_BACKTICK && byte != ASCII_QUOTE)
+        .copied()
+        .collect()
+}
+
+/// Returns the index just past the SQL string literal starting at `start`, in which a
+/// quote is escaped by doubling it or, as mysqldump does, with a backslash.
+fn string_end(line: &[u8], start: usize) -> usize {
+    let mut index = start + 1;
+    while index < line.len() {
+        match line[index] {
+            ASCII_BACKSLASH => index += 2,
+            ASCII_APOSTROPHE if line.get(index + 1) == Some(&ASCII_APOSTROPHE) => index += 2,
+            ASCII_APOSTROPHE => return index + 1,
+            _ => index += 1,
+        }
+    }
+    line.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{replace_epoch_timestamps_str_with_options, InputFormat};
+
+    fn replace(input: &str, columns: Vec<CsvColumn>) -> String {
+        let options = ReplacerOptions::new().input_format(InputFormat::SqlDump { columns });
+        replace_epoch_timestamps_str_with_options(input, &options)
+    }
+
+    #[test]
+    fn convert_columns_named_by_create_table() {
+        assert_eq!(
+            "CREATE TABLE `orders` (\n\
+             \x20 `id` bigint NOT NULL,\n\
+             \x20 `created_at` int DEFAULT NULL,\n\
+             \x20 PRIMARY KEY (`id`)\n\
+             );\n\
+             INSERT INTO `orders` VALUES (1530216070,'[2018-06-28 20:01:10 UTC]'),(2,NULL),(3,'[2018-06-28 20:01:10.317 UTC]');\n",
+            replace(
+                "CREATE TABLE `orders` (\n\
+                 \x20 `id` bigint NOT NULL,\n\
+                 \x20 `created_at` int DEFAULT NULL,\n\
+                 \x20 PRIMARY KEY (`id`)\n\
+                 );\n\
+                 INSERT INTO `orders` VALUES (1530216070,1530216070),(2,NULL),(3,'1530216070317');\n",
+                vec![CsvColumn::Name("created_at".to_string())]
+            )
+        );
+    }
+
+    #[test]
+    fn convert_columns_of_column_inserts_by_position() {
+        assert_eq!(
+            "INSERT INTO public.events (note, id, ts) VALUES ('it''s 1530216070, (ok)', 1530216070, '[2018-06-28 20:01:10 UTC]');\n",
+            replace(
+                "INSERT INTO public.events (note, id, ts) VALUES ('it''s 1530216070, (ok)', 1530216070, 1530216070);\n",
+                vec![CsvColumn::Index(1), CsvColumn::Index(3)]
+            )
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use crate::formatter::append_custom_format;
+use crate::{
+    parse_epoch_value, record_timestamp, render_timestamp, ReplacerOptions, ReplacerState,
+};
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection};
+use std::borrow::Cow;
+use std::io::Write;
+
+/// Where the converted rows of a SQLite table are written.
+pub enum SqliteOutput<'a> {
+    /// A copy of the database at the given path, in which the table's columns hold the
+    /// converted values. The table must have a rowid.
+    Copy(&'a str),
+    /// A CSV report of every column of the table, with the header line of their names.
+    Csv(&'a mut dyn Write),
+}
+
+/// Converts the epoch values in `columns` of `table` in the SQLite database at
+/// `database`, which is never modified. Values are converted if they consist entirely
+/// of an epoch value, whether they are stored as integers, reals or text.
+pub fn convert_sqlite_table(
+    database: &str,
+    table: &str,
+    columns: &[String],
+    output: SqliteOutput,
+    options: &ReplacerOptions,
+) -> Result<(), String> {
+    let error = |error: rusqlite::Error| format!("{}: {}", database, error);
+    match output {
+        SqliteOutput::Copy(path) => {
+            std::fs::copy(database, path)
+                .map_err(|error| format!("Unable to copy {}: {}", database, error))?;
+            let mut connection = Connection::open(path).map_err(error)?;
+            convert_columns(&mut connection, table, columns, options).map_err(error)
+        }
+        SqliteOutput::Csv(output) => {
+            let connection = Connection::open(database).map_err(error)?;
+            write_csv_report(&connection, table, columns, options, output).map_err(error)
+        }
+    }
+}
+
+fn convert_columns(
+    connection: &mut Connection,
+    table: &str,
+    columns: &[String],
+    options: &ReplacerOptions,
+) -> rusqlite::Result<()> {
+    let mut state = ReplacerState::new();
+    let transaction = connection.transaction()?;
+    for column in columns {
+        let select = format!("SELECT rowid, {} FROM {}", quoted(column), quoted(table));
+        let update = format!(
+            "UPDATE {} SET {} = ?1 WHERE rowid = ?2",
+            quoted(table),
+            quoted(column)
+        );
+        let mut updates = Vec::new();
+        {
+            let mut statement = transaction.prepare(&select)?;
+            let mut rows = statement.query([])?;
+            while let Some(row) = rows.next()? {
+                let rowid: i64 = row.get(0)?;
+                if let Some(converted) = convert_value(row.get_ref(1)?, options, &mut state) {
+                    updates.push((rowid, converted));
+                }
+            }
+        }
+        let mut statement = transaction.prepare(&update)?;
+        for (rowid, converted) in updates {
+            statement.execute(params![converted, rowid])?;
+        }
+    }
+    transaction.commit()
+}
+
+fn write_csv_report(
+    connection: &Connection,
+    table: &str,
+    columns: &[String],
+    options: &ReplacerOptions,
+    output: &mut dyn Write,
+) -> rusqlite::Result<()> {
+    let mut state = ReplacerState::new();
+    let mut statement = connection.prepare(&format!("SELECT * FROM {}", quoted(table)))?;
+    let names: Vec<String> = statement
+        .column_names()
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    let mut selected = Vec::new();
+    for column in columns {
+        match names.iter().position(|name| name == column) {
+            Some(index) => selected.push(index),
+            None => return Err(rusqlite::Error::InvalidColumnName(column.clone())),
+        }
+    }
+    let write = |output: &mut dyn Write, fields: Vec<String>| {
+        let line: Vec<Cow<str>> = fields.iter().map(|field| csv_field(field)).collect();
+        writeln!(output, "{}", line.join(",")).expect("Failed to write");
+    };
+
+    write(output, names.clone());
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let mut fields = Vec::with_capacity(names.len());
+        for index in 0..names.len() {
+            let value = row.get_ref(index)?;
+            let converted = if selected.contains(&index) {
+                convert_value(value, options, &mut state)
+            } else {
+                None
+            };
+            fields.push(converted.unwrap_or_else(|| value_text(value)));
+        }
+        write(output, fields);
+    }
+    Ok(())
+}
+
+/// Renders `value` if it is an epoch value, or returns `None`.
+fn convert_value(
+    value: ValueRef,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Option<String> {
+    let text = match value {
+        ValueRef::Integer(_) | ValueRef::Real(_) | ValueRef::Text(_) => value_text(value),
+        ValueRef::Null | ValueRef::Blob(_) => return None,
+    };
+    let timestamp = parse_epoch_value(text.as_bytes(), options)?;
+    record_timestamp(&timestamp, text.as_bytes(), 0, options, state);
+    let mut custom = Vec::new();
+    if append_custom_format(&timestamp, text.as_bytes(), options, &mut custom) {
+        return Some(String::from_utf8_lossy(&custom).into_owned());
+    }
+    Some(render_timestamp(&timestamp, options, state))
+}
+
+fn value_text(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => String::new(),
+        ValueRef::Integer(integer) => integer.to_string(),
+        ValueRef::Real(real) => real.to_string(),
+        ValueRef::Text(text) | ValueRef::Blob(text) => String::from_utf8_lossy(text).into_owned(),
+    }
+}
+
+/// Quotes a CSV field if it contains a comma, quote or line break.
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Quotes a table or column name for use in a statement.
+fn quoted(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn create_database(path: &str) {
+        let connection = Connection::open(path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE messages (id INTEGER, created_at INTEGER, note TEXT);
+                 INSERT INTO messages VALUES (1530216070, 1530216070317, 'a, 1530216070');
+                 INSERT INTO messages VALUES (2, NULL, 'b');",
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn write_a_csv_report() {
+        let database = format!("/tmp/{:?}-messages.db", Instant::now());
+        create_database(&database);
+
+        let mut report = Vec::new();
+        convert_sqlite_table(
+            &database,
+            "messages",
+            &["created_at".to_string()],
+            SqliteOutput::Csv(&mut report),
+            &ReplacerOptions::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            "id,created_at,note\n\
+             1530216070,[2018-06-28 20:01:10.317 UTC],\"a, 1530216070\"\n\
+             2,,b\n",
+            String::from_utf8(report).unwrap()
+        );
+    }
+
+    #[test]
+    fn convert_a_copy_of_the_database() {
+        let database = format!("/tmp/{:?}-messages.db", Instant::now());
+        let copy = database.replace(".db", ".depoch.db");
+        create_database(&database);
+
+        convert_sqlite_table(
+            &database,
+            "messages",
+            &["created_at".to_string()],
+            SqliteOutput::Copy(&copy),
+            &ReplacerOptions::new(),
+        )
+        .unwrap();
+        let connection = Connection::open(&copy).unwrap();
+        let created_at: String = connection
+            .query_row(
+                "SELECT created_at FROM messages WHERE id = 1530216070",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!("[2018-06-28 20:01:10.317 UTC]", created_at);
+        assert!(convert_sqlite_table(
+            &database,
+            "messages",
+            &["sent_at".to_string()],
+            SqliteOutput::Copy(&copy),
+            &ReplacerOptions::new(),
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use chrono::prelude::*;
+use std::fmt;
+use std::time::Duration;
+
+/// A summary of the conversion of one input. Timestamps are only counted when
+/// converting epochs to datetimes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub timestamps_replaced: u64,
+    pub earliest: Option<DateTime<Utc>>,
+    pub latest: Option<DateTime<Utc>>,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+    pub elapsed: Duration,
+}
+
+impl Stats {
+    pub(crate) fn record_timestamp(&mut self, date_time: DateTime<Utc>) {
+        self.timestamps_replaced += 1;
+        if self.earliest.is_none_or(|earliest| date_time < earliest) {
+            self.earliest = Some(date_time);
+        }
+        if self.latest.is_none_or(|latest| date_time > latest) {
+            self.latest = Some(date_time);
+        }
+    }
+
+    /// Renders the summary as a single-line JSON object, labelled with `name`.
+    pub fn to_json(&self, name: &str) -> String {
+        format!(
+            "{{\"name\":{},\"timestamps_replaced\":{},\"earliest\":{},\"latest\":{},\"bytes_read\":{},\"bytes_written\":{},\"elapsed_seconds\":{:.3}}}",
+            json_string(name),
+            self.timestamps_replaced,
+            json_date_time(self.earliest),
+            json_date_time(self.latest),
+            self.bytes_read,
+            self.bytes_written,
+            self.elapsed.as_secs_f64()
+        )
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} timestamps replaced", self.timestamps_replaced)?;
+        if let (Some(earliest), Some(latest)) = (self.earliest, self.latest) {
+            write!(
+                f,
+                " from {} to {}",
+                earliest.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                latest.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+            )?;
+        }
+        write!(
+            f,
+            ", {} bytes read, {} bytes written in {:.3}s",
+            self.bytes_read,
+            self.bytes_written,
+            self.elapsed.as_secs_f64()
+        )
+    }
+}
+
+fn json_date_time(date_time: Option<DateTime<Utc>>) -> String {
+    match date_time {
+        Some(date_time) => json_string(&date_time.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+        None => "null".to_string(),
+    }
+}
+
+pub(crate) fn json_string(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn track_timestamp_range() {
+        let mut stats = Stats::default();
+        stats.record_timestamp(Utc.timestamp_opt(1530216070, 0).unwrap());
+        stats.record_timestamp(Utc.timestamp_opt(1530216000, 0).unwrap());
+        stats.record_timestamp(Utc.timestamp_opt(1530216010, 0).unwrap());
+        stats.bytes_read = 100;
+        stats.bytes_written = 180;
+
+        assert_eq!(
+            "3 timestamps replaced from 2018-06-28T20:00:00Z to 2018-06-28T20:01:10Z, 100 bytes read, 180 bytes written in 0.000s",
+            stats.to_string()
+        );
+    }
+
+    #[test]
+    fn render_json_summary() {
+        let stats = Stats {
+            bytes_read: 12,
+            ..Stats::default()
+        };
+
+        assert_eq!(
+            "{\"name\":\"a \\\"b\\\".log\",\"timestamps_replaced\":0,\"earliest\":null,\"latest\":null,\"bytes_read\":12,\"bytes_written\":0,\"elapsed_seconds\":0.000}",
+            stats.to_json("a \"b\".log")
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use crate::formatter::append_custom_format;
+use crate::journal::pass_through;
+use crate::json::string_end;
+use crate::{
+    append_epoch_timestamp, parse_epoch_value, record_timestamp, replace_epoch_timestamps_in_state,
+    ReplacerOptions, ReplacerState, ASCII_BACKSLASH, ASCII_CLOSE_BRACKET, ASCII_HYPHEN,
+    ASCII_OPEN_BRACKET, ASCII_QUOTE, ASCII_SPACE,
+};
+
+const ASCII_LESS_THAN: u8 = 60;
+const ASCII_GREATER_THAN: u8 = 62;
+/// TIMESTAMP, HOSTNAME, APP-NAME, PROCID and MSGID follow the PRI and VERSION.
+const HEADER_FIELDS_AFTER_VERSION: usize = 5;
+
+/// Converts the epoch values of an RFC 5424 syslog message that are SD-PARAM values,
+/// such as `eventTs="1530216070"`, or in its MSG. The PRI, VERSION and other header
+/// fields are written unchanged. Lines that are not RFC 5424 messages are treated as
+/// free-form text.
+pub(crate) fn replace_syslog_timestamps(
+    line: &[u8],
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let mut replaced = Vec::new();
+    let header_end = match header_end(line) {
+        Some(header_end) => header_end,
+        None => {
+            replaced.extend(replace_epoch_timestamps_in_state(line, true, options, state).data);
+            return replaced;
+        }
+    };
+    pass_through(&line[..header_end], state, &mut replaced);
+
+    let mut index = header_end;
+    if line.get(index) == Some(&ASCII_HYPHEN) {
+        pass_through(&line[index..index + 1], state, &mut replaced);
+        index += 1;
+    }
+    while line.get(index) == Some(&ASCII_OPEN_BRACKET) {
+        index = append_sd_element(line, index, options, state, &mut replaced);
+    }
+    replaced.extend(replace_epoch_timestamps_in_state(&line[index..], true, options, state).data);
+    replaced
+}
+
+/// Returns the index just past the header's MSGID and the space that follows it, if the
+/// line starts with an RFC 5424 header.
+fn header_end(line: &[u8]) -> Option<usize> {
+    if line.first() != Some(&ASCII_LESS_THAN) {
+        return None;
+    }
+    let pri_end = line.iter().position(|&byte| byte == ASCII_GREATER_THAN)?;
+    let version_end = line[pri_end + 1..]
+        .iter()
+        .position(|&byte| byte == ASCII_SPACE)?
+        + pri_end
+        + 1;
+    let is_number = |digits: &[u8]| !digits.is_empty() && digits.iter().all(u8::is_ascii_digit);
+    if !is_number(&line[1..pri_end]) || !is_number(&line[pri_end + 1..version_end]) {
+        return None;
+    }
+    let mut index = version_end + 1;
+    for _ in 0..HEADER_FIELDS_AFTER_VERSION {
+        let field_end = line[index..].iter().position(|&byte| byte == ASCII_SPACE)? + index;
+        if field_end == index {
+            return None;
+        }
+        index = field_end + 1;
+    }
+    Some(index)
+}
+
+/// Writes the SD-ELEMENT starting at `start`, converting its parameter values, and
+/// returns the index just past it.
+fn append_sd_element(
+    line: &[u8],
+    start: usize,
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+    append_buffer: &mut Vec<u8>,
+) -> usize {
+    let mut unchanged_start = start;
+    let mut index = start + 1;
+    while index < line.len() {
+        match line[index] {
+            ASCII_QUOTE => {
+                pass_through(&line[unchanged_start..index + 1], state, append_buffer);
+                let string_end = string_end(line, index);
+                let value_end = if line[string_end - 1] == ASCII_QUOTE && string_end - 1 > index {
+                    string_end - 1
+                } else {
+                    string_end
+                };
+                append_param_value(&line[index + 1..value_end], options, state, append_buffer);
+                unchanged_start = value_end;
+                index = string_end;
+            }
+            ASCII_CLOSE_BRACKET => {
+                index += 1;
+                break;
+            }
+            _ => index += 1,
+        }
+    }
+    pass_through(&line[unchanged_start..index], state, append_buffer);
+    index
+}
+
+fn append_param_value(
+    value: &[u8],
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+    append_buffer: &mut Vec<u8>,
+) {
+    let timestamp = match parse_epoch_value(value, options) {
+        Some(timestamp) => timestamp,
+        None => {
+            pass_through(value, state, append_buffer);
+            return;
+        }
+    };
+    record_timestamp(&timestamp, value, 0, options, state);
+    let mut rendered = Vec::new();
+    if !append_custom_format(&timestamp, value, options, &mut rendered) {
+        if options.annotate {
+            rendered.extend_from_slice(value);
+            rendered.push(ASCII_SPACE);
+        }
+        append_epoch_timestamp(&timestamp, options, state, &mut rendered);
+    }
+    for byte in rendered {
+        // these must be escaped within a PARAM-VALUE
+        if [ASCII_QUOTE, ASCII_BACKSLASH, ASCII_CLOSE_BRACKET].contains(&byte) {
+            append_buffer.push(ASCII_BACKSLASH);
+        }
+        append_buffer.push(byte);
+    }
+    if let Some(&byte) = value.last() {
+        state.preceding_byte = Some(byte);
+    }
+    state.offset += value.len() as u64;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{replace_epoch_timestamps_str_with_options, InputFormat};
+
+    fn replace(input: &str) -> String {
+        let options = ReplacerOptions::new().input_format(InputFormat::Syslog);
+        replace_epoch_timestamps_str_with_options(input, &options)
+    }
+
+    #[test]
+    fn convert_sd_params_and_message() {
+        assert_eq!(
+            "<165>1 2018-06-28T20:01:10Z host app 1530216070 ID47 [origin ip=\"10.0.0.1\" eventTs=\"[2018-06-28 20:01:10 UTC\\]\"][x@1 n=\"1\"] started at [2018-06-28 20:01:10 UTC]\n",
+            replace(
+                "<165>1 2018-06-28T20:01:10Z host app 1530216070 ID47 [origin ip=\"10.0.0.1\" eventTs=\"1530216070\"][x@1 n=\"1\"] started at 1530216070\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use crate::formatter::TimestampFormatter;
+use chrono::prelude::*;
+
+const SECONDS_PER_MINUTE: i64 = 60;
+const SECONDS_PER_HOUR: i64 = 3600;
+const SECONDS_PER_DAY: i64 = 86_400;
+const SECONDS_PER_YEAR: i64 = 365 * SECONDS_PER_DAY;
+/// The units of a relative time, largest first.
+const RELATIVE_UNITS: &[(i64, &str)] = &[
+    (SECONDS_PER_YEAR, "year"),
+    (SECONDS_PER_DAY, "day"),
+    (SECONDS_PER_HOUR, "hour"),
+    (SECONDS_PER_MINUTE, "minute"),
+    (1, "second"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Placeholder {
+    Original,
+    Iso,
+    EpochSeconds,
+    EpochMilliseconds,
+    EpochMicroseconds,
+    EpochNanoseconds,
+    Relative,
+}
+
+#[derive(Debug)]
+enum TemplatePart {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// Renders converted timestamps from a template of text and placeholders: `{orig}`,
+/// `{iso}`, `{epoch}`, `{epoch_ms}`, `{epoch_us}`, `{epoch_ns}` and `{relative}`.
+/// Literal braces are written as `{{` and `}}`.
+#[derive(Debug)]
+pub(crate) struct Template {
+    parts: Vec<TemplatePart>,
+}
+
+impl Template {
+    pub(crate) fn parse(template: &str) -> Result<Template, String> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+        while let Some(next) = chars.next() {
+            match next {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let close = rest
+                        .find('}')
+                        .ok_or_else(|| format!("Unclosed placeholder in template: {}", template))?;
+                    let placeholder = placeholder(&rest[..close])?;
+                    chars = rest[close + 1..].chars();
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(TemplatePart::Placeholder(placeholder));
+                }
+                '}' => return Err(format!("Unmatched '}}' in template: {}", template)),
+                _ => literal.push(next),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(literal));
+        }
+        Ok(Template { parts })
+    }
+}
+
+impl TimestampFormatter for Template {
+    fn format(&self, date_time: &DateTime<Utc>, original: &[u8]) -> Vec<u8> {
+        let mut rendered = Vec::new();
+        for part in &self.parts {
+            match part {
+                TemplatePart::Literal(literal) => rendered.extend_from_slice(literal.as_bytes()),
+                TemplatePart::Placeholder(placeholder) => {
+                    rendered.extend(render_placeholder(*placeholder, date_time, original))
+                }
+            }
+        }
+        rendered
+    }
+}
+
+fn placeholder(name: &str) -> Result<Placeholder, String> {
+    match name {
+        "orig" => Ok(Placeholder::Original),
+        "iso" => Ok(Placeholder::Iso),
+        "epoch" => Ok(Placeholder::EpochSeconds),
+        "epoch_ms" => Ok(Placeholder::EpochMilliseconds),
+        "epoch_us" => Ok(Placeholder::EpochMicroseconds),
+        "epoch_ns" => Ok(Placeholder::EpochNanoseconds),
+        "relative" => Ok(Placeholder::Relative),
+        _ => Err(format!("Unknown template placeholder: {{{}}}", name)),
+    }
+}
+
+fn render_placeholder(
+    placeholder: Placeholder,
+    date_time: &DateTime<Utc>,
+    original: &[u8],
+) -> Vec<u8> {
+    let rendered = match placeholder {
+        Placeholder::Original => return original.to_vec(),
+        Placeholder::Iso => date_time.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+        Placeholder::EpochSeconds => date_time.timestamp().to_string(),
+        Placeholder::EpochMilliseconds => date_time.timestamp_millis().to_string(),
+        Placeholder::EpochMicroseconds => date_time.timestamp_micros().to_string(),
+        Placeholder::EpochNanoseconds => match date_time.timestamp_nanos_opt() {
+            Some(nanos) => nanos.to_string(),
+            None => format!("{}{:09}", date_time.timestamp(), date_time.nanosecond()),
+        },
+        Placeholder::Relative => relative_to_now(date_time),
+    };
+    rendered.into_bytes()
+}
+
+/// Describes `date_time` relative to now in its largest whole unit, such as
+/// `3 hours ago` or `in 2 days`.
+fn relative_to_now(date_time: &DateTime<Utc>) -> String {
+    let seconds = date_time.signed_duration_since(Utc::now()).num_seconds();
+    let magnitude = seconds.abs();
+    let (length, unit) = RELATIVE_UNITS
+        .iter()
+        .find(|(length, _)| magnitude >= *length)
+        .copied()
+        .unwrap_or((1, "second"));
+    let count = magnitude / length;
+    let plural = if count == 1 { "" } else { "s" };
+    if seconds < 0 {
+        format!("{} {}{} ago", count, unit, plural)
+    } else {
+        format!("in {} {}{}", count, unit, plural)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{replace_epoch_timestamps_str_with_options, ReplacerOptions};
+
+    fn replace(input: &str, template: &str) -> String {
+        let options = ReplacerOptions::new().template(template).unwrap();
+        replace_epoch_timestamps_str_with_options(input, &options)
+    }
+
+    #[test]
+    fn render_placeholders() {
+        assert_eq!(
+            "at 1530216070317 (2018-06-28T20:01:10.317Z)",
+            replace("at 1530216070317", "{orig} ({iso})")
+        );
+        assert_eq!(
+            "<time datetime='2018-06-28T20:01:10Z'>{1530216070000}</time>",
+            replace("1530216070", "<time datetime='{iso}'>{{{epoch_ms}}}</time>")
+        );
+        let an_hour_ago = (Utc::now().timestamp() - 5400).to_string();
+        assert_eq!("1 hour ago", replace(&an_hour_ago, "{relative}"));
+    }
+
+    #[test]
+    fn reject_invalid_templates() {
+        assert!(Template::parse("{iso").is_err());
+        assert!(Template::parse("{date}").is_err());
+        assert!(Template::parse("iso}").is_err());
+    }
+}
@@ -0,0 +1,49 @@
+/// The replacement character, U+FFFD, encoded in UTF-8.
+const REPLACEMENT_CHARACTER: &str = "\u{FFFD}";
+
+/// Replaces invalid UTF-8 in a stream of chunks, keeping a sequence that is incomplete
+/// at the end of a chunk until the next chunk shows whether it is valid.
+#[derive(Default)]
+pub(crate) struct LossyDecoder {
+    incomplete: Vec<u8>,
+}
+
+impl LossyDecoder {
+    /// Returns `chunk` with each invalid sequence replaced by U+FFFD, as
+    /// `String::from_utf8_lossy` does. Unless `end_of_input` is set, an incomplete
+    /// sequence at the end is kept for the next chunk.
+    pub(crate) fn decode(&mut self, chunk: &[u8], end_of_input: bool) -> Vec<u8> {
+        let mut input = std::mem::take(&mut self.incomplete);
+        input.extend_from_slice(chunk);
+        let mut decoded = Vec::with_capacity(input.len());
+        let mut remaining = &input[..];
+        loop {
+            match std::str::from_utf8(remaining) {
+                Ok(valid) => {
+                    decoded.extend_from_slice(valid.as_bytes());
+                    break;
+                }
+                Err(error) => {
+                    let (valid, invalid) = remaining.split_at(error.valid_up_to());
+                    decoded.extend_from_slice(valid);
+                    match error.error_len() {
+                        Some(length) => {
+                            decoded.extend_from_slice(REPLACEMENT_CHARACTER.as_bytes());
+                            remaining = &invalid[length..];
+                        }
+                        // the sequence may be completed by the next chunk
+                        None if !end_of_input => {
+                            self.incomplete = invalid.to_vec();
+                            break;
+                        }
+                        None => {
+                            decoded.extend_from_slice(REPLACEMENT_CHARACTER.as_bytes());
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        decoded
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{OutputStyle, ReplacerOptions};
+use wasm_bindgen::prelude::*;
+
+/// Configuration for `replace`, built up from JavaScript by calling its setters. The
+/// browser bindings only use the in-memory replacer, never the filesystem.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Options {
+    options: ReplacerOptions,
+}
+
+#[wasm_bindgen]
+impl Options {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Options {
+        Options::default()
+    }
+
+    /// Renders timestamps with a chrono strftime pattern.
+    pub fn format(&mut self, format: &str) -> Result<(), String> {
+        self.options = std::mem::take(&mut self.options).format(format)?;
+        Ok(())
+    }
+
+    /// Renders timestamps as `default` or `rfc3339`.
+    pub fn style(&mut self, style: &str) -> Result<(), String> {
+        let style = match style {
+            "default" => OutputStyle::Default,
+            "rfc3339" => OutputStyle::Rfc3339,
+            other => return Err(format!("Unknown output style: {}", other)),
+        };
+        self.options = std::mem::take(&mut self.options).style(style);
+        Ok(())
+    }
+
+    /// Renders timestamps in an IANA timezone, such as `Europe/London`.
+    pub fn timezone(&mut self, name: &str) -> Result<(), String> {
+        self.options = std::mem::take(&mut self.options).timezone(name)?;
+        Ok(())
+    }
+
+    pub fn annotate(&mut self, annotate: bool) {
+        self.options = std::mem::take(&mut self.options).annotate(annotate);
+    }
+
+    pub fn prefix(&mut self, prefix: &str) {
+        self.options = std::mem::take(&mut self.options).prefix(prefix);
+    }
+
+    pub fn suffix(&mut self, suffix: &str) {
+        self.options = std::mem::take(&mut self.options).suffix(suffix);
+    }
+}
+
+/// Returns `input` with its timestamps converted as configured by `options`.
+#[wasm_bindgen]
+pub fn replace(input: &str, options: &Options) -> String {
+    let replacer = options.options.clone().build();
+    String::from_utf8_lossy(&replacer.replace(input.as_bytes())).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replace_with_configured_options() {
+        let mut options = Options::new();
+        options.style("rfc3339").unwrap();
+        options.timezone("Europe/London").unwrap();
+
+        assert_eq!(
+            "at [2018-06-28T21:01:10.317+01:00]",
+            replace("at 1530216070317", &options)
+        );
+        assert!(options.style("iso").is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{ReplacerOptions, Stats, StreamingReplacer};
+use std::io::{self, Write};
+
+/// Converts epoch timestamps in the data written through it to `inner`. A digit run at
+/// the end of a write is held back until a later write shows whether it continues;
+/// `finish` resolves it at the end of the data. Dropping the writer finishes it,
+/// ignoring any error.
+pub struct DepochWriter<W: Write> {
+    inner: Option<W>,
+    replacer: StreamingReplacer,
+}
+
+impl<W: Write> DepochWriter<W> {
+    pub fn new(inner: W) -> DepochWriter<W> {
+        DepochWriter::with_options(inner, ReplacerOptions::new())
+    }
+
+    pub fn with_options(inner: W, options: ReplacerOptions) -> DepochWriter<W> {
+        DepochWriter {
+            inner: Some(inner),
+            replacer: StreamingReplacer::with_options(options),
+        }
+    }
+
+    /// The totals for the data written so far; the elapsed time is not set.
+    pub fn stats(&self) -> &Stats {
+        self.replacer.stats()
+    }
+
+    /// Writes any held-back data and flushes `inner`, returning it.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.finish_output()?;
+        Ok(self.inner.take().expect("Writer already finished"))
+    }
+
+    fn finish_output(&mut self) -> io::Result<()> {
+        let converted = self.replacer.finish();
+        let inner = self.inner.as_mut().expect("Writer already finished");
+        inner.write_all(&converted)?;
+        inner.flush()
+    }
+}
+
+impl<W: Write> Write for DepochWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let converted = self.replacer.push_chunk(buf);
+        self.inner
+            .as_mut()
+            .expect("Writer already finished")
+            .write_all(&converted)?;
+        Ok(buf.len())
+    }
+
+    /// Flushes everything that can be converted so far; a trailing digit run is still
+    /// held back, as it may continue in the next write.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner
+            .as_mut()
+            .expect("Writer already finished")
+            .flush()
+    }
+}
+
+impl<W: Write> Drop for DepochWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.finish_output();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_trailing_digits_on_finish() {
+        let mut writer = DepochWriter::new(Vec::new());
+        writer.write_all(b"a 15302").expect("Failed to write");
+        writer
+            .write_all(b"16070317 b 1530216070")
+            .expect("Failed to write");
+        let output = writer.finish().expect("Failed to finish");
+
+        assert_eq!(
+            "a [2018-06-28 20:01:10.317 UTC] b [2018-06-28 20:01:10 UTC]",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn finish_when_dropped() {
+        let mut output = Vec::new();
+        {
+            let mut writer = DepochWriter::new(&mut output);
+            writer.write_all(b"1530216070").expect("Failed to write");
+            writer.flush().expect("Failed to flush");
+        }
+
+        assert_eq!(b"[2018-06-28 20:01:10 UTC]".to_vec(), output);
+    }
+}
@@ -0,0 +1,103 @@
+use crate::csv::line_content_end;
+use crate::formatter::append_custom_format;
+use crate::{
+    append_epoch_timestamp, parse_epoch_value, record_timestamp, ReplacerOptions, ReplacerState,
+    ASCII_HASH, ASCII_SPACE, ASCII_TAB,
+};
+
+/// The header line that declares the type of each column.
+const TYPES_HEADER: &str = "#types";
+/// The type of columns holding fractional epoch seconds; `interval` columns are
+/// durations and are never converted.
+const TIME_TYPE: &str = "time";
+
+/// Progress through a Zeek log, carried from one line to the next.
+#[derive(Default)]
+pub(crate) struct ZeekState {
+    /// The zero-based indices of the columns whose type is `time`.
+    time_columns: Vec<usize>,
+}
+
+/// Converts the `time` columns of a line of a Zeek (formerly Bro) TSV log, such as
+/// `ts`, as declared by the most recent `#types` header. Header lines and every other
+/// column are written unchanged, as are the tabs between columns.
+pub(crate) fn replace_zeek_timestamps(
+    line: &[u8],
+    options: &ReplacerOptions,
+    state: &mut ReplacerState,
+) -> Vec<u8> {
+    let content_end = line_content_end(line);
+    let mut replaced = Vec::new();
+    if line.first() == Some(&ASCII_HASH) {
+        let mut fields = line[..content_end].split(|&byte| byte == ASCII_TAB);
+        if fields.next() == Some(TYPES_HEADER.as_bytes()) {
+            state.zeek.time_columns = fields
+                .enumerate()
+                .filter(|&(_, column_type)| column_type == TIME_TYPE.as_bytes())
+                .map(|(column, _)| column)
+                .collect();
+        }
+        replaced.extend_from_slice(line);
+    } else {
+        let mut index = 0;
+        let mut start = 0;
+        for (column, field) in line[..content_end]
+            .split(|&byte| byte == ASCII_TAB)
+            .enumerate()
+        {
+            let end = start + field.len();
+            let timestamp = if state.zeek.time_columns.contains(&column) {
+                parse_epoch_value(field, options)
+            } else {
+                None
+            };
+            if let Some(timestamp) = timestamp {
+                replaced.extend_from_slice(&line[index..start]);
+                record_timestamp(&timestamp, field, start, options, state);
+                if !append_custom_format(&timestamp, field, options, &mut replaced) {
+                    if options.annotate {
+                        replaced.extend_from_slice(field);
+                        replaced.push(ASCII_SPACE);
+                    }
+                    append_epoch_timestamp(&timestamp, options, state, &mut replaced);
+                }
+                index = end;
+            }
+            start = end + 1;
+        }
+        replaced.extend_from_slice(&line[index..]);
+    }
+
+    if let Some(&byte) = line.last() {
+        state.preceding_byte = Some(byte);
+    }
+    state.offset += line.len() as u64;
+    replaced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{replace_epoch_timestamps_str_with_options, InputFormat};
+
+    fn replace(input: &str) -> String {
+        let options = ReplacerOptions::new().input_format(InputFormat::Zeek);
+        replace_epoch_timestamps_str_with_options(input, &options)
+    }
+
+    #[test]
+    fn convert_only_time_columns() {
+        assert_eq!(
+            "#separator \\x09\n\
+             #fields\tts\tuid\tduration\torig_bytes\tlast_seen\n\
+             #types\ttime\tstring\tinterval\tcount\ttime\n\
+             [2018-06-28 20:01:10.317123 UTC]\tC1530216070\t1530216070.5\t1530216070\t-\n",
+            replace(
+                "#separator \\x09\n\
+                 #fields\tts\tuid\tduration\torig_bytes\tlast_seen\n\
+                 #types\ttime\tstring\tinterval\tcount\ttime\n\
+                 1530216070.317123\tC1530216070\t1530216070.5\t1530216070\t-\n"
+            )
+        );
+    }
+}
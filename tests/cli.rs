@@ -0,0 +1,37 @@
+use std::process::Command;
+use std::time::Instant;
+
+fn nail() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_nail"))
+}
+
+#[test]
+fn convert_a_file_given_without_a_command() {
+    let file_name = format!("/tmp/{:?}-cli.log", Instant::now());
+    std::fs::write(&file_name, "a 1530216070\n").unwrap();
+
+    let status = nail().arg(&file_name).status().unwrap();
+    assert!(status.success());
+    assert_eq!(
+        "a [2018-06-28 20:01:10 UTC]\n",
+        std::fs::read_to_string(file_name.clone() + ".depoch").unwrap()
+    );
+
+    // output that is not a terminal is never paged
+    let output = nail().arg(&file_name).arg("--pager").output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        "a [2018-06-28 20:01:10 UTC]\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+#[test]
+fn reject_an_unknown_command() {
+    let output = nail().arg("no-such-command").output().unwrap();
+    assert!(!output.status.success());
+    assert_eq!(
+        "Unknown command or file: no-such-command\n",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}